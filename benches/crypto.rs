@@ -0,0 +1,140 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use talk::crypto::primitives::{
+    channel::{self, Sender as ChannelSender},
+    exchange::KeyPair as ExchangeKeyPair,
+    hash,
+    multi::{KeyPair as MultiKeyPair, Signature as MultiSignature},
+    sign::{KeyPair as SignKeyPair, Signature as SignSignature},
+};
+
+const BATCH_SIZES: [usize; 4] = [1, 16, 128, 1024];
+const SIGNER_COUNTS: [usize; 3] = [4, 64, 256];
+const MESSAGE_SIZES: [usize; 4] = [64, 1024, 64 * 1024, 1024 * 1024];
+
+fn sign(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sign");
+
+    let keypair = SignKeyPair::random();
+    let signature = keypair.sign_raw(&0u64).unwrap();
+
+    group.bench_function("sign", |b| b.iter(|| keypair.sign_raw(&0u64).unwrap()));
+
+    group.bench_function("verify", |b| {
+        b.iter(|| signature.verify_raw(keypair.public(), &0u64).unwrap())
+    });
+
+    for size in BATCH_SIZES {
+        let keypairs = (0..size).map(|_| SignKeyPair::random()).collect::<Vec<_>>();
+        let messages = (0..size as u64).collect::<Vec<_>>();
+
+        let signatures = keypairs
+            .iter()
+            .zip(messages.iter())
+            .map(|(keypair, message)| keypair.sign_raw(message).unwrap())
+            .collect::<Vec<_>>();
+
+        let public_keys = keypairs.iter().map(SignKeyPair::public).collect::<Vec<_>>();
+
+        group.throughput(Throughput::Elements(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("batch_verify", size), &size, |b, _| {
+            b.iter(|| {
+                SignSignature::batch_verify_raw(
+                    public_keys.iter().copied(),
+                    messages.iter(),
+                    signatures.iter().copied(),
+                )
+                .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn multi(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multi");
+
+    for count in SIGNER_COUNTS {
+        let keypairs = (0..count)
+            .map(|_| MultiKeyPair::random())
+            .collect::<Vec<_>>();
+
+        let signatures = keypairs
+            .iter()
+            .map(|keypair| keypair.sign_raw(&0u64).unwrap())
+            .collect::<Vec<_>>();
+
+        let public_keys = keypairs
+            .iter()
+            .map(MultiKeyPair::public)
+            .collect::<Vec<_>>();
+
+        let aggregate = MultiSignature::aggregate(signatures.iter().copied()).unwrap();
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("aggregate", count), &count, |b, _| {
+            b.iter(|| MultiSignature::aggregate(signatures.iter().copied()).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("verify_raw", count), &count, |b, _| {
+            b.iter(|| {
+                aggregate
+                    .verify_raw(public_keys.iter().copied(), &0u64)
+                    .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn sender() -> ChannelSender {
+    let alice = ExchangeKeyPair::random();
+    let bob = ExchangeKeyPair::random();
+
+    let (shared_key, role) = alice.exchange(bob.public());
+    channel::channel(shared_key, role).0
+}
+
+fn channel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("channel");
+
+    for size in MESSAGE_SIZES {
+        let message = vec![0u8; size];
+        let mut sender = sender();
+
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("encrypt", size), &size, |b, _| {
+            b.iter(|| sender.encrypt(&message).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("authenticate", size), &size, |b, _| {
+            b.iter(|| sender.authenticate(&message).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash");
+
+    for size in MESSAGE_SIZES {
+        let message = vec![0u8; size];
+
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("hash", size), &size, |b, _| {
+            b.iter(|| hash::hash(&message).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, sign, multi, channel, hash);
+criterion_main!(benches);
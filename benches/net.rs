@@ -0,0 +1,188 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use std::time::Instant;
+
+use talk::{
+    net::{test::System as NetSystem, SessionConnector, SessionListener},
+    unicast::test::UnicastSystem,
+};
+
+use tokio::runtime::Runtime;
+
+const PAYLOAD_SIZES: [usize; 3] = [1024, 64 * 1024, 1024 * 1024];
+const WINDOW: usize = 128;
+
+fn secure_connection(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("secure_connection");
+
+    let mut pair = runtime.block_on(async {
+        let mut system = NetSystem::setup(2).await;
+        system.connect(0, 1).await
+    });
+
+    group.bench_function("latency", |b| {
+        b.iter_custom(|iterations| {
+            runtime.block_on(async {
+                let start = Instant::now();
+
+                for _ in 0..iterations {
+                    pair.transmit(&0u64).await.unwrap();
+                }
+
+                start.elapsed()
+            })
+        })
+    });
+
+    for size in PAYLOAD_SIZES {
+        let payload = vec![0u8; size];
+
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("throughput", size), &size, |b, _| {
+            b.iter_custom(|iterations| {
+                runtime.block_on(async {
+                    let start = Instant::now();
+
+                    for _ in 0..iterations {
+                        pair.transmit(&payload).await.unwrap();
+                    }
+
+                    start.elapsed()
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn session(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("session");
+
+    let (remote, connector) = runtime.block_on(async {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(2).await;
+
+        let connector = SessionConnector::new(connectors.remove(0));
+        let mut listener = SessionListener::new(listeners.remove(1));
+
+        // Echo every request, then return the session to the pool
+        tokio::spawn(async move {
+            loop {
+                let (_, mut session) = listener.accept().await;
+
+                tokio::spawn(async move {
+                    if let Ok(request) = session.receive::<u64>().await {
+                        if session.send(&request).await.is_ok() {
+                            session.end();
+                        }
+                    }
+                });
+            }
+        });
+
+        (keys[1], connector)
+    });
+
+    for pooled in [true, false] {
+        let name = if pooled { "pooled" } else { "cold" };
+
+        group.bench_function(name, |b| {
+            b.iter_custom(|iterations| {
+                runtime.block_on(async {
+                    let start = Instant::now();
+
+                    for _ in 0..iterations {
+                        let mut session = connector.connect(remote).await.unwrap();
+
+                        session.send(&0u64).await.unwrap();
+                        session.receive::<u64>().await.unwrap();
+
+                        // Dropping `session` (rather than `end`ing it) forces
+                        // the next `connect` to establish a new connection
+                        if pooled {
+                            session.end();
+                        }
+                    }
+
+                    start.elapsed()
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn unicast(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("unicast");
+
+    let (remote, sender) = runtime.block_on(async {
+        let UnicastSystem {
+            keys,
+            mut senders,
+            mut receivers,
+        } = UnicastSystem::<u64>::setup(2).await;
+
+        let mut receiver = receivers.remove(1);
+
+        tokio::spawn(async move {
+            loop {
+                let (_, _, acknowledger) = receiver.receive().await;
+                acknowledger.strong();
+            }
+        });
+
+        (keys[1], senders.remove(0))
+    });
+
+    group.bench_function("latency", |b| {
+        b.iter_custom(|iterations| {
+            runtime.block_on(async {
+                let start = Instant::now();
+
+                for _ in 0..iterations {
+                    sender.send(remote, 0).await.unwrap();
+                }
+
+                start.elapsed()
+            })
+        })
+    });
+
+    group.throughput(Throughput::Elements(WINDOW as u64));
+
+    group.bench_function(BenchmarkId::new("throughput", WINDOW), |b| {
+        b.iter_custom(|iterations| {
+            runtime.block_on(async {
+                let start = Instant::now();
+
+                for _ in 0..iterations {
+                    (0..WINDOW as u64)
+                        .map(|message| sender.send(remote, message))
+                        .collect::<FuturesUnordered<_>>()
+                        .for_each(|result| async move {
+                            result.unwrap();
+                        })
+                        .await;
+                }
+
+                start.elapsed()
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, secure_connection, session, unicast);
+criterion_main!(benches);
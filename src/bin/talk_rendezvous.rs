@@ -0,0 +1,310 @@
+//! Standalone rendezvous server.
+//!
+//! Settings are read from command-line flags and, optionally, from a TOML
+//! file passed with `--config`. Flags take precedence over the file:
+//!
+//! ```toml
+//! address = "0.0.0.0:9000"
+//! shard_sizes = [4, 4]
+//! persistence = "/var/lib/talk/rendezvous.db"
+//! log_level = "info"
+//! keychain = "/var/lib/talk/rendezvous.seed"
+//! operator = "/etc/talk/operator.keycard"
+//! sync_interval = 1000
+//!
+//! [[peers]]
+//! address = "10.0.0.2:9000"
+//! keycard = "/etc/talk/replica.keycard"
+//! ```
+//!
+//! `keychain` is a file holding a 32-byte secret seed, created if missing:
+//! the server's `KeyCard` is written alongside it (with a `.keycard`
+//! extension), to be distributed to its peers. `operator` and each peer's
+//! `keycard` are files holding a bincode-serialized `KeyCard`. On the command
+//! line, peers are passed as `--peers <address>=<keycard>,...`, and
+//! `sync_interval` is in milliseconds.
+//!
+//! Replicas must all list each other as peers: the replica with the lowest
+//! `Identity` acts as primary, and is the only one to execute operator
+//! commands and publications to a shard. Other replicas refer clients to it.
+//!
+//! On SIGINT or SIGTERM (or Ctrl-C, on non-Unix platforms) the server stops
+//! serving and, if a persistence path is set, persists its database before exiting.
+
+use log::LevelFilter;
+
+use serde::Deserialize;
+
+use std::{
+    convert::TryFrom,
+    fmt::Display,
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    time::Duration,
+};
+
+use structopt::StructOpt;
+
+use talk::{
+    crypto::{KeyCard, KeyChain},
+    link::rendezvous::{Server, ServerSettings},
+};
+
+use tokio::signal;
+
+#[cfg(unix)]
+use tokio::signal::unix::{self as unix_signal, SignalKind};
+
+#[derive(StructOpt)]
+#[structopt(name = "talk-rendezvous", about = "Runs a rendezvous server")]
+struct Options {
+    /// Path to a TOML configuration file
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Address to listen on
+    #[structopt(long)]
+    address: Option<SocketAddr>,
+
+    /// Comma-separated sizes of the initial shards
+    #[structopt(long, use_delimiter = true)]
+    shard_sizes: Option<Vec<usize>>,
+
+    /// Path to persist the database to
+    #[structopt(long, parse(from_os_str))]
+    persistence: Option<PathBuf>,
+
+    /// Log verbosity (off, error, warn, info, debug or trace)
+    #[structopt(long)]
+    log_level: Option<LevelFilter>,
+
+    /// Path to the server's secret seed (created if missing)
+    #[structopt(long, parse(from_os_str))]
+    keychain: Option<PathBuf>,
+
+    /// Comma-separated peers to synchronize with, each as `<address>=<keycard path>`
+    #[structopt(long, use_delimiter = true)]
+    peers: Option<Vec<Peer>>,
+
+    /// Interval between synchronizations with peers, in milliseconds
+    #[structopt(long)]
+    sync_interval: Option<u64>,
+
+    /// Path to the `KeyCard` of the operator allowed to issue commands
+    #[structopt(long, parse(from_os_str))]
+    operator: Option<PathBuf>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    address: Option<SocketAddr>,
+    shard_sizes: Option<Vec<usize>>,
+    persistence: Option<PathBuf>,
+    log_level: Option<String>,
+    keychain: Option<PathBuf>,
+    peers: Option<Vec<Peer>>,
+    sync_interval: Option<u64>,
+    operator: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Peer {
+    address: SocketAddr,
+    keycard: PathBuf,
+}
+
+impl FromStr for Peer {
+    type Err = String;
+
+    fn from_str(peer: &str) -> Result<Self, Self::Err> {
+        let (address, keycard) = peer
+            .split_once('=')
+            .ok_or_else(|| format!("expected `<address>=<keycard path>`, found `{}`", peer))?;
+
+        let address = address.parse().map_err(|error| format!("{}", error))?;
+
+        Ok(Peer {
+            address,
+            keycard: keycard.into(),
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+
+    let config = match &options.config {
+        Some(path) => load_config(path),
+        None => Config::default(),
+    };
+
+    let log_level = match (options.log_level, config.log_level) {
+        (Some(level), _) => level,
+        (None, Some(level)) => level
+            .parse()
+            .unwrap_or_else(|error| exit("Invalid `log_level` in configuration", error)),
+        (None, None) => LevelFilter::Info,
+    };
+
+    env_logger::Builder::new().filter_level(log_level).init();
+
+    let address = options.address.or(config.address).unwrap_or_else(|| {
+        exit(
+            "Missing listen address",
+            "pass `--address` or set `address`",
+        )
+    });
+
+    let defaults = ServerSettings::default();
+
+    let keychain = match options.keychain.or(config.keychain) {
+        Some(path) => load_keychain(&path),
+        None => {
+            log::warn!("No `keychain` set: using a random, ephemeral identity");
+            defaults.keychain.clone()
+        }
+    };
+
+    log::info!("Identity: {:?}", keychain.keycard().identity());
+
+    let peers = options
+        .peers
+        .or(config.peers)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|peer| (peer.address, load_keycard(&peer.keycard)))
+        .collect();
+
+    let settings = ServerSettings {
+        shard_sizes: options
+            .shard_sizes
+            .or(config.shard_sizes)
+            .unwrap_or(defaults.shard_sizes),
+        keychain,
+        peers,
+        sync_interval: options
+            .sync_interval
+            .or(config.sync_interval)
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.sync_interval),
+        operator: options
+            .operator
+            .or(config.operator)
+            .map(|path| load_keycard(&path)),
+        persistence: options.persistence.or(config.persistence),
+        ..defaults
+    };
+
+    let server = Server::new(address, settings)
+        .await
+        .unwrap_or_else(|error| exit("Failed to start server", error.top()));
+
+    log::info!("Listening on {}", address);
+
+    wait_for_shutdown().await;
+
+    log::info!("Shutting down");
+
+    if let Err(error) = server.shutdown().await {
+        exit("Failed to shut down gracefully", error.top());
+    }
+}
+
+fn load_config(path: &Path) -> Config {
+    let config = fs::read_to_string(path)
+        .unwrap_or_else(|error| exit("Failed to read configuration file", error));
+
+    toml::from_str(config.as_str())
+        .unwrap_or_else(|error| exit("Failed to parse configuration file", error))
+}
+
+// Reads the secret seed at `path`, generating (and saving) a random one if
+// `path` does not exist. The corresponding `KeyCard` is (re-)written next to it
+fn load_keychain(path: &Path) -> KeyChain {
+    let seed = match fs::read(path) {
+        Ok(seed) => <[u8; 32]>::try_from(seed.as_slice())
+            .unwrap_or_else(|_| exit("Malformed keychain seed", "expected 32 bytes")),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            let seed = rand::random::<[u8; 32]>();
+
+            write_secret(path, &seed)
+                .unwrap_or_else(|error| exit("Failed to write keychain seed", error));
+
+            log::info!("Generated keychain seed at {}", path.display());
+            seed
+        }
+        Err(error) => exit("Failed to read keychain seed", error),
+    };
+
+    let keychain = KeyChain::from_seed(seed);
+
+    let keycard = bincode::serialize(&keychain.keycard())
+        .unwrap_or_else(|error| exit("Failed to serialize keycard", error));
+
+    fs::write(path.with_extension("keycard"), keycard)
+        .unwrap_or_else(|error| exit("Failed to write keycard", error));
+
+    keychain
+}
+
+#[cfg(unix)]
+fn write_secret(path: &Path, secret: &[u8]) -> io::Result<()> {
+    use std::{fs::OpenOptions, io::Write, os::unix::fs::OpenOptionsExt};
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(secret)
+}
+
+#[cfg(not(unix))]
+fn write_secret(path: &Path, secret: &[u8]) -> io::Result<()> {
+    fs::write(path, secret)
+}
+
+fn load_keycard(path: &Path) -> KeyCard {
+    let keycard = fs::read(path).unwrap_or_else(|error| {
+        exit(
+            format!("Failed to read keycard {}", path.display()).as_str(),
+            error,
+        )
+    });
+
+    bincode::deserialize(keycard.as_slice()).unwrap_or_else(|error| {
+        exit(
+            format!("Failed to parse keycard {}", path.display()).as_str(),
+            error,
+        )
+    })
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown() {
+    let mut terminate = unix_signal::signal(SignalKind::terminate())
+        .unwrap_or_else(|error| exit("Failed to install SIGTERM handler", error));
+
+    tokio::select! {
+        _ = signal::ctrl_c() => {},
+        _ = terminate.recv() => {},
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown() {
+    signal::ctrl_c()
+        .await
+        .unwrap_or_else(|error| exit("Failed to install Ctrl-C handler", error));
+}
+
+fn exit<E: Display>(context: &str, error: E) -> ! {
+    eprintln!("{}: {}", context, error);
+    process::exit(1)
+}
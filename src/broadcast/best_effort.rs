@@ -2,21 +2,34 @@ use crate::{
     broadcast::BestEffortSettings,
     crypto::Identity,
     sync::fuse::{Fuse, Relay},
-    unicast::{Message, Sender},
+    time,
+    unicast::{Acknowledgement, Message, PushHandle, Sender},
 };
 
-use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use futures::stream::{self, Stream, StreamExt};
 
-use std::pin::Pin;
+use std::{collections::HashMap, future::Future, pin::Pin, time::Duration};
 
 use tokio::task::JoinHandle;
 
+/// Pushes a message to a set of remotes, tracking the highest `Acknowledgement`
+/// received from each of them. Per-recipient timeouts and attempt limits are
+/// set by `BestEffortSettings::push_settings`.
 pub struct BestEffort {
-    stream: Pin<Box<dyn Stream<Item = Identity> + Send + Sync>>,
+    stream: Pin<Box<dyn Stream<Item = Event> + Send + Sync>>,
+    acknowledgements: HashMap<Identity, Acknowledgement>,
     completed: Vec<Identity>,
 }
 
+enum Event {
+    Acknowledged(Identity, Acknowledgement),
+    Completed(Identity),
+}
+
 impl BestEffort {
+    /// Pushes start once the returned `BestEffort` is first awaited (e.g., by
+    /// `until` or `complete`). They are stopped by `cancel`, or by dropping the
+    /// `BestEffort`.
     pub fn new<M, R>(
         sender: Sender<M>,
         remotes: R,
@@ -30,6 +43,8 @@ impl BestEffort {
         BestEffort::setup(sender, remotes, message, None, settings)
     }
 
+    /// Pushes `brief`, then `expanded` to every remote that acknowledges `Expand`.
+    /// For large payloads, see `PayloadStore::broadcast` for a pull-based alternative.
     pub fn brief<M, R>(
         sender: Sender<M>,
         remotes: R,
@@ -55,7 +70,7 @@ impl BestEffort {
         M: Message + Clone,
         R: IntoIterator<Item = Identity>,
     {
-        let unordered = remotes
+        let streams = remotes
             .into_iter()
             .map(|remote| {
                 let sender = sender.clone();
@@ -63,39 +78,124 @@ impl BestEffort {
                 let fallback = fallback.clone();
                 let settings = settings.clone();
 
-                async move {
+                let start = async move {
                     if let Some(fallback) = fallback {
-                        sender
-                            .push_brief(remote, message, fallback, settings.push_settings)
-                            .await;
+                        sender.start_push_brief(remote, message, fallback, settings.push_settings)
                     } else {
-                        sender.push(remote, message, settings.push_settings).await;
+                        sender.start_push(remote, message, settings.push_settings)
                     }
+                };
 
-                    remote
-                }
+                BestEffort::events(remote, start)
             })
-            .collect::<FuturesUnordered<_>>();
+            .collect::<Vec<_>>();
 
-        let completed = Vec::with_capacity(unordered.len());
-        let stream = Box::pin(unordered);
+        let completed = Vec::with_capacity(streams.len());
+        let stream = Box::pin(stream::select_all(streams));
 
-        BestEffort { stream, completed }
+        BestEffort {
+            stream,
+            acknowledgements: HashMap::new(),
+            completed,
+        }
     }
 
+    // Every `Acknowledgement` received from `remote`, followed by
+    // `Event::Completed` if its push terminated acknowledged. The push
+    // is only started (by `start`) once the stream is first polled
+    fn events<S>(remote: Identity, start: S) -> Pin<Box<dyn Stream<Item = Event> + Send + Sync>>
+    where
+        S: 'static + Future<Output = PushHandle> + Send + Sync,
+    {
+        let stream = stream::once(start).flat_map(move |handle| {
+            stream::unfold(Some(handle), move |handle| async move {
+                let mut handle = handle?;
+
+                match handle.next_acknowledgement().await {
+                    Some(acknowledgement) => {
+                        Some((Event::Acknowledged(remote, acknowledgement), Some(handle)))
+                    }
+                    None => {
+                        if handle.outcome().await.acknowledged() {
+                            Some((Event::Completed(remote), None))
+                        } else {
+                            None
+                        }
+                    }
+                }
+            })
+        });
+
+        Box::pin(stream)
+    }
+
+    /// Remotes whose push reached `PushSettings::stop_condition`.
     pub fn completed(&self) -> &[Identity] {
         self.completed.as_slice()
     }
 
+    /// Highest `Acknowledgement` received so far from each remote.
+    pub fn acknowledgements(&self) -> &HashMap<Identity, Acknowledgement> {
+        &self.acknowledgements
+    }
+
+    /// Stream of every `Acknowledgement` received, along with its remote.
+    /// The stream ends once all pushes have terminated (or were cancelled).
+    pub fn progress(&mut self) -> impl Stream<Item = (Identity, Acknowledgement)> + '_ {
+        stream::unfold(self, |best_effort| async move {
+            loop {
+                match best_effort.next().await? {
+                    Event::Acknowledged(remote, acknowledgement) => {
+                        return Some(((remote, acknowledgement), best_effort));
+                    }
+                    Event::Completed(_) => continue,
+                }
+            }
+        })
+    }
+
     pub async fn until(&mut self, threshold: usize) {
+        if !self.try_until(threshold).await {
+            panic!("Called `BestEffort::until` beyond maximum number of recipients");
+        }
+    }
+
+    /// Waits until at least `threshold` pushes reached `PushSettings::stop_condition`.
+    /// Returns `false` if all pushes terminated (or were cancelled) before then.
+    pub async fn try_until(&mut self, threshold: usize) -> bool {
         while self.completed.len() < threshold {
-            self.completed.push(
-                self.stream
-                    .next()
-                    .await
-                    .expect("Called `BestEffort::until` beyond maximum number of recipients"),
-            )
+            if self.next().await.is_none() {
+                return false;
+            }
         }
+
+        true
+    }
+
+    /// Waits until `predicate` holds on the highest `Acknowledgement` received
+    /// from each remote, or until `deadline` elapses. Returns `false` if
+    /// `deadline` elapsed, or all pushes terminated, before `predicate` held.
+    pub async fn until_with<P>(&mut self, mut predicate: P, deadline: Option<Duration>) -> bool
+    where
+        P: FnMut(&HashMap<Identity, Acknowledgement>) -> bool,
+    {
+        let satisfied = async {
+            while !predicate(&self.acknowledgements) {
+                self.next().await?;
+            }
+
+            Some(())
+        };
+
+        matches!(
+            time::optional_timeout(deadline, satisfied).await,
+            Ok(Some(()))
+        )
+    }
+
+    /// Stops all remaining pushes.
+    pub fn cancel(&mut self) {
+        self.stream = Box::pin(stream::empty());
     }
 
     pub async fn complete(self) {
@@ -109,6 +209,23 @@ impl BestEffort {
     pub fn spawn(self, fuse: &Fuse) -> JoinHandle<Option<()>> {
         self.run(fuse.relay())
     }
+
+    async fn next(&mut self) -> Option<Event> {
+        let event = self.stream.next().await?;
+
+        match event {
+            Event::Acknowledged(remote, acknowledgement) => {
+                let highest = self
+                    .acknowledgements
+                    .entry(remote)
+                    .or_insert(acknowledgement);
+                *highest = (*highest).max(acknowledgement);
+            }
+            Event::Completed(remote) => self.completed.push(remote),
+        }
+
+        Some(event)
+    }
 }
 
 #[cfg(test)]
@@ -128,7 +245,7 @@ mod tests {
             keys,
             mut senders,
             receivers,
-        } = UnicastSystem::<u32>::setup(PEERS).await.into();
+        } = UnicastSystem::<u32>::setup(PEERS).await;
 
         let sender = senders.remove(0);
 
@@ -166,7 +283,7 @@ mod tests {
             keys,
             senders,
             receivers,
-        } = UnicastSystem::<u32>::setup(PEERS).await.into();
+        } = UnicastSystem::<u32>::setup(PEERS).await;
 
         let _ = receivers
             .into_iter()
@@ -216,7 +333,7 @@ mod tests {
             keys,
             senders,
             receivers,
-        } = UnicastSystem::<u32>::setup(PEERS).await.into();
+        } = UnicastSystem::<u32>::setup(PEERS).await;
 
         let _ = receivers
             .into_iter()
@@ -269,7 +386,7 @@ mod tests {
             keys,
             senders,
             receivers,
-        } = UnicastSystem::<u32>::setup(PEERS).await.into();
+        } = UnicastSystem::<u32>::setup(PEERS).await;
 
         let _ = receivers
             .into_iter()
@@ -312,4 +429,231 @@ mod tests {
 
         join([best_effort_handle]).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn constant_all_broadcast_threshold_exhausted() {
+        const FAULTY: usize = 1;
+        const PEERS: usize = 3 * FAULTY + 1;
+
+        let UnicastSystem {
+            keys,
+            senders,
+            receivers,
+        } = UnicastSystem::<u32>::setup(PEERS).await;
+
+        let _ = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut receiver)| {
+                tokio::spawn(async move {
+                    loop {
+                        let (_, message, acknowledger) = receiver.receive().await;
+                        assert_eq!(message, 42);
+                        if i < FAULTY {
+                            acknowledger.weak();
+                        } else {
+                            acknowledger.strong();
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let best_effort_handle = tokio::spawn(async move {
+            senders
+                .into_iter()
+                .map(|sender| {
+                    let keys = keys.clone();
+
+                    async move {
+                        let mut settings = BestEffortSettings::strong_constant();
+                        settings.push_settings.max_attempts = Some(3);
+
+                        let mut best_effort = BestEffort::new(sender, keys, 42u32, settings);
+
+                        // The `Weak` remote exhausts its attempts
+                        assert!(!best_effort.try_until(PEERS - FAULTY + 1).await);
+                    }
+                })
+                .collect::<FuturesUnordered<_>>()
+                .collect::<Vec<_>>()
+                .await;
+        });
+
+        join([best_effort_handle]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn progress_events() {
+        const PEERS: usize = 4;
+
+        let UnicastSystem {
+            keys,
+            mut senders,
+            receivers,
+        } = UnicastSystem::<u32>::setup(PEERS).await;
+
+        let _ = receivers
+            .into_iter()
+            .map(|mut receiver| {
+                tokio::spawn(async move {
+                    loop {
+                        let (_, message, acknowledger) = receiver.receive().await;
+                        assert_eq!(message, 42);
+                        acknowledger.strong();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut best_effort = BestEffort::new(
+            senders.remove(0),
+            keys.clone(),
+            42u32,
+            BestEffortSettings::strong_constant(),
+        );
+
+        let mut events = best_effort.progress().collect::<Vec<_>>().await;
+        events.sort();
+
+        let mut expected = keys
+            .iter()
+            .map(|key| (*key, Acknowledgement::Strong))
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(events, expected);
+        assert_eq!(best_effort.completed().len(), PEERS);
+    }
+
+    #[tokio::test]
+    async fn until_with_mixed_acknowledgements() {
+        const FAULTY: usize = 1;
+        const PEERS: usize = 3 * FAULTY + 1;
+
+        let UnicastSystem {
+            keys,
+            mut senders,
+            receivers,
+        } = UnicastSystem::<u32>::setup(PEERS).await;
+
+        let _ = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut receiver)| {
+                tokio::spawn(async move {
+                    loop {
+                        let (_, message, acknowledger) = receiver.receive().await;
+                        assert_eq!(message, 42);
+                        if i < FAULTY {
+                            acknowledger.weak();
+                        } else {
+                            acknowledger.strong();
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut best_effort = BestEffort::new(
+            senders.remove(0),
+            keys.clone(),
+            42u32,
+            BestEffortSettings::strong_constant(),
+        );
+
+        let count = |acknowledgements: &HashMap<Identity, Acknowledgement>, level| {
+            acknowledgements
+                .values()
+                .filter(|acknowledgement| **acknowledgement >= level)
+                .count()
+        };
+
+        // A quorum acknowledges `Strong`, or at least f + 1 acknowledge `Weak`
+        assert!(
+            best_effort
+                .until_with(
+                    |acknowledgements| {
+                        count(acknowledgements, Acknowledgement::Strong) >= PEERS - FAULTY
+                            || count(acknowledgements, Acknowledgement::Weak) > FAULTY
+                    },
+                    Some(Duration::from_secs(1)),
+                )
+                .await
+        );
+
+        // The `Weak` remote never acknowledges `Strong`
+        assert!(
+            !best_effort
+                .until_with(
+                    |acknowledgements| count(acknowledgements, Acknowledgement::Strong) == PEERS,
+                    Some(Duration::from_millis(500)),
+                )
+                .await
+        );
+
+        assert_eq!(
+            best_effort.acknowledgements().get(&keys[0]),
+            Some(&Acknowledgement::Weak)
+        );
+
+        // Once cancelled, no further progress is made
+        best_effort.cancel();
+
+        assert!(
+            !best_effort
+                .until_with(
+                    |acknowledgements| count(acknowledgements, Acknowledgement::Strong) == PEERS,
+                    None,
+                )
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn disconnect_and_reconnect() {
+        const PEERS: usize = 4;
+
+        tokio::time::pause();
+
+        let (
+            UnicastSystem {
+                keys,
+                mut senders,
+                receivers,
+            },
+            scenario,
+        ) = UnicastSystem::<u32>::setup_simulated(PEERS, Default::default());
+
+        let _ = receivers
+            .into_iter()
+            .map(|mut receiver| {
+                tokio::spawn(async move {
+                    loop {
+                        let (_, message, acknowledger) = receiver.receive().await;
+                        assert_eq!(message, 42);
+                        acknowledger.strong();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        scenario.disconnect(PEERS - 1);
+
+        let mut best_effort = BestEffort::new(
+            senders.remove(0),
+            keys.clone(),
+            42u32,
+            BestEffortSettings::strong_constant(),
+        );
+
+        // Every remote but the disconnected one completes
+        best_effort.until(PEERS - 1).await;
+        assert!(!best_effort.completed().contains(&keys[PEERS - 1]));
+
+        scenario.reconnect(PEERS - 1);
+
+        best_effort.until(PEERS).await;
+        assert!(best_effort.completed().contains(&keys[PEERS - 1]));
+    }
 }
@@ -0,0 +1,10 @@
+use crate::crypto::{primitives::multi::Signature as MultiSignature, Identity};
+
+use serde::{Deserialize, Serialize};
+
+// Aggregated `Echo` signatures of `signers`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) struct Certificate {
+    pub signers: Vec<Identity>,
+    pub signature: MultiSignature,
+}
@@ -0,0 +1,709 @@
+use crate::{
+    broadcast::{Certificate, ConsistentBroadcastSettings, ConsistentPhase, Echo, Quorum},
+    crypto::{
+        primitives::{
+            hash::{self, Hash},
+            multi::Signature as MultiSignature,
+        },
+        Identity, KeyCard, KeyChain,
+    },
+    net::{Connector, Listener},
+    sync::fuse::Fuse,
+    unicast::{Acknowledgement, Message, PushSettings, Receiver, Sender},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
+    iter,
+    sync::Arc,
+};
+
+use tokio::sync::mpsc::{self, Receiver as MpscReceiver, Sender as MpscSender};
+
+type DeliveryInlet<M> = MpscSender<(Identity, u64, M)>;
+type DeliveryOutlet<M> = MpscReceiver<(Identity, u64, M)>;
+
+type PhaseSender<M> = Sender<ConsistentPhase<M>, MultiSignature>;
+type PhaseReceiver<M> = Receiver<ConsistentPhase<M>, MultiSignature>;
+
+/// Consistent (echo) broadcast among a committee of `n >= 3f + 1` members,
+/// up to `f` of which can be Byzantine. The origin of a message collects
+/// `Echo` multisignatures from a quorum of the committee, then disseminates
+/// their aggregate as a certificate. Since two quorums always share a correct
+/// member, correct members deliver at most one message per (origin, slot).
+/// Unlike `ReliableBroadcast`, a faulty origin can get some correct members
+/// (and not others) to deliver.
+///
+/// Each member only keeps track of the slots within a window of
+/// `settings.window` slots past the lowest slot it did not deliver yet for
+/// each origin. Beyond the window, `Propose`s and `Deliver`s are weakly
+/// acknowledged, and retried by their origin until the window slides.
+///
+/// The origin keeps disseminating each certificate until every member
+/// strongly acknowledges it, so that a member lagging behind by more than
+/// `settings.window` slots still catches up. As a result, pushes toward a
+/// crashed member are retried for as long as `settings.push_settings` allows.
+pub struct ConsistentBroadcast<M: Message + Clone> {
+    identity: Identity,
+    sender: PhaseSender<M>,
+    committee: Arc<Committee>,
+    slot: u64,
+    delivery_outlet: DeliveryOutlet<M>,
+    push_settings: PushSettings,
+    fuse: Fuse,
+}
+
+#[derive(Doom)]
+pub enum ConsistentBroadcastError {
+    #[doom(description("Empty committee"))]
+    EmptyCommittee,
+}
+
+struct Committee {
+    cards: HashMap<Identity, KeyCard>,
+    quorum: usize,
+}
+
+struct Log {
+    floor: u64,
+    delivered: BTreeSet<u64>,
+    echoed: HashMap<u64, Hash>,
+}
+
+impl<M> ConsistentBroadcast<M>
+where
+    M: Message + Clone,
+{
+    pub fn new<C, L>(
+        keychain: KeyChain,
+        committee: Vec<KeyCard>,
+        connector: C,
+        listener: L,
+        settings: ConsistentBroadcastSettings,
+    ) -> Result<Self, Top<ConsistentBroadcastError>>
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let committee = match Committee::new(committee) {
+            Some(committee) => Arc::new(committee),
+            None => {
+                return ConsistentBroadcastError::EmptyCommittee
+                    .fail()
+                    .spot(here!())
+            }
+        };
+
+        let identity = keychain.keycard().identity();
+
+        let sender = Sender::new(connector, settings.sender_settings);
+        let receiver = Receiver::new(listener, settings.receiver_settings);
+
+        let (delivery_inlet, delivery_outlet) = mpsc::channel(settings.delivery_channel_capacity);
+
+        let fuse = Fuse::new();
+
+        {
+            let committee = committee.clone();
+            let window = settings.window;
+
+            fuse.spawn(async move {
+                ConsistentBroadcast::run(keychain, committee, window, receiver, delivery_inlet)
+                    .await;
+            });
+        }
+
+        Ok(ConsistentBroadcast {
+            identity,
+            sender,
+            committee,
+            slot: 0,
+            delivery_outlet,
+            push_settings: settings.push_settings,
+            fuse,
+        })
+    }
+
+    /// Broadcasts `message` to the committee, returning its slot.
+    pub fn broadcast(&mut self, message: M) -> u64 {
+        let slot = self.slot;
+        self.slot += 1;
+
+        let identity = self.identity;
+        let sender = self.sender.clone();
+        let committee = self.committee.clone();
+        let push_settings = self.push_settings.clone();
+
+        self.fuse.spawn(async move {
+            ConsistentBroadcast::disseminate(
+                identity,
+                sender,
+                committee,
+                slot,
+                message,
+                push_settings,
+            )
+            .await;
+        });
+
+        slot
+    }
+
+    /// Returns the next message delivered, along with its origin and slot.
+    pub async fn deliver(&mut self) -> (Identity, u64, M) {
+        // This cannot fail, as `delivery_inlet` is held by `run` until
+        // `self.fuse` is dropped along with `self`
+        self.delivery_outlet.recv().await.unwrap()
+    }
+
+    async fn disseminate(
+        identity: Identity,
+        sender: PhaseSender<M>,
+        committee: Arc<Committee>,
+        slot: u64,
+        message: M,
+        push_settings: PushSettings,
+    ) {
+        let hash = match hash::hash(&message) {
+            Ok(hash) => hash,
+            Err(_) => return,
+        };
+
+        let echo = Echo {
+            origin: identity,
+            slot,
+            hash,
+        };
+
+        let mut echoes = committee
+            .cards
+            .values()
+            .map(|card| {
+                let sender = sender.clone();
+                let card = card.clone();
+                let proposal = ConsistentPhase::Propose {
+                    slot,
+                    message: message.clone(),
+                };
+                let echo = echo.clone();
+                let mut sleep_agent = push_settings.retry_schedule.agent();
+
+                async move {
+                    loop {
+                        match sender.send(card.identity(), proposal.clone()).await {
+                            // Invalid signatures are discarded, lest they spoil the aggregate
+                            Ok((_, Some(signature))) => {
+                                return signature
+                                    .verify(iter::once(&card), &echo)
+                                    .ok()
+                                    .map(|_| (card.identity(), signature));
+                            }
+                            // `card` is not ready to echo `slot` yet
+                            Ok((Acknowledgement::Weak, None)) | Err(_) => sleep_agent.step().await,
+                            Ok((_, None)) => return None,
+                        }
+                    }
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut signers = Vec::with_capacity(committee.quorum);
+        let mut signatures = Vec::with_capacity(committee.quorum);
+
+        while signers.len() < committee.quorum {
+            match echoes.next().await {
+                Some(Some((signer, signature))) => {
+                    signers.push(signer);
+                    signatures.push(signature);
+                }
+                Some(None) => {}
+                None => return, // Too many members refused to echo
+            }
+        }
+
+        let signature = match MultiSignature::aggregate(signatures) {
+            Ok(signature) => signature,
+            Err(_) => return,
+        };
+
+        let delivery = ConsistentPhase::Deliver {
+            origin: identity,
+            slot,
+            message,
+            certificate: Certificate { signers, signature },
+        };
+
+        committee
+            .cards
+            .keys()
+            .map(|member| sender.push(*member, delivery.clone(), push_settings.clone()))
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+    }
+
+    async fn run(
+        keychain: KeyChain,
+        committee: Arc<Committee>,
+        window: u64,
+        mut receiver: PhaseReceiver<M>,
+        delivery_inlet: DeliveryInlet<M>,
+    ) {
+        let mut logs: HashMap<Identity, Log> = HashMap::new();
+
+        loop {
+            let (remote, phase, acknowledger) = receiver.receive().await;
+
+            let (origin, slot) = match &phase {
+                ConsistentPhase::Propose { slot, .. } => (remote, *slot),
+                ConsistentPhase::Deliver { origin, slot, .. } => (*origin, *slot),
+            };
+
+            // Refusals are strongly acknowledged, lest they be retried
+            if !committee.cards.contains_key(&origin) {
+                acknowledger.strong();
+                continue;
+            }
+
+            let log = logs.entry(origin).or_insert_with(Log::new);
+
+            if slot >= log.floor.saturating_add(window) {
+                acknowledger.weak();
+                continue;
+            }
+
+            match phase {
+                ConsistentPhase::Propose { message, .. } => {
+                    // Having delivered `slot`, this member no longer knows what it echoed
+                    if log.delivered(slot) {
+                        acknowledger.strong();
+                        continue;
+                    }
+
+                    let hash = match hash::hash(&message) {
+                        Ok(hash) => hash,
+                        Err(_) => {
+                            acknowledger.strong();
+                            continue;
+                        }
+                    };
+
+                    // Only one message is ever echoed per (origin, slot). Echoing the
+                    // same message again is harmless, and tolerates retries
+                    let echoable = match log.echoed.entry(slot) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(hash);
+                            true
+                        }
+                        Entry::Occupied(entry) => *entry.get() == hash,
+                    };
+
+                    if !echoable {
+                        acknowledger.strong();
+                        continue;
+                    }
+
+                    let echo = Echo { origin, slot, hash };
+
+                    match keychain.multisign(&echo) {
+                        Ok(signature) => acknowledger.reply(signature),
+                        Err(_) => acknowledger.strong(),
+                    }
+                }
+                ConsistentPhase::Deliver {
+                    message,
+                    certificate,
+                    ..
+                } => {
+                    if log.delivered(slot) {
+                        acknowledger.strong();
+                        continue;
+                    }
+
+                    if !committee.certifies(origin, slot, &message, &certificate) {
+                        acknowledger.weak();
+                        continue;
+                    }
+
+                    log.deliver(slot);
+                    acknowledger.strong();
+
+                    let _ = delivery_inlet.send((origin, slot, message)).await;
+                }
+            }
+        }
+    }
+}
+
+impl Committee {
+    fn new(cards: Vec<KeyCard>) -> Option<Self> {
+        let cards = cards
+            .into_iter()
+            .map(|card| (card.identity(), card))
+            .collect::<HashMap<_, _>>();
+
+        let quorum = Quorum::new(cards.len())?.intersecting();
+
+        Some(Committee { cards, quorum })
+    }
+
+    fn certifies<M>(
+        &self,
+        origin: Identity,
+        slot: u64,
+        message: &M,
+        certificate: &Certificate,
+    ) -> bool
+    where
+        M: Message,
+    {
+        if !self.cards.contains_key(&origin) {
+            return false;
+        }
+
+        let signers = certificate.signers.iter().collect::<HashSet<_>>();
+
+        if signers.len() != certificate.signers.len() || signers.len() < self.quorum {
+            return false;
+        }
+
+        let cards = match certificate
+            .signers
+            .iter()
+            .map(|signer| self.cards.get(signer))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(cards) => cards,
+            None => return false,
+        };
+
+        let hash = match hash::hash(message) {
+            Ok(hash) => hash,
+            Err(_) => return false,
+        };
+
+        let echo = Echo { origin, slot, hash };
+
+        certificate.signature.verify(cards, &echo).is_ok()
+    }
+}
+
+impl Log {
+    fn new() -> Self {
+        Log {
+            floor: 0,
+            delivered: BTreeSet::new(),
+            echoed: HashMap::new(),
+        }
+    }
+
+    fn delivered(&self, slot: u64) -> bool {
+        slot < self.floor || self.delivered.contains(&slot)
+    }
+
+    fn deliver(&mut self, slot: u64) {
+        self.echoed.remove(&slot);
+        self.delivered.insert(slot);
+
+        // Only the slots delivered out of order are remembered
+        while self.delivered.remove(&self.floor) {
+            self.floor += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        net::test::{SimulatedSystem, System as NetSystem},
+        time::test::join,
+    };
+
+    use std::time::Duration;
+
+    use tokio::time;
+
+    const PEERS: usize = 4;
+
+    async fn setup() -> (Vec<KeyChain>, Vec<KeyCard>, NetSystem) {
+        let keychains = (0..PEERS).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let cards = keychains.iter().map(KeyChain::keycard).collect::<Vec<_>>();
+
+        let system = NetSystem::setup_with_keychains(keychains.clone()).await;
+
+        (keychains, cards, system)
+    }
+
+    #[tokio::test]
+    async fn certified_delivery() {
+        let (keychains, cards, system) = setup().await;
+
+        let NetSystem {
+            keys,
+            connectors,
+            listeners,
+        } = system;
+
+        let mut broadcasts = keychains
+            .into_iter()
+            .zip(connectors)
+            .zip(listeners)
+            .map(|((keychain, connector), listener)| {
+                ConsistentBroadcast::<u32>::new(
+                    keychain,
+                    cards.clone(),
+                    connector,
+                    listener,
+                    ConsistentBroadcastSettings::strong_constant(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(broadcasts[0].broadcast(42), 0);
+
+        let origin = keys[0];
+
+        let handles = broadcasts
+            .into_iter()
+            .map(|mut broadcast| {
+                tokio::spawn(async move {
+                    assert_eq!(broadcast.deliver().await, (origin, 0, 42));
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join(handles).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn empty_committee() {
+        let (mut keychains, _, system) = setup().await;
+
+        let NetSystem {
+            mut connectors,
+            mut listeners,
+            ..
+        } = system;
+
+        assert!(ConsistentBroadcast::<u32>::new(
+            keychains.remove(0),
+            Vec::new(),
+            connectors.remove(0),
+            listeners.remove(0),
+            ConsistentBroadcastSettings::strong_constant(),
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn sliding_window() {
+        let (keychains, cards, system) = setup().await;
+
+        let NetSystem {
+            keys,
+            connectors,
+            listeners,
+        } = system;
+
+        let mut broadcasts = keychains
+            .into_iter()
+            .zip(connectors)
+            .zip(listeners)
+            .map(|((keychain, connector), listener)| {
+                ConsistentBroadcast::<u32>::new(
+                    keychain,
+                    cards.clone(),
+                    connector,
+                    listener,
+                    ConsistentBroadcastSettings {
+                        window: 1,
+                        ..ConsistentBroadcastSettings::strong_constant()
+                    },
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Slots 1 and 2 are not echoed until 0 and 1 are delivered, respectively
+        for message in 0..3 {
+            broadcasts[0].broadcast(message);
+        }
+
+        let origin = keys[0];
+
+        let handles = broadcasts
+            .into_iter()
+            .map(|mut broadcast| {
+                tokio::spawn(async move {
+                    for slot in 0..3 {
+                        assert_eq!(broadcast.deliver().await, (origin, slot, slot as u32));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join(handles).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lagging_member() {
+        time::pause();
+
+        let keychains = (0..PEERS).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let cards = keychains.iter().map(KeyChain::keycard).collect::<Vec<_>>();
+
+        let system = SimulatedSystem::setup_with_keychains(keychains.clone(), Default::default());
+        let scenario = system.scenario();
+
+        let SimulatedSystem {
+            keys,
+            connectors,
+            listeners,
+            ..
+        } = system;
+
+        let mut broadcasts = keychains
+            .into_iter()
+            .zip(connectors)
+            .zip(listeners)
+            .map(|((keychain, connector), listener)| {
+                ConsistentBroadcast::<u32>::new(
+                    keychain,
+                    cards.clone(),
+                    connector,
+                    listener,
+                    ConsistentBroadcastSettings {
+                        window: 1,
+                        ..ConsistentBroadcastSettings::strong_constant()
+                    },
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Member 3 falls behind by more than `window` slots
+        scenario.disconnect(PEERS - 1);
+
+        for message in 0..5 {
+            broadcasts[0].broadcast(message);
+        }
+
+        let origin = keys[0];
+        let mut lagging = broadcasts.pop().unwrap();
+
+        for broadcast in broadcasts.iter_mut() {
+            for slot in 0..5 {
+                assert_eq!(broadcast.deliver().await, (origin, slot, slot as u32));
+            }
+        }
+
+        scenario.reconnect(PEERS - 1);
+
+        for slot in 0..5 {
+            assert_eq!(lagging.deliver().await, (origin, slot, slot as u32));
+        }
+    }
+
+    #[tokio::test]
+    async fn equivocation() {
+        let (mut keychains, cards, system) = setup().await;
+
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = system;
+
+        // Member 0 is Byzantine, and talks `ConsistentPhase`s directly
+        let byzantine = PhaseSender::<u32>::new(connectors.remove(0), Default::default());
+        let _byzantine_receiver =
+            PhaseReceiver::<u32>::new(listeners.remove(0), Default::default());
+        keychains.remove(0);
+
+        let mut correct = keychains
+            .into_iter()
+            .zip(connectors)
+            .zip(listeners)
+            .map(|((keychain, connector), listener)| {
+                ConsistentBroadcast::<u32>::new(
+                    keychain,
+                    cards.clone(),
+                    connector,
+                    listener,
+                    ConsistentBroadcastSettings::strong_constant(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let propose = |message| ConsistentPhase::Propose { slot: 0, message };
+
+        // Members echo the first message proposed for a slot...
+        let mut signers = Vec::new();
+        let mut signatures = Vec::new();
+
+        for member in &keys[1..] {
+            let (_, signature) = byzantine.send(*member, propose(1)).await.unwrap();
+
+            signers.push(*member);
+            signatures.push(signature.unwrap());
+        }
+
+        // ... and refuse to echo any other
+        for member in &keys[1..] {
+            let (acknowledgement, signature) = byzantine.send(*member, propose(2)).await.unwrap();
+
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+            assert!(signature.is_none());
+        }
+
+        let deliver =
+            |signers: Vec<Identity>, signatures: Vec<MultiSignature>| ConsistentPhase::Deliver {
+                origin: keys[0],
+                slot: 0,
+                message: 1,
+                certificate: Certificate {
+                    signers,
+                    signature: MultiSignature::aggregate(signatures).unwrap(),
+                },
+            };
+
+        // A certificate short of a quorum is not delivered
+        let (acknowledgement, _) = byzantine
+            .send(
+                keys[1],
+                deliver(signers[..2].to_vec(), signatures[..2].to_vec()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(acknowledgement, Acknowledgement::Weak);
+
+        assert!(
+            time::timeout(Duration::from_millis(100), correct[0].deliver())
+                .await
+                .is_err()
+        );
+
+        // A quorum certificate is delivered
+        for member in &keys[1..] {
+            let (acknowledgement, _) = byzantine
+                .send(*member, deliver(signers.clone(), signatures.clone()))
+                .await
+                .unwrap();
+
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+        }
+
+        for broadcast in correct.iter_mut() {
+            assert_eq!(broadcast.deliver().await, (keys[0], 0, 1));
+        }
+    }
+}
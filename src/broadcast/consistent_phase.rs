@@ -0,0 +1,17 @@
+use crate::{broadcast::Certificate, crypto::Identity};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) enum ConsistentPhase<Message> {
+    Propose {
+        slot: u64,
+        message: Message,
+    },
+    Deliver {
+        origin: Identity,
+        slot: u64,
+        message: Message,
+        certificate: Certificate,
+    },
+}
@@ -0,0 +1,18 @@
+use crate::crypto::{primitives::hash::Hash, Identity, Scope, Statement, TalkHeader};
+
+use serde::{Deserialize, Serialize};
+
+// Signed by a member of the committee to vouch that `origin` proposed
+// (only) the message hashing to `hash` for `slot`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) struct Echo {
+    pub origin: Identity,
+    pub slot: u64,
+    pub hash: Hash,
+}
+
+impl Statement for Echo {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::ConsistentBroadcastEcho;
+}
@@ -0,0 +1,641 @@
+use crate::{
+    broadcast::{Envelope, Exchange, GossipMessage, GossipSettings, GossipStatistics},
+    crypto::{
+        primitives::hash::{self, Hash},
+        Identity, KeyCard,
+    },
+    link::rendezvous::{Client, ShardId},
+    net::{Connector, Listener},
+    sync::fuse::Fuse,
+    unicast::{Message, Receiver, Sender},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use parking_lot::Mutex;
+
+use rand::seq::SliceRandom;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{
+    sync::mpsc::{self, Receiver as MpscReceiver, Sender as MpscSender},
+    time::{self, Instant},
+};
+
+type DeliveryInlet<M> = MpscSender<M>;
+type DeliveryOutlet<M> = MpscReceiver<M>;
+
+type GossipSender<M> = Sender<GossipMessage<M>, Exchange<M>>;
+type GossipReceiver<M> = Receiver<GossipMessage<M>, Exchange<M>>;
+
+/// Epidemic dissemination among `members`. Each new message is forwarded to
+/// `fanout` random members, for up to `ttl` hops. Periodically, each member
+/// reconciles the messages it retains with a random member (push-pull anti-entropy),
+/// so that messages missed by rumor mongering are eventually delivered. Each
+/// reconciliation carries at most `exchange_max_messages` (or `exchange_max_bytes`)
+/// messages, oldest first. Messages from non-members are ignored.
+/// Messages are timestamped upon `broadcast`, retained (and deduplicated by `Hash`)
+/// for `retention`, then refused as stale: as long as members' clocks are
+/// synchronized, no message is delivered twice. The system clock is only read
+/// upon `new`: from then on, time elapses according to `tokio::time`. The origin
+/// of a message does not deliver it.
+pub struct Gossip<M: Message + Clone> {
+    sender: GossipSender<M>,
+    members: Arc<Vec<Identity>>,
+    database: Arc<Mutex<Database<M>>>,
+    delivery_outlet: DeliveryOutlet<M>,
+    settings: GossipSettings,
+    fuse: Fuse,
+}
+
+struct Database<M> {
+    records: HashMap<Hash, Record<M>>,
+    statistics: GossipStatistics,
+    clock: Clock,
+}
+
+struct Record<M> {
+    message: M,
+    timestamp: u64,
+}
+
+// Milliseconds since `UNIX_EPOCH`
+#[derive(Clone, Copy)]
+struct Clock {
+    epoch: u64,
+    start: Instant,
+}
+
+#[derive(Doom)]
+pub enum GossipError {
+    #[doom(description("Failed to get shard"))]
+    GetShardFailed,
+}
+
+impl<M> Gossip<M>
+where
+    M: Message + Clone,
+{
+    pub fn new<C, L>(
+        identity: Identity,
+        members: Vec<Identity>,
+        connector: C,
+        listener: L,
+        settings: GossipSettings,
+    ) -> Self
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let sender = Sender::new(connector, settings.sender_settings.clone());
+        let receiver = Receiver::new(listener, settings.receiver_settings.clone());
+
+        let members = members
+            .into_iter()
+            .filter(|member| *member != identity)
+            .collect::<Vec<_>>();
+
+        let members = Arc::new(members);
+
+        let database = Arc::new(Mutex::new(Database {
+            records: HashMap::new(),
+            statistics: GossipStatistics::default(),
+            clock: Clock::new(),
+        }));
+
+        let (delivery_inlet, delivery_outlet) = mpsc::channel(settings.delivery_channel_capacity);
+
+        let fuse = Fuse::new();
+
+        {
+            let sender = sender.clone();
+            let members = members.clone();
+            let database = database.clone();
+            let delivery_inlet = delivery_inlet.clone();
+            let settings = settings.clone();
+
+            fuse.spawn(async move {
+                Gossip::run(
+                    receiver,
+                    sender,
+                    members,
+                    database,
+                    delivery_inlet,
+                    settings,
+                )
+                .await;
+            });
+        }
+
+        {
+            let sender = sender.clone();
+            let members = members.clone();
+            let database = database.clone();
+            let settings = settings.clone();
+
+            fuse.spawn(async move {
+                Gossip::anti_entropy(sender, members, database, delivery_inlet, settings).await;
+            });
+        }
+
+        Gossip {
+            sender,
+            members,
+            database,
+            delivery_outlet,
+            settings,
+            fuse,
+        }
+    }
+
+    /// Gossips among the members of `shard`, as retrieved from a rendezvous `client`.
+    pub async fn from_shard<C, L>(
+        identity: Identity,
+        client: &Client,
+        shard: ShardId,
+        connector: C,
+        listener: L,
+        settings: GossipSettings,
+    ) -> Result<Self, Top<GossipError>>
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let members = client
+            .get_shard(shard)
+            .await
+            .pot(GossipError::GetShardFailed, here!())?
+            .iter()
+            .map(KeyCard::identity)
+            .collect::<Vec<_>>();
+
+        Ok(Gossip::new(
+            identity, members, connector, listener, settings,
+        ))
+    }
+
+    pub fn broadcast(&self, message: M) {
+        let hash = match hash::hash(&message) {
+            Ok(hash) => hash,
+            Err(_) => return,
+        };
+
+        let timestamp = {
+            let mut database = self.database.lock();
+            let timestamp = database.clock.now();
+
+            database.records.insert(
+                hash,
+                Record {
+                    message: message.clone(),
+                    timestamp,
+                },
+            );
+
+            timestamp
+        };
+
+        Gossip::forward(
+            &self.sender,
+            self.members.as_slice(),
+            None,
+            GossipMessage::Rumor {
+                ttl: self.settings.ttl,
+                envelope: Envelope { timestamp, message },
+            },
+            self.settings.fanout,
+            &self.fuse,
+        );
+    }
+
+    pub async fn deliver(&mut self) -> M {
+        // This cannot fail, as `delivery_inlet` is held by `run` until
+        // `self.fuse` is dropped along with `self`
+        self.delivery_outlet.recv().await.unwrap()
+    }
+
+    pub fn statistics(&self) -> GossipStatistics {
+        self.database.lock().statistics
+    }
+
+    async fn run(
+        mut receiver: GossipReceiver<M>,
+        sender: GossipSender<M>,
+        members: Arc<Vec<Identity>>,
+        database: Arc<Mutex<Database<M>>>,
+        delivery_inlet: DeliveryInlet<M>,
+        settings: GossipSettings,
+    ) {
+        let fuse = Fuse::new();
+
+        loop {
+            let (remote, message, acknowledger) = receiver.receive().await;
+
+            if !members.contains(&remote) {
+                acknowledger.strong();
+                continue;
+            }
+
+            match message {
+                GossipMessage::Rumor { ttl, envelope } => {
+                    acknowledger.strong();
+
+                    if !Gossip::learn(&database, &envelope, &settings) {
+                        continue;
+                    }
+
+                    if ttl > 0 {
+                        Gossip::forward(
+                            &sender,
+                            members.as_slice(),
+                            Some(remote),
+                            GossipMessage::Rumor {
+                                ttl: ttl - 1,
+                                envelope: envelope.clone(),
+                            },
+                            settings.fanout,
+                            &fuse,
+                        );
+                    }
+
+                    let _ = delivery_inlet.send(envelope.message).await;
+                }
+                GossipMessage::Digest(digest) => {
+                    let exchange = database.lock().exchange(digest, &settings);
+                    acknowledger.reply(exchange);
+                }
+                GossipMessage::Push(envelopes) => {
+                    acknowledger.strong();
+
+                    for envelope in envelopes {
+                        if Gossip::learn(&database, &envelope, &settings) {
+                            let _ = delivery_inlet.send(envelope.message).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn anti_entropy(
+        sender: GossipSender<M>,
+        members: Arc<Vec<Identity>>,
+        database: Arc<Mutex<Database<M>>>,
+        delivery_inlet: DeliveryInlet<M>,
+        settings: GossipSettings,
+    ) {
+        loop {
+            time::sleep(settings.anti_entropy_interval).await;
+
+            let digest = {
+                let mut database = database.lock();
+                database.prune(&settings);
+                database.records.keys().copied().collect::<Vec<_>>()
+            };
+
+            let member = match members.choose(&mut rand::thread_rng()) {
+                Some(member) => *member,
+                None => continue,
+            };
+
+            let exchange = match sender.send(member, GossipMessage::Digest(digest)).await {
+                Ok((_, Some(exchange))) => exchange,
+                _ => continue,
+            };
+
+            // Pull
+            for envelope in exchange.messages {
+                if Gossip::learn(&database, &envelope, &settings) {
+                    let _ = delivery_inlet.send(envelope.message).await;
+                }
+            }
+
+            // Push
+            let wanted = {
+                let database = database.lock();
+
+                let wanted = exchange
+                    .wanted
+                    .iter()
+                    .filter_map(|hash| database.records.get(hash))
+                    .collect::<Vec<_>>();
+
+                Database::page(wanted, &settings)
+            };
+
+            if !wanted.is_empty() {
+                let _ = sender.send(member, GossipMessage::Push(wanted)).await;
+            }
+        }
+    }
+
+    // Returns `true` if `envelope` is new and fresh
+    fn learn(
+        database: &Mutex<Database<M>>,
+        envelope: &Envelope<M>,
+        settings: &GossipSettings,
+    ) -> bool {
+        let hash = match hash::hash(&envelope.message) {
+            Ok(hash) => hash,
+            Err(_) => return false,
+        };
+
+        let mut database = database.lock();
+
+        // A stale message might have been delivered, then pruned: it is
+        // refused, as it cannot be told apart from a new one
+        if !Gossip::<M>::fresh(envelope.timestamp, database.clock.now(), settings) {
+            return false;
+        }
+
+        if database.records.contains_key(&hash) {
+            database.statistics.duplicates += 1;
+            return false;
+        }
+
+        database.records.insert(
+            hash,
+            Record {
+                message: envelope.message.clone(),
+                timestamp: envelope.timestamp,
+            },
+        );
+
+        database.statistics.delivered += 1;
+        true
+    }
+
+    // Messages timestamped too far in the future are also refused, lest they
+    // be retained (and their `Record`s grow) indefinitely
+    fn fresh(timestamp: u64, now: u64, settings: &GossipSettings) -> bool {
+        let retention = settings.retention.as_millis() as u64;
+
+        now.saturating_sub(timestamp) <= retention && timestamp.saturating_sub(now) <= retention
+    }
+
+    fn forward(
+        sender: &GossipSender<M>,
+        members: &[Identity],
+        exclude: Option<Identity>,
+        rumor: GossipMessage<M>,
+        fanout: usize,
+        fuse: &Fuse,
+    ) {
+        let candidates = members
+            .iter()
+            .copied()
+            .filter(|member| Some(*member) != exclude)
+            .collect::<Vec<_>>();
+
+        let targets = candidates
+            .choose_multiple(&mut rand::thread_rng(), fanout)
+            .copied()
+            .collect::<Vec<_>>();
+
+        for target in targets {
+            sender.spawn_send(target, rumor.clone(), fuse);
+        }
+    }
+}
+
+impl<M> Database<M>
+where
+    M: Message + Clone,
+{
+    fn exchange(&self, digest: Vec<Hash>, settings: &GossipSettings) -> Exchange<M> {
+        let digest = digest.into_iter().collect::<HashSet<_>>();
+        let now = self.clock.now();
+
+        let missing = self
+            .records
+            .iter()
+            .filter(|(hash, record)| {
+                !digest.contains(hash) && Gossip::<M>::fresh(record.timestamp, now, settings)
+            })
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>();
+
+        let messages = Database::page(missing, settings);
+
+        let wanted = digest
+            .into_iter()
+            .filter(|hash| !self.records.contains_key(hash))
+            .take(settings.exchange_max_messages)
+            .collect::<Vec<_>>();
+
+        Exchange { messages, wanted }
+    }
+
+    // Selects the oldest `records` (i.e., the closest to going stale) that fit
+    // within `exchange_max_messages` and `exchange_max_bytes`: the rest are
+    // left to the following rounds of anti-entropy
+    fn page(mut records: Vec<&Record<M>>, settings: &GossipSettings) -> Vec<Envelope<M>> {
+        records.sort_by_key(|record| record.timestamp);
+
+        let mut page = Vec::new();
+        let mut bytes = 0;
+
+        for record in records.into_iter().take(settings.exchange_max_messages) {
+            let envelope = record.envelope();
+
+            bytes += match bincode::serialized_size(&envelope) {
+                Ok(size) => size as usize,
+                Err(_) => continue,
+            };
+
+            if bytes > settings.exchange_max_bytes {
+                break;
+            }
+
+            page.push(envelope);
+        }
+
+        page
+    }
+
+    fn prune(&mut self, settings: &GossipSettings) {
+        let now = self.clock.now();
+
+        self.records
+            .retain(|_, record| Gossip::<M>::fresh(record.timestamp, now, settings));
+    }
+}
+
+impl Clock {
+    fn new() -> Self {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        Clock {
+            epoch,
+            start: Instant::now(),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.epoch + self.start.elapsed().as_millis() as u64
+    }
+}
+
+impl<M> Record<M>
+where
+    M: Clone,
+{
+    fn envelope(&self) -> Envelope<M> {
+        Envelope {
+            timestamp: self.timestamp,
+            message: self.message.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::test::SimulatedSystem;
+
+    use futures::stream::{FuturesUnordered, StreamExt};
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn coverage() {
+        const PEERS: usize = 100;
+        const FANOUT: usize = 4;
+        const RETENTION: Duration = Duration::from_secs(2);
+
+        time::pause();
+
+        let SimulatedSystem {
+            keys,
+            connectors,
+            listeners,
+            ..
+        } = SimulatedSystem::setup(PEERS, Default::default());
+
+        let settings = GossipSettings {
+            fanout: FANOUT,
+            anti_entropy_interval: Duration::from_millis(100),
+            retention: RETENTION,
+            ..Default::default()
+        };
+
+        let mut gossips = keys
+            .iter()
+            .zip(connectors)
+            .zip(listeners)
+            .map(|((identity, connector), listener)| {
+                Gossip::<u32>::new(
+                    *identity,
+                    keys.clone(),
+                    connector,
+                    listener,
+                    settings.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        gossips[0].broadcast(42);
+
+        let delivered = time::timeout(
+            Duration::from_secs(10),
+            gossips[1..]
+                .iter_mut()
+                .map(|gossip| gossip.deliver())
+                .collect::<FuturesUnordered<_>>()
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+
+        assert!(delivered.into_iter().all(|message| message == 42));
+
+        // Every member other than the origin delivers exactly once, even after
+        // the message is pruned while anti-entropy keeps running
+        let redelivered = time::timeout(
+            2 * RETENTION,
+            gossips
+                .iter_mut()
+                .map(|gossip| gossip.deliver())
+                .collect::<FuturesUnordered<_>>()
+                .next(),
+        )
+        .await;
+
+        assert!(redelivered.is_err());
+
+        let statistics = gossips.iter().map(Gossip::statistics).fold(
+            GossipStatistics::default(),
+            |total, statistics| GossipStatistics {
+                delivered: total.delivered + statistics.delivered,
+                duplicates: total.duplicates + statistics.duplicates,
+            },
+        );
+
+        assert_eq!(statistics.delivered, (PEERS - 1) as u64);
+
+        // Each member forwards a rumor at most once, to `FANOUT` members
+        assert!(statistics.duplicates <= (PEERS * FANOUT) as u64);
+    }
+
+    #[test]
+    fn exchange_paging() {
+        let settings = GossipSettings {
+            exchange_max_messages: 4,
+            ..Default::default()
+        };
+
+        let clock = Clock::new();
+        let now = clock.now();
+
+        let mut database = Database {
+            records: HashMap::new(),
+            statistics: GossipStatistics::default(),
+            clock,
+        };
+
+        for message in 0..10u32 {
+            database.records.insert(
+                hash::hash(&message).unwrap(),
+                Record {
+                    message,
+                    timestamp: now + message as u64,
+                },
+            );
+        }
+
+        let mut digest = Vec::new();
+
+        // Each exchange carries the oldest messages missing from `digest`
+        for expected in [vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]].iter() {
+            let exchange = database.exchange(digest.clone(), &settings);
+
+            let messages = exchange
+                .messages
+                .iter()
+                .map(|envelope| envelope.message)
+                .collect::<Vec<_>>();
+
+            assert_eq!(&messages, expected);
+
+            digest.extend(messages.iter().map(|message| hash::hash(message).unwrap()));
+        }
+
+        // Pages are also bounded in size
+        let settings = GossipSettings {
+            exchange_max_bytes: 40,
+            ..Default::default()
+        };
+
+        let exchange = database.exchange(Vec::new(), &settings);
+        assert_eq!(exchange.messages.len(), 3);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::crypto::primitives::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) enum GossipMessage<Message> {
+    Rumor {
+        ttl: u32,
+        envelope: Envelope<Message>,
+    },
+    Digest(Vec<Hash>),
+    Push(Vec<Envelope<Message>>),
+}
+
+// A message, along with the time (in milliseconds since the UNIX epoch)
+// at which it was broadcast: stale messages are refused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) struct Envelope<Message> {
+    pub timestamp: u64,
+    pub message: Message,
+}
+
+// Reply to a `GossipMessage::Digest`: the `messages` missing from the digest,
+// and the hashes in the digest that the replier has never seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) struct Exchange<Message> {
+    pub messages: Vec<Envelope<Message>>,
+    pub wanted: Vec<Hash>,
+}
@@ -0,0 +1,32 @@
+use crate::unicast::{ReceiverSettings, SenderSettings};
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct GossipSettings {
+    pub sender_settings: SenderSettings,
+    pub receiver_settings: ReceiverSettings,
+    pub fanout: usize,
+    pub ttl: u32,
+    pub anti_entropy_interval: Duration,
+    pub retention: Duration,
+    pub exchange_max_messages: usize,
+    pub exchange_max_bytes: usize,
+    pub delivery_channel_capacity: usize,
+}
+
+impl Default for GossipSettings {
+    fn default() -> Self {
+        GossipSettings {
+            sender_settings: SenderSettings::default(),
+            receiver_settings: ReceiverSettings::default(),
+            fanout: 4,
+            ttl: 6,
+            anti_entropy_interval: Duration::from_secs(1),
+            retention: Duration::from_secs(60),
+            exchange_max_messages: 1024,
+            exchange_max_bytes: 16 * 1024 * 1024,
+            delivery_channel_capacity: 32768,
+        }
+    }
+}
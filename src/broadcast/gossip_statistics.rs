@@ -0,0 +1,5 @@
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GossipStatistics {
+    pub delivered: u64,
+    pub duplicates: u64,
+}
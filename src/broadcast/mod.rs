@@ -1,5 +1,36 @@
 mod best_effort;
 mod best_effort_settings;
+mod certificate;
+mod consistent_broadcast;
+mod consistent_broadcast_settings;
+mod consistent_phase;
+mod echo;
+mod gossip;
+mod gossip_message;
+mod gossip_settings;
+mod gossip_statistics;
+mod payload_store;
+mod payload_store_settings;
+mod phase;
+mod quorum;
+mod reliable_broadcast;
+mod reliable_broadcast_settings;
+
+use certificate::Certificate;
+use consistent_phase::ConsistentPhase;
+use echo::Echo;
+use gossip_message::{Envelope, Exchange, GossipMessage};
+use phase::Phase;
+use quorum::Quorum;
 
 pub use best_effort::BestEffort;
 pub use best_effort_settings::BestEffortSettings;
+pub use consistent_broadcast::{ConsistentBroadcast, ConsistentBroadcastError};
+pub use consistent_broadcast_settings::ConsistentBroadcastSettings;
+pub use gossip::{Gossip, GossipError};
+pub use gossip_settings::GossipSettings;
+pub use gossip_statistics::GossipStatistics;
+pub use payload_store::{PayloadStore, PayloadStoreError};
+pub use payload_store_settings::PayloadStoreSettings;
+pub use reliable_broadcast::{ReliableBroadcast, ReliableBroadcastError};
+pub use reliable_broadcast_settings::ReliableBroadcastSettings;
@@ -0,0 +1,506 @@
+use crate::{
+    broadcast::{BestEffort, BestEffortSettings, PayloadStoreSettings},
+    crypto::{
+        primitives::hash::{self, Hash},
+        Identity,
+    },
+    net::{Connector, Listener},
+    sync::fuse::Fuse,
+    unicast::{Acknowledger, Message, Receiver, Sender},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use parking_lot::Mutex;
+
+use rand::seq::SliceRandom;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    iter,
+    sync::Arc,
+};
+
+use tokio::time;
+
+/// Content-addressed store of payloads, served to any peer that asks for them
+/// by `Hash`. Paired with a brief that only carries a payload's `Hash`, this
+/// enables pull-based dissemination: a receiver `fetch`es the payload from any
+/// peer that already holds it, rather than from the origin alone. Fetched
+/// payloads are verified against their `Hash`, stored and, in turn, served.
+/// `broadcast` and `expand` implement this dissemination mode on top of `BestEffort`.
+/// At most `capacity` payloads are stored: beyond that, the oldest are evicted.
+pub struct PayloadStore<P: Message + Clone> {
+    sender: Sender<Hash, P>,
+    database: Arc<Mutex<Database<P>>>,
+    settings: PayloadStoreSettings,
+    _fuse: Fuse,
+}
+
+struct Database<P> {
+    payloads: HashMap<Hash, P>,
+    insertions: VecDeque<Hash>,
+    capacity: usize,
+    served: u64,
+}
+
+#[derive(Doom)]
+pub enum PayloadStoreError {
+    #[doom(description("Failed to fetch payload"))]
+    FetchFailed,
+    #[doom(description("Failed to hash payload"))]
+    HashFailed,
+}
+
+impl<P> PayloadStore<P>
+where
+    P: Message + Clone,
+{
+    pub fn new<C, L>(connector: C, listener: L, settings: PayloadStoreSettings) -> Self
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let sender = Sender::new(connector, settings.sender_settings.clone());
+        let receiver = Receiver::new(listener, settings.receiver_settings.clone());
+
+        let database = Arc::new(Mutex::new(Database {
+            payloads: HashMap::new(),
+            insertions: VecDeque::new(),
+            capacity: settings.capacity,
+            served: 0,
+        }));
+
+        let fuse = Fuse::new();
+
+        {
+            let database = database.clone();
+            fuse.spawn(async move { PayloadStore::serve(receiver, database).await });
+        }
+
+        PayloadStore {
+            sender,
+            database,
+            settings,
+            _fuse: fuse,
+        }
+    }
+
+    /// Stores `payload`, returning the `Hash` under which it is served.
+    pub fn insert(&self, payload: P) -> Result<Hash, Top<PayloadStoreError>> {
+        let hash = hash::hash(&payload).pot(PayloadStoreError::HashFailed, here!())?;
+        self.database.lock().store(hash, payload);
+        Ok(hash)
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<P> {
+        self.database.lock().payloads.get(hash).cloned()
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.database.lock().payloads.contains_key(hash)
+    }
+
+    /// Stores `payload`, then pushes its `Hash` (as a brief) to `remotes`. Each
+    /// remote is expected to handle the brief with `expand`, pulling `payload`
+    /// from any peer that holds it: the returned `BestEffort` observes `Expand`
+    /// from the remotes still pulling, and completes on those that acknowledge
+    /// `Strong` upon a later retry, once they store `payload`.
+    pub fn broadcast<R>(
+        &self,
+        sender: Sender<Hash>,
+        remotes: R,
+        payload: P,
+        settings: BestEffortSettings,
+    ) -> Result<BestEffort, Top<PayloadStoreError>>
+    where
+        R: IntoIterator<Item = Identity>,
+    {
+        let hash = self.insert(payload)?;
+        Ok(BestEffort::new(sender, remotes, hash, settings))
+    }
+
+    /// Handles a brief `hash` received from `remote` (see `broadcast`). If the
+    /// payload is already stored, acknowledges `Strong`. Otherwise, acknowledges
+    /// `Expand`, then `fetch`es the payload from `remote` and `sources`.
+    pub async fn expand<S>(
+        &self,
+        remote: Identity,
+        hash: Hash,
+        acknowledger: Acknowledger,
+        sources: S,
+    ) -> Result<P, Top<PayloadStoreError>>
+    where
+        S: IntoIterator<Item = Identity>,
+    {
+        if let Some(payload) = self.get(&hash) {
+            acknowledger.strong();
+            return Ok(payload);
+        }
+
+        acknowledger.expand();
+        self.fetch(hash, iter::once(remote).chain(sources)).await
+    }
+
+    /// Number of payloads served to peers so far.
+    pub fn served(&self) -> u64 {
+        self.database.lock().served
+    }
+
+    /// Retrieves the payload with `hash`, locally if stored, or else from
+    /// `sources`, which are polled in random order for up to `max_rounds`.
+    /// Payloads that do not match `hash` are discarded.
+    pub async fn fetch<S>(&self, hash: Hash, sources: S) -> Result<P, Top<PayloadStoreError>>
+    where
+        S: IntoIterator<Item = Identity>,
+    {
+        let mut sources = sources.into_iter().collect::<Vec<_>>();
+        sources.shuffle(&mut rand::thread_rng());
+
+        let mut sleep_agent = self.settings.retry_schedule.agent();
+
+        for round in 0..self.settings.max_rounds {
+            if round > 0 {
+                sleep_agent.step().await;
+            }
+
+            // `hash` might have been fetched concurrently in the meantime
+            if let Some(payload) = self.get(&hash) {
+                return Ok(payload);
+            }
+
+            for source in sources.iter() {
+                // An unresponsive source is given up on after `fetch_timeout`
+                let payload = match time::timeout(
+                    self.settings.fetch_timeout,
+                    self.sender.send(*source, hash),
+                )
+                .await
+                {
+                    Ok(Ok((_, Some(payload)))) => payload,
+                    _ => continue,
+                };
+
+                if hash::hash(&payload).ok() == Some(hash) {
+                    self.database.lock().store(hash, payload.clone());
+                    return Ok(payload);
+                }
+            }
+        }
+
+        PayloadStoreError::FetchFailed.fail().spot(here!())
+    }
+
+    async fn serve(mut receiver: Receiver<Hash, P>, database: Arc<Mutex<Database<P>>>) {
+        loop {
+            let (_, hash, acknowledger) = receiver.receive().await;
+
+            let payload = {
+                let mut database = database.lock();
+                let payload = database.payloads.get(&hash).cloned();
+
+                if payload.is_some() {
+                    database.served += 1;
+                }
+
+                payload
+            };
+
+            match payload {
+                Some(payload) => acknowledger.reply(payload),
+                None => acknowledger.weak(),
+            }
+        }
+    }
+}
+
+impl<P> Database<P> {
+    fn store(&mut self, hash: Hash, payload: P) {
+        if self.payloads.contains_key(&hash) {
+            return;
+        }
+
+        self.payloads.insert(hash, payload);
+        self.insertions.push_back(hash);
+
+        while self.payloads.len() > self.capacity {
+            match self.insertions.pop_front() {
+                Some(evicted) => {
+                    self.payloads.remove(&evicted);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{crypto::KeyChain, net::test::System as NetSystem};
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn chained_fetch() {
+        const PEERS: usize = 8;
+
+        let NetSystem {
+            keys,
+            connectors,
+            listeners,
+        } = NetSystem::setup(PEERS).await;
+
+        let stores = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                PayloadStore::<Vec<u8>>::new(connector, listener, PayloadStoreSettings::constant())
+            })
+            .collect::<Vec<_>>();
+
+        let payload = vec![42u8; 1 << 16];
+        let hash = stores[0].insert(payload.clone()).unwrap();
+
+        // Each peer pulls from the previous one, so that the origin serves only once
+        for (index, store) in stores.iter().enumerate().skip(1) {
+            let fetched = store.fetch(hash, vec![keys[index - 1]]).await.unwrap();
+            assert_eq!(fetched, payload);
+            assert!(store.contains(&hash));
+        }
+
+        for store in stores.iter().take(PEERS - 1) {
+            assert_eq!(store.served(), 1);
+        }
+
+        assert_eq!(stores[PEERS - 1].served(), 0);
+    }
+
+    #[tokio::test]
+    async fn any_holder() {
+        let NetSystem {
+            keys,
+            connectors,
+            listeners,
+        } = NetSystem::setup(3).await;
+
+        let stores = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                PayloadStore::<Vec<u8>>::new(connector, listener, PayloadStoreSettings::constant())
+            })
+            .collect::<Vec<_>>();
+
+        let hash = stores[0].insert(vec![1, 2, 3]).unwrap();
+
+        // Peer 1 does not hold the payload, so it can only be served by peer 0
+        let fetched = stores[2].fetch(hash, vec![keys[1], keys[0]]).await.unwrap();
+
+        assert_eq!(fetched, vec![1, 2, 3]);
+        assert_eq!(stores[0].served(), 1);
+        assert_eq!(stores[1].served(), 0);
+    }
+
+    #[tokio::test]
+    async fn tampered_payload() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(3).await;
+
+        // Peer 0 replies to every request with a payload that does not match its `Hash`
+        let mut tamperer = Receiver::<Hash, Vec<u8>>::new(listeners.remove(0), Default::default());
+        connectors.remove(0);
+
+        tokio::spawn(async move {
+            loop {
+                let (_, _, acknowledger) = tamperer.receive().await;
+                acknowledger.reply(vec![0; 3]);
+            }
+        });
+
+        let stores = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                PayloadStore::<Vec<u8>>::new(connector, listener, PayloadStoreSettings::constant())
+            })
+            .collect::<Vec<_>>();
+
+        let hash = stores[0].insert(vec![1, 2, 3]).unwrap();
+
+        assert!(stores[1].fetch(hash, vec![keys[0]]).await.is_err());
+        assert!(!stores[1].contains(&hash));
+
+        let fetched = stores[1].fetch(hash, vec![keys[0], keys[1]]).await.unwrap();
+
+        assert_eq!(fetched, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn hashed_broadcast() {
+        const PEERS: usize = 8;
+
+        let keychains = (0..PEERS).map(|_| KeyChain::random()).collect::<Vec<_>>();
+
+        // Briefs and payloads travel on separate connections between the same peers
+        let NetSystem {
+            keys,
+            mut connectors,
+            listeners,
+        } = NetSystem::setup_with_keychains(keychains.clone()).await;
+
+        let payloads = NetSystem::setup_with_keychains(keychains).await;
+
+        let stores = payloads
+            .connectors
+            .into_iter()
+            .zip(payloads.listeners)
+            .map(|(connector, listener)| {
+                Arc::new(PayloadStore::<Vec<u8>>::new(
+                    connector,
+                    listener,
+                    PayloadStoreSettings::constant(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let handles = listeners
+            .into_iter()
+            .zip(stores.iter().cloned())
+            .zip(keys.iter().copied())
+            .skip(1)
+            .map(|((listener, store), key)| {
+                let mut receiver = Receiver::<Hash>::new(listener, Default::default());
+
+                let sources = keys
+                    .iter()
+                    .copied()
+                    .filter(|source| *source != key)
+                    .collect::<Vec<_>>();
+
+                tokio::spawn(async move {
+                    let (remote, hash, acknowledger) = receiver.receive().await;
+                    let payload = store
+                        .expand(remote, hash, acknowledger, sources.clone())
+                        .await;
+
+                    // Retried briefs are acknowledged `Strong`
+                    tokio::spawn(async move {
+                        loop {
+                            let (remote, hash, acknowledger) = receiver.receive().await;
+                            let _ = store
+                                .expand(remote, hash, acknowledger, sources.clone())
+                                .await;
+                        }
+                    });
+
+                    payload.unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let payload = vec![42u8; 1 << 16];
+        let sender = Sender::<Hash>::new(connectors.remove(0), Default::default());
+
+        let best_effort = stores[0]
+            .broadcast(
+                sender,
+                keys[1..].iter().copied(),
+                payload.clone(),
+                BestEffortSettings::strong_constant(),
+            )
+            .unwrap();
+
+        best_effort.complete().await;
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), payload);
+        }
+
+        // Each remote pulls the payload exactly once, from any peer holding it
+        let served = stores.iter().map(|store| store.served()).sum::<u64>();
+        assert_eq!(served, (PEERS - 1) as u64);
+    }
+
+    #[tokio::test]
+    async fn eviction() {
+        let NetSystem {
+            mut connectors,
+            mut listeners,
+            ..
+        } = NetSystem::setup(1).await;
+
+        let store = PayloadStore::<u32>::new(
+            connectors.remove(0),
+            listeners.remove(0),
+            PayloadStoreSettings {
+                capacity: 2,
+                ..PayloadStoreSettings::constant()
+            },
+        );
+
+        let hashes = (0..3)
+            .map(|payload| store.insert(payload).unwrap())
+            .collect::<Vec<_>>();
+
+        // Re-inserting a stored payload does not refresh it
+        store.insert(1).unwrap();
+
+        assert!(!store.contains(&hashes[0]));
+        assert!(store.contains(&hashes[1]));
+        assert!(store.contains(&hashes[2]));
+    }
+
+    #[tokio::test]
+    async fn unresponsive_source() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(3).await;
+
+        // Peer 0 never acknowledges (nor replies to) any request
+        let mut sink = Receiver::<Hash, Vec<u8>>::new(listeners.remove(0), Default::default());
+        connectors.remove(0);
+
+        tokio::spawn(async move {
+            let mut acknowledgers = Vec::new();
+
+            loop {
+                let (_, _, acknowledger) = sink.receive().await;
+                acknowledgers.push(acknowledger);
+            }
+        });
+
+        let stores = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                PayloadStore::<Vec<u8>>::new(
+                    connector,
+                    listener,
+                    PayloadStoreSettings {
+                        fetch_timeout: Duration::from_millis(100),
+                        ..PayloadStoreSettings::constant()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let hash = stores[0].insert(vec![1, 2, 3]).unwrap();
+
+        let fetched = time::timeout(
+            Duration::from_secs(5),
+            stores[1].fetch(hash, vec![keys[0], keys[1]]),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(fetched, vec![1, 2, 3]);
+    }
+}
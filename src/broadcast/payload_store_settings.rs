@@ -0,0 +1,50 @@
+use crate::{
+    time::{sleep_schedules::CappedExponential, SleepSchedule},
+    unicast::{ReceiverSettings, SenderSettings},
+};
+
+use std::{sync::Arc, time::Duration};
+
+#[derive(Debug, Clone)]
+pub struct PayloadStoreSettings {
+    pub sender_settings: SenderSettings,
+    pub receiver_settings: ReceiverSettings,
+    pub retry_schedule: Arc<dyn SleepSchedule>,
+    pub max_rounds: u32,
+    pub fetch_timeout: Duration,
+    pub capacity: usize,
+}
+
+impl Default for PayloadStoreSettings {
+    fn default() -> Self {
+        PayloadStoreSettings {
+            sender_settings: SenderSettings::default(),
+            receiver_settings: ReceiverSettings::default(),
+            retry_schedule: Arc::new(CappedExponential::new(
+                Duration::from_millis(100),
+                2.,
+                Duration::from_secs(10),
+            )),
+            max_rounds: 8,
+            fetch_timeout: Duration::from_secs(5),
+            capacity: 1024,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::time::sleep_schedules::Constant;
+
+    impl PayloadStoreSettings {
+        pub fn constant() -> Self {
+            PayloadStoreSettings {
+                retry_schedule: Arc::new(Constant::new(Duration::from_millis(100))),
+                max_rounds: 4,
+                ..Default::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::crypto::Identity;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::broadcast) enum Phase<Message> {
+    Send {
+        sequence: u64,
+        message: Message,
+    },
+    Echo {
+        origin: Identity,
+        sequence: u64,
+        message: Message,
+    },
+    Ready {
+        origin: Identity,
+        sequence: u64,
+        message: Message,
+    },
+}
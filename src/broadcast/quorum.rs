@@ -0,0 +1,67 @@
+/// Quorum sizes among `members` processes, up to `faulty` of which can be
+/// Byzantine, with `members >= 3 * faulty + 1`.
+#[derive(Debug, Clone, Copy)]
+pub(in crate::broadcast) struct Quorum {
+    members: usize,
+    faulty: usize,
+}
+
+impl Quorum {
+    /// Returns `None` if `members` is zero, as no process can be correct.
+    pub fn new(members: usize) -> Option<Self> {
+        if members == 0 {
+            return None;
+        }
+
+        Some(Quorum {
+            members,
+            faulty: (members - 1) / 3,
+        })
+    }
+
+    /// Any two sets of `intersecting()` members share at least one correct member.
+    pub fn intersecting(&self) -> usize {
+        (self.members + self.faulty) / 2 + 1
+    }
+
+    /// Any set of `plurality()` members contains at least one correct member.
+    pub fn plurality(&self) -> usize {
+        self.faulty + 1
+    }
+
+    /// Any set of `majority()` members contains a majority of correct members.
+    pub fn majority(&self) -> usize {
+        2 * self.faulty + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert!(Quorum::new(0).is_none());
+    }
+
+    #[test]
+    fn sizes() {
+        let quorum = Quorum::new(1).unwrap();
+        assert_eq!(
+            (quorum.intersecting(), quorum.plurality(), quorum.majority()),
+            (1, 1, 1)
+        );
+
+        let quorum = Quorum::new(4).unwrap();
+        assert_eq!(
+            (quorum.intersecting(), quorum.plurality(), quorum.majority()),
+            (3, 2, 3)
+        );
+
+        let quorum = Quorum::new(10).unwrap();
+        assert_eq!(
+            (quorum.intersecting(), quorum.plurality(), quorum.majority()),
+            (7, 4, 7)
+        );
+    }
+}
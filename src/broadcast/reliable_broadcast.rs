@@ -0,0 +1,638 @@
+use crate::{
+    broadcast::{Phase, Quorum, ReliableBroadcastSettings},
+    crypto::{
+        primitives::hash::{self, Hash},
+        Identity,
+    },
+    net::{Connector, Listener},
+    sync::fuse::Fuse,
+    unicast::{Message, PushSettings, Receiver, Sender},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
+
+use tokio::sync::mpsc::{self, Receiver as MpscReceiver, Sender as MpscSender};
+
+type DeliveryInlet<M> = MpscSender<(Identity, u64, M)>;
+type DeliveryOutlet<M> = MpscReceiver<(Identity, u64, M)>;
+
+/// Bracha's reliable broadcast among a fixed set of `members`, up to `f` of
+/// which can be Byzantine, with `members.len() >= 3f + 1`. Every correct member
+/// delivers the same message (if any) for each (origin, sequence), and if a
+/// correct member delivers, every correct member eventually delivers.
+///
+/// Each member only keeps track of the (origin, sequence)s within a window of
+/// `settings.window` sequence numbers past the lowest sequence it did not
+/// deliver yet for that origin: `Phase`s beyond the window are refused (and
+/// retried by their sender) until the window slides.
+///
+/// Every `Phase` is pushed until each member strongly acknowledges it, so
+/// that a member lagging behind by more than `settings.window` sequence
+/// numbers still collects enough `Phase`s to catch up. As a result, pushes
+/// toward a crashed member are retried for as long as `settings.push_settings`
+/// allows.
+pub struct ReliableBroadcast<M: Message + Clone> {
+    sender: Sender<Phase<M>>,
+    members: Arc<Vec<Identity>>,
+    sequence: u64,
+    delivery_outlet: DeliveryOutlet<M>,
+    push_settings: PushSettings,
+    fuse: Fuse,
+}
+
+#[derive(Doom)]
+pub enum ReliableBroadcastError {
+    #[doom(description("Empty membership"))]
+    EmptyMembership,
+}
+
+struct Thresholds {
+    echo: usize,
+    ready: usize,
+    deliver: usize,
+}
+
+struct Log<M> {
+    floor: u64,
+    delivered: BTreeSet<u64>,
+    instances: HashMap<u64, Instance<M>>,
+}
+
+struct Instance<M> {
+    messages: HashMap<Hash, M>,
+    echoes: HashMap<Hash, usize>,
+    readies: HashMap<Hash, usize>,
+    echoers: HashSet<Identity>,
+    readiers: HashSet<Identity>,
+    echoed: bool,
+    readied: bool,
+}
+
+impl<M> ReliableBroadcast<M>
+where
+    M: Message + Clone,
+{
+    pub fn new<C, L>(
+        members: Vec<Identity>,
+        connector: C,
+        listener: L,
+        settings: ReliableBroadcastSettings,
+    ) -> Result<Self, Top<ReliableBroadcastError>>
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let thresholds = match Quorum::new(members.len()) {
+            Some(quorum) => Thresholds::new(quorum),
+            None => return ReliableBroadcastError::EmptyMembership.fail().spot(here!()),
+        };
+
+        let sender = Sender::new(connector, settings.sender_settings);
+        let receiver = Receiver::new(listener, settings.receiver_settings);
+
+        let members = Arc::new(members);
+
+        let (delivery_inlet, delivery_outlet) = mpsc::channel(settings.delivery_channel_capacity);
+
+        let fuse = Fuse::new();
+
+        {
+            let sender = sender.clone();
+            let members = members.clone();
+            let push_settings = settings.push_settings.clone();
+            let window = settings.window;
+
+            fuse.spawn(async move {
+                ReliableBroadcast::run(
+                    receiver,
+                    sender,
+                    members,
+                    thresholds,
+                    window,
+                    delivery_inlet,
+                    push_settings,
+                )
+                .await;
+            });
+        }
+
+        Ok(ReliableBroadcast {
+            sender,
+            members,
+            sequence: 0,
+            delivery_outlet,
+            push_settings: settings.push_settings,
+            fuse,
+        })
+    }
+
+    /// Broadcasts `message` to all members, returning its sequence number.
+    pub fn broadcast(&mut self, message: M) -> u64 {
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        ReliableBroadcast::multicast(
+            &self.sender,
+            self.members.as_slice(),
+            Phase::Send { sequence, message },
+            &self.push_settings,
+            &self.fuse,
+        );
+
+        sequence
+    }
+
+    /// Returns the next message delivered, along with its origin and sequence number.
+    pub async fn deliver(&mut self) -> (Identity, u64, M) {
+        // This cannot fail, as `delivery_inlet` is held by `run` until
+        // `self.fuse` is dropped along with `self`
+        self.delivery_outlet.recv().await.unwrap()
+    }
+
+    async fn run(
+        mut receiver: Receiver<Phase<M>>,
+        sender: Sender<Phase<M>>,
+        members: Arc<Vec<Identity>>,
+        thresholds: Thresholds,
+        window: u64,
+        delivery_inlet: DeliveryInlet<M>,
+        push_settings: PushSettings,
+    ) {
+        let mut logs: HashMap<Identity, Log<M>> = HashMap::new();
+
+        let fuse = Fuse::new();
+
+        loop {
+            let (remote, phase, acknowledger) = receiver.receive().await;
+
+            let (origin, sequence) = match &phase {
+                Phase::Send { sequence, .. } => (remote, *sequence),
+                Phase::Echo {
+                    origin, sequence, ..
+                }
+                | Phase::Ready {
+                    origin, sequence, ..
+                } => (*origin, *sequence),
+            };
+
+            if !members.contains(&remote) || !members.contains(&origin) {
+                acknowledger.strong();
+                continue;
+            }
+
+            let log = logs.entry(origin).or_insert_with(Log::new);
+
+            if log.delivered(sequence) {
+                acknowledger.strong();
+                continue;
+            }
+
+            if sequence >= log.floor.saturating_add(window) {
+                // `remote` will retry once the window slides
+                acknowledger.weak();
+                continue;
+            }
+
+            acknowledger.strong();
+
+            let instance = log.instances.entry(sequence).or_insert_with(Instance::new);
+
+            let multicast = |phase| {
+                ReliableBroadcast::multicast(
+                    &sender,
+                    members.as_slice(),
+                    phase,
+                    &push_settings,
+                    &fuse,
+                )
+            };
+
+            match phase {
+                Phase::Send { message, .. } => {
+                    // Only the first `Send` is echoed: an equivocating origin
+                    // cannot get two messages echoed by the same member
+                    if !instance.echoed {
+                        instance.echoed = true;
+
+                        multicast(Phase::Echo {
+                            origin,
+                            sequence,
+                            message,
+                        });
+                    }
+                }
+                Phase::Echo { message, .. } => {
+                    if !instance.echoers.insert(remote) {
+                        continue;
+                    }
+
+                    let hash = match hash::hash(&message) {
+                        Ok(hash) => hash,
+                        Err(_) => continue,
+                    };
+
+                    instance.messages.entry(hash).or_insert(message);
+
+                    let echoes = instance.echoes.entry(hash).or_insert(0);
+                    *echoes += 1;
+
+                    if *echoes >= thresholds.echo && !instance.readied {
+                        instance.readied = true;
+
+                        multicast(Phase::Ready {
+                            origin,
+                            sequence,
+                            message: instance.messages[&hash].clone(),
+                        });
+                    }
+                }
+                Phase::Ready { message, .. } => {
+                    if !instance.readiers.insert(remote) {
+                        continue;
+                    }
+
+                    let hash = match hash::hash(&message) {
+                        Ok(hash) => hash,
+                        Err(_) => continue,
+                    };
+
+                    instance.messages.entry(hash).or_insert(message);
+
+                    let readies = instance.readies.entry(hash).or_insert(0);
+                    *readies += 1;
+
+                    let readies = *readies;
+
+                    // At least one correct member is ready: amplify
+                    if readies >= thresholds.ready && !instance.readied {
+                        instance.readied = true;
+
+                        multicast(Phase::Ready {
+                            origin,
+                            sequence,
+                            message: instance.messages[&hash].clone(),
+                        });
+                    }
+
+                    if readies >= thresholds.deliver {
+                        // Having amplified, this member has nothing left to
+                        // contribute to (origin, sequence): its instance is dropped
+                        let message = log.deliver(sequence, &hash);
+                        let _ = delivery_inlet.send((origin, sequence, message)).await;
+                    }
+                }
+            }
+        }
+    }
+
+    fn multicast(
+        sender: &Sender<Phase<M>>,
+        members: &[Identity],
+        phase: Phase<M>,
+        push_settings: &PushSettings,
+        fuse: &Fuse,
+    ) {
+        for member in members {
+            sender.spawn_push(*member, phase.clone(), push_settings.clone(), fuse);
+        }
+    }
+}
+
+impl Thresholds {
+    fn new(quorum: Quorum) -> Self {
+        Thresholds {
+            echo: quorum.intersecting(),
+            ready: quorum.plurality(),
+            deliver: quorum.majority(),
+        }
+    }
+}
+
+impl<M> Log<M> {
+    fn new() -> Self {
+        Log {
+            floor: 0,
+            delivered: BTreeSet::new(),
+            instances: HashMap::new(),
+        }
+    }
+
+    fn delivered(&self, sequence: u64) -> bool {
+        sequence < self.floor || self.delivered.contains(&sequence)
+    }
+
+    fn deliver(&mut self, sequence: u64, hash: &Hash) -> M {
+        let mut instance = self.instances.remove(&sequence).unwrap();
+        self.delivered.insert(sequence);
+
+        // Only the sequence numbers delivered out of order are remembered
+        while self.delivered.remove(&self.floor) {
+            self.floor += 1;
+        }
+
+        instance.messages.remove(hash).unwrap()
+    }
+}
+
+impl<M> Instance<M> {
+    fn new() -> Self {
+        Instance {
+            messages: HashMap::new(),
+            echoes: HashMap::new(),
+            readies: HashMap::new(),
+            echoers: HashSet::new(),
+            readiers: HashSet::new(),
+            echoed: false,
+            readied: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        net::test::{SimulatedSystem, System as NetSystem},
+        time::test::join,
+    };
+
+    use std::time::Duration;
+
+    use tokio::time;
+
+    const PEERS: usize = 4;
+
+    #[tokio::test]
+    async fn correct_origin() {
+        let NetSystem {
+            keys,
+            connectors,
+            listeners,
+        } = NetSystem::setup(PEERS).await;
+
+        let mut broadcasts = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                ReliableBroadcast::<u32>::new(
+                    keys.clone(),
+                    connector,
+                    listener,
+                    ReliableBroadcastSettings::strong_constant(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(broadcasts[0].broadcast(42), 0);
+        assert_eq!(broadcasts[0].broadcast(43), 1);
+
+        let origin = keys[0];
+
+        let handles = broadcasts
+            .into_iter()
+            .map(|mut broadcast| {
+                tokio::spawn(async move {
+                    let mut delivered = vec![broadcast.deliver().await, broadcast.deliver().await];
+                    delivered.sort_by_key(|(_, sequence, _)| *sequence);
+
+                    assert_eq!(delivered, vec![(origin, 0, 42), (origin, 1, 43)]);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join(handles).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn empty_membership() {
+        let NetSystem {
+            mut connectors,
+            mut listeners,
+            ..
+        } = NetSystem::setup(1).await;
+
+        assert!(ReliableBroadcast::<u32>::new(
+            Vec::new(),
+            connectors.remove(0),
+            listeners.remove(0),
+            ReliableBroadcastSettings::strong_constant(),
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn sliding_window() {
+        let NetSystem {
+            keys,
+            connectors,
+            listeners,
+        } = NetSystem::setup(PEERS).await;
+
+        let mut broadcasts = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                ReliableBroadcast::<u32>::new(
+                    keys.clone(),
+                    connector,
+                    listener,
+                    ReliableBroadcastSettings {
+                        window: 1,
+                        ..ReliableBroadcastSettings::strong_constant()
+                    },
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Sequences 1 and 2 are refused until 0 and 1 are delivered, respectively
+        for message in 0..3 {
+            broadcasts[0].broadcast(message);
+        }
+
+        let origin = keys[0];
+
+        let handles = broadcasts
+            .into_iter()
+            .map(|mut broadcast| {
+                tokio::spawn(async move {
+                    for sequence in 0..3 {
+                        assert_eq!(
+                            broadcast.deliver().await,
+                            (origin, sequence, sequence as u32)
+                        );
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join(handles).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lagging_member() {
+        time::pause();
+
+        let system = SimulatedSystem::setup(PEERS, Default::default());
+        let scenario = system.scenario();
+
+        let SimulatedSystem {
+            keys,
+            connectors,
+            listeners,
+            ..
+        } = system;
+
+        let mut broadcasts = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                ReliableBroadcast::<u32>::new(
+                    keys.clone(),
+                    connector,
+                    listener,
+                    ReliableBroadcastSettings {
+                        window: 1,
+                        ..ReliableBroadcastSettings::strong_constant()
+                    },
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Member 3 falls behind by more than `window` sequence numbers
+        scenario.disconnect(PEERS - 1);
+
+        for message in 0..5 {
+            broadcasts[0].broadcast(message);
+        }
+
+        let origin = keys[0];
+        let mut lagging = broadcasts.pop().unwrap();
+
+        for broadcast in broadcasts.iter_mut() {
+            for sequence in 0..5 {
+                assert_eq!(
+                    broadcast.deliver().await,
+                    (origin, sequence, sequence as u32)
+                );
+            }
+        }
+
+        scenario.reconnect(PEERS - 1);
+
+        for sequence in 0..5 {
+            assert_eq!(lagging.deliver().await, (origin, sequence, sequence as u32));
+        }
+    }
+
+    // Keys 1 to 3 are correct members, while key 0 belongs to an equivocating
+    // origin that `Send`s 1 to members 1 and 2, and 2 to member 3. Returns the
+    // correct members and the equivocating origin's `Sender`.
+    async fn equivocation() -> (
+        Vec<Identity>,
+        Vec<ReliableBroadcast<u32>>,
+        Sender<Phase<u32>>,
+    ) {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(PEERS).await;
+
+        let byzantine = Sender::<Phase<u32>>::new(connectors.remove(0), Default::default());
+        let mut byzantine_receiver =
+            Receiver::<Phase<u32>>::new(listeners.remove(0), Default::default());
+
+        tokio::spawn(async move {
+            loop {
+                let (_, _, acknowledger) = byzantine_receiver.receive().await;
+                acknowledger.strong();
+            }
+        });
+
+        let correct = connectors
+            .into_iter()
+            .zip(listeners)
+            .map(|(connector, listener)| {
+                ReliableBroadcast::<u32>::new(
+                    keys.clone(),
+                    connector,
+                    listener,
+                    ReliableBroadcastSettings::strong_constant(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        for (member, message) in [(1, 1), (2, 1), (3, 2)].iter() {
+            byzantine
+                .send(
+                    keys[*member],
+                    Phase::Send {
+                        sequence: 0,
+                        message: *message,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        (keys, correct, byzantine)
+    }
+
+    #[tokio::test]
+    async fn equivocation_without_quorum() {
+        let (_, correct, _byzantine) = equivocation().await;
+
+        // Neither 1 nor 2 gathers enough `Echo`es for any correct member to be ready
+        for mut broadcast in correct {
+            assert!(
+                time::timeout(Duration::from_millis(500), broadcast.deliver())
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn equivocation_agreement() {
+        let (keys, correct, byzantine) = equivocation().await;
+
+        // The equivocating origin also `Echo`es 1, enabling a quorum for 1
+        for member in keys.iter() {
+            byzantine
+                .send(
+                    *member,
+                    Phase::Echo {
+                        origin: keys[0],
+                        sequence: 0,
+                        message: 1,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let origin = keys[0];
+
+        // Member 3, which received 2 from the origin, still delivers 1
+        let handles = correct
+            .into_iter()
+            .map(|mut broadcast| {
+                tokio::spawn(async move {
+                    assert_eq!(broadcast.deliver().await, (origin, 0, 1));
+                })
+            })
+            .collect::<Vec<_>>();
+
+        join(handles).await.unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+use crate::unicast::{PushSettings, ReceiverSettings, SenderSettings};
+
+#[derive(Debug, Clone)]
+pub struct ReliableBroadcastSettings {
+    pub sender_settings: SenderSettings,
+    pub receiver_settings: ReceiverSettings,
+    pub push_settings: PushSettings,
+    pub delivery_channel_capacity: usize,
+    pub window: u64,
+}
+
+impl Default for ReliableBroadcastSettings {
+    fn default() -> Self {
+        ReliableBroadcastSettings {
+            sender_settings: SenderSettings::default(),
+            receiver_settings: ReceiverSettings::default(),
+            push_settings: PushSettings::default(),
+            delivery_channel_capacity: 32768,
+            window: 1024,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    impl ReliableBroadcastSettings {
+        pub fn strong_constant() -> Self {
+            ReliableBroadcastSettings {
+                push_settings: PushSettings::strong_constant(),
+                ..Default::default()
+            }
+        }
+    }
+}
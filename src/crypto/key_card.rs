@@ -80,7 +80,7 @@ impl Eq for KeyCard {}
 
 impl PartialOrd for KeyCard {
     fn partial_cmp(&self, rho: &Self) -> Option<Ordering> {
-        Some(self.cmp(&rho))
+        Some(self.cmp(rho))
     }
 }
 
@@ -8,7 +8,11 @@ use crate::crypto::{
 
 use doomstack::Top;
 
-use std::sync::Arc;
+use std::{
+    fmt,
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
 
 #[derive(Clone)]
 pub struct KeyChain {
@@ -30,8 +34,23 @@ impl KeyChain {
         KeyChain { keypairs }
     }
 
+    /// Deterministically derives a `KeyChain` from a secret `seed`, e.g. to
+    /// preserve an `Identity` across restarts. Each keypair is derived from
+    /// its own key, so that no two keypairs share secret material.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let sign = blake3::derive_key("talk KeyChain sign", &seed);
+        let multi = blake3::derive_key("talk KeyChain multi", &seed);
+
+        let keypairs = Arc::new(KeyPairs {
+            sign: SignKeyPair::from_seed(sign),
+            multi: MultiKeyPair::from_seed(multi),
+        });
+
+        KeyChain { keypairs }
+    }
+
     pub fn keycard(&self) -> KeyCard {
-        KeyCard::from_keychain(&self)
+        KeyCard::from_keychain(self)
     }
 
     pub fn sign<S: Statement>(&self, message: &S) -> Result<SignSignature, Top<SignError>> {
@@ -44,3 +63,10 @@ impl KeyChain {
             .sign_raw(&(S::SCOPE, S::HEADER, message))
     }
 }
+
+// Only the public part of a `KeyChain` is ever formatted
+impl Debug for KeyChain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "KeyChain({:?})", self.keycard().identity())
+    }
+}
@@ -76,11 +76,7 @@ impl Sender {
 
         self.0
             .cipher
-            .encrypt_in_place(
-                &ChaChaNonce::from_slice(&nonce),
-                &[],
-                buffer as &mut Vec<u8>,
-            )
+            .encrypt_in_place(&ChaChaNonce::from(nonce), &[], buffer as &mut Vec<u8>)
             .unwrap(); // Encrypt `buffer` in place
 
         Ok(())
@@ -112,7 +108,7 @@ impl Sender {
 
         self.0.hasher.reset(); // Compute the keyed hash..
         self.0.hasher.update(&nonce); // .. of `nonce`..
-        self.0.hasher.update(&buffer); // .. and `buffer`..
+        self.0.hasher.update(buffer); // .. and `buffer`..
 
         let tag = self.0.hasher.finalize(); // .. to obtain `tag`
 
@@ -132,7 +128,7 @@ impl Receiver {
         let message = self
             .0
             .cipher
-            .decrypt(&ChaChaNonce::from_slice(&nonce), ciphertext)
+            .decrypt(&ChaChaNonce::from(nonce), ciphertext)
             .map_err(|_| ChannelError::DecryptFailed.into_top())
             .spot(here!())?; // Decrypt `ciphertext` to obtain `message`
 
@@ -150,11 +146,7 @@ impl Receiver {
 
         self.0
             .cipher
-            .decrypt_in_place(
-                &ChaChaNonce::from_slice(&nonce),
-                &[],
-                ciphertext as &mut Vec<u8>,
-            )
+            .decrypt_in_place(&ChaChaNonce::from(nonce), &[], ciphertext as &mut Vec<u8>)
             .map_err(|_| ChannelError::DecryptFailed.into_top())
             .spot(here!())?; // Decrypt `ciphertext` in place
 
@@ -217,7 +209,7 @@ impl State {
 pub fn channel(key: SharedKey, role: Role) -> (Sender, Receiver) {
     let key = key.to_bytes();
 
-    let cipher_key = ChaChaKey::from_slice(&key);
+    let cipher_key = ChaChaKey::from(key);
     let hasher_key = key;
 
     // Corresponding ends of opposite roles must match
@@ -227,14 +219,14 @@ pub fn channel(key: SharedKey, role: Role) -> (Sender, Receiver) {
     };
 
     let sender = Sender(State {
-        cipher: ChaCha20Poly1305::new(cipher_key),
+        cipher: ChaCha20Poly1305::new(&cipher_key),
         hasher: Hasher::new_keyed(&hasher_key),
         lane: sender_lane,
         nonce: 0,
     });
 
     let receiver = Receiver(State {
-        cipher: ChaCha20Poly1305::new(cipher_key),
+        cipher: ChaCha20Poly1305::new(&cipher_key),
         hasher: Hasher::new_keyed(&hasher_key),
         lane: receiver_lane,
         nonce: 0,
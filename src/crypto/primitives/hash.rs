@@ -104,6 +104,12 @@ impl Hasher {
     }
 }
 
+impl Default for Hasher {
+    fn default() -> Self {
+        Hasher::new()
+    }
+}
+
 /// The default hash function.
 pub fn hash<M>(message: &M) -> Result<Hash, Top<HashError>>
 where
@@ -133,7 +139,7 @@ impl Debug for Hash {
 
 impl PartialOrd for Hash {
     fn partial_cmp(&self, rho: &Hash) -> Option<Ordering> {
-        Some(self.cmp(&rho))
+        Some(self.cmp(rho))
     }
 }
 
@@ -147,8 +153,8 @@ impl Ord for Hash {
 #[serde(remote = "BlakeHash")]
 struct SerdeBlakeHash(#[serde(getter = "BlakeHash::as_bytes")] [u8; HASH_LENGTH]);
 
-impl Into<BlakeHash> for SerdeBlakeHash {
-    fn into(self) -> BlakeHash {
-        BlakeHash::from(self.0)
+impl From<SerdeBlakeHash> for BlakeHash {
+    fn from(hash: SerdeBlakeHash) -> Self {
+        BlakeHash::from(hash.0)
     }
 }
@@ -21,6 +21,7 @@ use std::{
 pub const KEYPAIR_LENGTH: usize = 128;
 pub const PUBLIC_KEY_LENGTH: usize = 96;
 pub const SIGNATURE_LENGTH: usize = 48;
+pub const SEED_LENGTH: usize = 32;
 
 const BLST_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
 
@@ -56,9 +57,15 @@ pub enum MultiError {
 
 impl KeyPair {
     pub fn random() -> Self {
-        let mut seed = [0; 32];
+        let mut seed = [0; SEED_LENGTH];
         OsRng.fill_bytes(&mut seed);
 
+        KeyPair::from_seed(seed)
+    }
+
+    /// Deterministically derives a `KeyPair` from a secret `seed`.
+    pub fn from_seed(seed: [u8; SEED_LENGTH]) -> Self {
+        // `key_gen` only fails on seeds shorter than 32 bytes
         let secret = BlstSecretKey::key_gen(&seed, &[]).unwrap();
         let public = secret.sk_to_pk();
 
@@ -312,7 +319,7 @@ impl Hash for Signature {
 
 impl PartialOrd for PublicKey {
     fn partial_cmp(&self, rho: &PublicKey) -> Option<Ordering> {
-        Some(self.cmp(&rho))
+        Some(self.cmp(rho))
     }
 }
 
@@ -1,8 +1,8 @@
 use doomstack::{here, Doom, ResultExt, Top};
 
 use ed25519_dalek::{
-    Keypair as EdKeyPair, PublicKey as EdPublicKey, Signature as EdSignature, Signer as EdSigner,
-    Verifier as EdVerifier,
+    Keypair as EdKeyPair, PublicKey as EdPublicKey, SecretKey as EdSecretKey,
+    Signature as EdSignature, Signer as EdSigner, Verifier as EdVerifier,
 };
 
 use rand::rngs::OsRng;
@@ -16,7 +16,7 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-pub use ed25519_dalek::{KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+pub use ed25519_dalek::{KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH};
 
 pub struct KeyPair(EdKeyPair);
 
@@ -56,6 +56,15 @@ impl KeyPair {
         KeyPair(keypair)
     }
 
+    /// Deterministically derives a `KeyPair` from a secret `seed`.
+    pub fn from_seed(seed: [u8; SECRET_KEY_LENGTH]) -> Self {
+        // Any `SECRET_KEY_LENGTH` bytes make a valid secret key
+        let secret = EdSecretKey::from_bytes(&seed).unwrap();
+        let public = EdPublicKey::from(&secret);
+
+        KeyPair(EdKeyPair { secret, public })
+    }
+
     /// Returns this `KeyPair`'s `PublicKey`.
     pub fn public(&self) -> PublicKey {
         PublicKey(self.0.public)
@@ -304,7 +313,7 @@ impl Hash for Signature {
 
 impl PartialOrd for PublicKey {
     fn partial_cmp(&self, rho: &PublicKey) -> Option<Ordering> {
-        Some(self.cmp(&rho))
+        Some(self.cmp(rho))
     }
 }
 
@@ -327,6 +336,16 @@ mod tests {
         signature.verify_raw(keypair.public(), &message).unwrap();
     }
 
+    #[test]
+    fn from_seed() {
+        let keypair = KeyPair::from_seed([42; SECRET_KEY_LENGTH]);
+        let message: u32 = 1234;
+        let signature = keypair.sign_raw(&message).unwrap();
+
+        let other = KeyPair::from_seed([42; SECRET_KEY_LENGTH]);
+        signature.verify_raw(other.public(), &message).unwrap();
+    }
+
     #[test]
     fn compromise_message() {
         let keypair = KeyPair::random();
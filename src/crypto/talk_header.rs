@@ -4,4 +4,6 @@ use serde::{Deserialize, Serialize};
 #[repr(i8)]
 pub(crate) enum TalkHeader {
     SecureConnectionIdentityChallenge = 0,
+    RendezvousCommand = 1,
+    ConsistentBroadcastEcho = 2,
 }
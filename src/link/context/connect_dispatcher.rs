@@ -1,8 +1,11 @@
 use crate::{
-    link::context::{Connector, ContextId},
+    crypto::Identity,
+    link::context::{Connector, ContextId, Request, Response},
     net::Connector as NetConnector,
 };
 
+use doomstack::{here, Doom, ResultExt, Top};
+
 use parking_lot::Mutex;
 
 use std::{collections::HashSet, sync::Arc};
@@ -16,6 +19,18 @@ pub(in crate::link::context) struct Database {
     pub contexts: HashSet<ContextId>,
 }
 
+#[derive(Doom)]
+pub enum ConnectDispatcherError {
+    #[doom(description("`ContextId` already registered: \"{}\"", context))]
+    AlreadyRegistered { context: ContextId },
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Failed to connect"))]
+    ConnectFailed,
+    #[doom(description("Unexpected response"))]
+    UnexpectedResponse,
+}
+
 impl ConnectDispatcher {
     pub fn new<C>(connector: C) -> Self
     where
@@ -34,15 +49,54 @@ impl ConnectDispatcher {
     }
 
     pub fn register(&self, context: ContextId) -> Connector {
-        if self
-            .database
-            .lock()
-            .contexts
-            .insert(context.clone())
-        {
-            Connector::new(context, self.connector.clone(), self.database.clone())
+        match self.try_register(context) {
+            Ok(connector) => connector,
+            Err(_) => panic!("called `register` twice for the same `context`"),
+        }
+    }
+
+    pub fn try_register(
+        &self,
+        context: ContextId,
+    ) -> Result<Connector, Top<ConnectDispatcherError>> {
+        if self.database.lock().contexts.insert(context.clone()) {
+            Ok(Connector::new(
+                context,
+                self.connector.clone(),
+                self.database.clone(),
+            ))
         } else {
-            panic!("called `register` twice for the same `context`");
+            ConnectDispatcherError::AlreadyRegistered { context }
+                .fail()
+                .spot(here!())
+        }
+    }
+
+    /// Queries `remote` for the contexts its `ListenDispatcher` serves.
+    pub async fn contexts(
+        &self,
+        remote: Identity,
+    ) -> Result<Vec<ContextId>, Top<ConnectDispatcherError>> {
+        let mut connection = self
+            .connector
+            .connect(remote)
+            .await
+            .pot(ConnectDispatcherError::ConnectFailed, here!())?;
+
+        connection
+            .send(&Request::Contexts)
+            .await
+            .pot(ConnectDispatcherError::ConnectionError, here!())?;
+
+        match connection
+            .receive()
+            .await
+            .pot(ConnectDispatcherError::ConnectionError, here!())?
+        {
+            Response::Contexts(contexts) => Ok(contexts),
+            _ => ConnectDispatcherError::UnexpectedResponse
+                .fail()
+                .spot(here!()),
         }
     }
 }
@@ -2,11 +2,11 @@ use async_trait::async_trait;
 
 use crate::{
     crypto::Identity,
-    link::context::{connect_dispatcher::Database, ContextId, Request, Response},
+    link::context::{connect_dispatcher::Database, ContextId, Request, Response, Version},
     net::{Connector as NetConnector, SecureConnection},
 };
 
-use doomstack::{here, Doom, ResultExt, Stack};
+use doomstack::{here, Doom, ResultExt, Stack, Top};
 
 use parking_lot::Mutex;
 
@@ -24,8 +24,27 @@ pub enum ConnectorError {
     ConnectionError,
     #[doom(description("Failed to connect"))]
     ConnectFailed,
+    #[doom(description("Context busy"))]
+    ContextBusy,
     #[doom(description("Context refused"))]
     ContextRefused,
+    #[doom(description("Unexpected response"))]
+    UnexpectedResponse,
+    #[doom(description("Version mismatch (available: {:?})", available))]
+    VersionMismatch { available: Vec<Version> },
+}
+
+impl ConnectorError {
+    /// Determines whether a failed `connect` might succeed if attempted again
+    /// later, e.g., because the remote `Listener` was momentarily busy.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ConnectorError::ConnectionError
+                | ConnectorError::ConnectFailed
+                | ConnectorError::ContextBusy
+        )
+    }
 }
 
 impl Connector {
@@ -40,11 +59,17 @@ impl Connector {
             database,
         }
     }
-}
 
-#[async_trait]
-impl NetConnector for Connector {
-    async fn connect(&self, remote: Identity) -> Result<SecureConnection, Stack> {
+    pub fn context(&self) -> &ContextId {
+        &self.context
+    }
+
+    /// Connects to `remote` like `connect`, additionally returning the
+    /// `Version` that `remote` picked to serve `self.context()`.
+    pub async fn negotiate(
+        &self,
+        remote: Identity,
+    ) -> Result<(Version, SecureConnection), Top<ConnectorError>> {
         let mut connection = self
             .connector
             .connect(remote)
@@ -61,15 +86,27 @@ impl NetConnector for Connector {
             .await
             .pot(ConnectorError::ConnectionError, here!())?
         {
-            Response::ContextAccepted => Ok(connection),
-            Response::ContextRefused => ConnectorError::ContextRefused
+            Response::ContextAccepted(version) => Ok((version, connection)),
+            Response::ContextRefused => ConnectorError::ContextRefused.fail().spot(here!()),
+            Response::ContextBusy => ConnectorError::ContextBusy.fail().spot(here!()),
+            Response::VersionMismatch(available) => ConnectorError::VersionMismatch { available }
                 .fail()
-                .spot(here!())
-                .map_err(Into::into),
+                .spot(here!()),
+            _ => ConnectorError::UnexpectedResponse.fail().spot(here!()),
         }
     }
 }
 
+#[async_trait]
+impl NetConnector for Connector {
+    async fn connect(&self, remote: Identity) -> Result<SecureConnection, Stack> {
+        self.negotiate(remote)
+            .await
+            .map(|(_, connection)| connection)
+            .map_err(Into::into)
+    }
+}
+
 impl Drop for Connector {
     fn drop(&mut self) {
         self.database.lock().contexts.remove(&self.context);
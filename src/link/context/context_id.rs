@@ -1 +1,44 @@
-pub type ContextId = String;
+use crate::link::context::Version;
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ContextId {
+    namespace: String,
+    version: Version,
+}
+
+impl ContextId {
+    pub fn new<N>(namespace: N, version: Version) -> Self
+    where
+        N: Into<String>,
+    {
+        ContextId {
+            namespace: namespace.into(),
+            version,
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        self.namespace.as_str()
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Determines whether `self` can serve a peer that requested `context`,
+    /// i.e., whether both share the same namespace and `self`'s `Version`
+    /// serves the requested `Version`.
+    pub fn serves(&self, context: &ContextId) -> bool {
+        self.namespace == context.namespace && self.version.serves(&context.version)
+    }
+}
+
+impl Display for ContextId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}@{}", self.namespace, self.version)
+    }
+}
@@ -12,9 +12,13 @@ use parking_lot::Mutex;
 use std::{
     collections::{hash_map::Entry, HashMap},
     sync::Arc,
+    time::Duration,
 };
 
-use tokio::sync::mpsc::{self, Sender};
+use tokio::{
+    sync::mpsc::{self, Permit, Sender},
+    time,
+};
 
 type Inlet = Sender<(Identity, SecureConnection)>;
 
@@ -28,6 +32,12 @@ pub(in crate::link::context) struct Database {
     pub inlets: HashMap<ContextId, Inlet>,
 }
 
+#[derive(Doom)]
+pub enum ListenDispatcherError {
+    #[doom(description("`ContextId` already registered: \"{}\"", context))]
+    AlreadyRegistered { context: ContextId },
+}
+
 #[derive(Doom)]
 enum ServeError {
     #[doom(description("Failed to receive context"))]
@@ -36,6 +46,18 @@ enum ServeError {
     SendFailed,
     #[doom(description("`ContextId` missing: \"{}\"", context))]
     MissingContext { context: ContextId },
+    #[doom(description("`ContextId` busy: \"{}\"", context))]
+    ContextBusy { context: ContextId },
+    #[doom(description("No version compatible with `ContextId`: \"{}\"", context))]
+    VersionMismatch { context: ContextId },
+}
+
+impl Database {
+    fn contexts(&self) -> Vec<ContextId> {
+        let mut contexts = self.inlets.keys().cloned().collect::<Vec<_>>();
+        contexts.sort();
+        contexts
+    }
 }
 
 impl ListenDispatcher {
@@ -51,9 +73,10 @@ impl ListenDispatcher {
 
         {
             let database = database.clone();
+            let delivery_timeout = settings.delivery_timeout;
 
             fuse.spawn(async move {
-                let _ = ListenDispatcher::listen(listener, database).await;
+                let _ = ListenDispatcher::listen(listener, database, delivery_timeout).await;
             });
         }
 
@@ -65,25 +88,44 @@ impl ListenDispatcher {
     }
 
     pub fn register(&self, context: ContextId) -> Listener {
-        let (inlet, outlet) = mpsc::channel(self.settings.channel_capacity);
+        match self.try_register(context) {
+            Ok(listener) => listener,
+            Err(_) => panic!("called `register` twice for the same `context`"),
+        }
+    }
 
-        let mut database = self.database.lock();
+    pub fn try_register(&self, context: ContextId) -> Result<Listener, Top<ListenDispatcherError>> {
+        let (inlet, outlet) = mpsc::channel(self.settings.channel_capacity);
 
-        match database.inlets.entry(context.clone()) {
+        match self.database.lock().inlets.entry(context.clone()) {
             Entry::Vacant(entry) => {
                 entry.insert(inlet);
             }
             Entry::Occupied(_) => {
-                drop(database);
-                panic!("called `register` twice for the same `context`")
+                return ListenDispatcherError::AlreadyRegistered { context }
+                    .fail()
+                    .spot(here!());
             }
         }
 
-        Listener::new(context, outlet, self.database.clone(), self.fuse.clone())
+        Ok(Listener::new(
+            context,
+            outlet,
+            self.database.clone(),
+            self.fuse.clone(),
+        ))
     }
 
-    async fn listen<L>(mut listener: L, database: Arc<Mutex<Database>>)
-    where
+    /// Lists the contexts currently registered, sorted by namespace and `Version`.
+    pub fn contexts(&self) -> Vec<ContextId> {
+        self.database.lock().contexts()
+    }
+
+    async fn listen<L>(
+        mut listener: L,
+        database: Arc<Mutex<Database>>,
+        delivery_timeout: Option<Duration>,
+    ) where
         L: NetListener,
     {
         let fuse = Fuse::new();
@@ -93,7 +135,8 @@ impl ListenDispatcher {
                 let database = database.clone();
 
                 fuse.spawn(async move {
-                    let _ = ListenDispatcher::serve(remote, connection, database).await;
+                    let _ = ListenDispatcher::serve(remote, connection, database, delivery_timeout)
+                        .await;
                 });
             }
         }
@@ -103,32 +146,75 @@ impl ListenDispatcher {
         remote: Identity,
         mut connection: SecureConnection,
         database: Arc<Mutex<Database>>,
+        delivery_timeout: Option<Duration>,
     ) -> Result<(), Top<ServeError>> {
-        let Request::Context(context) = connection
+        let context = match connection
             .receive()
             .await
-            .pot(ServeError::ReceiveFailed, here!())?;
-
-        let inlet = database
-            .lock()
-            .inlets
-            .get(&context)
-            .map(Clone::clone);
+            .pot(ServeError::ReceiveFailed, here!())?
+        {
+            Request::Context(context) => context,
+            Request::Contexts => {
+                let contexts = database.lock().contexts();
 
-        match inlet {
-            Some(inlet) => {
                 connection
-                    .send(&Response::ContextAccepted)
+                    .send(&Response::Contexts(contexts))
                     .await
                     .pot(ServeError::SendFailed, here!())?;
 
-                // This can only fail if the (local) receiving end is
-                // dropped, in which case we don't care about the error
-                let _ = inlet.try_send((remote, connection));
-
-                Ok(())
+                return Ok(());
             }
-            None => {
+        };
+
+        // Among the `Listener`s registered under the requested namespace, the
+        // one with the highest `Version` that serves the request is picked
+        let (negotiated, available) = {
+            let database = database.lock();
+
+            let negotiated = database
+                .inlets
+                .iter()
+                .filter(|(candidate, _)| candidate.serves(&context))
+                .max_by_key(|(candidate, _)| candidate.version())
+                .map(|(candidate, inlet)| (candidate.version(), inlet.clone()));
+
+            let available = database
+                .inlets
+                .keys()
+                .filter(|candidate| candidate.namespace() == context.namespace())
+                .map(ContextId::version)
+                .collect::<Vec<_>>();
+
+            (negotiated, available)
+        };
+
+        match negotiated {
+            Some((version, inlet)) => {
+                // Room for `connection` is reserved before accepting: if the
+                // `Listener` is not keeping up, the connector is told to retry
+                // later instead of having its connection silently dropped
+                match ListenDispatcher::reserve(&inlet, delivery_timeout).await {
+                    Some(permit) => {
+                        connection
+                            .send(&Response::ContextAccepted(version))
+                            .await
+                            .pot(ServeError::SendFailed, here!())?;
+
+                        permit.send((remote, connection));
+
+                        Ok(())
+                    }
+                    None => {
+                        connection
+                            .send(&Response::ContextBusy)
+                            .await
+                            .pot(ServeError::SendFailed, here!())?;
+
+                        ServeError::ContextBusy { context }.fail().spot(here!())
+                    }
+                }
+            }
+            None if available.is_empty() => {
                 connection
                     .send(&Response::ContextRefused)
                     .await
@@ -136,6 +222,29 @@ impl ListenDispatcher {
 
                 ServeError::MissingContext { context }.fail().spot(here!())
             }
+            None => {
+                connection
+                    .send(&Response::VersionMismatch(available))
+                    .await
+                    .pot(ServeError::SendFailed, here!())?;
+
+                ServeError::VersionMismatch { context }.fail().spot(here!())
+            }
+        }
+    }
+
+    // Returns `None` if `inlet` is full (after waiting up to `delivery_timeout`,
+    // if provided) or if the corresponding `Listener` was dropped in the meantime
+    async fn reserve(
+        inlet: &Inlet,
+        delivery_timeout: Option<Duration>,
+    ) -> Option<Permit<'_, (Identity, SecureConnection)>> {
+        match delivery_timeout {
+            Some(delivery_timeout) => time::timeout(delivery_timeout, inlet.reserve())
+                .await
+                .ok()
+                .and_then(Result::ok),
+            None => inlet.try_reserve().ok(),
         }
     }
 }
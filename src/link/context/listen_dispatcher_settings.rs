@@ -1,12 +1,16 @@
+use std::time::Duration;
+
 #[derive(Debug, Clone)]
 pub struct ListenDispatcherSettings {
     pub channel_capacity: usize,
+    pub delivery_timeout: Option<Duration>,
 }
 
 impl Default for ListenDispatcherSettings {
     fn default() -> Self {
         ListenDispatcherSettings {
             channel_capacity: 32,
+            delivery_timeout: None,
         }
     }
 }
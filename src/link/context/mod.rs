@@ -6,13 +6,15 @@ mod listen_dispatcher_settings;
 mod listener;
 mod request;
 mod response;
+mod version;
 
 use request::Request;
 use response::Response;
 
-pub use connect_dispatcher::ConnectDispatcher;
+pub use connect_dispatcher::{ConnectDispatcher, ConnectDispatcherError};
 pub use connector::{Connector, ConnectorError};
 pub use context_id::ContextId;
-pub use listen_dispatcher::ListenDispatcher;
+pub use listen_dispatcher::{ListenDispatcher, ListenDispatcherError};
 pub use listen_dispatcher_settings::ListenDispatcherSettings;
 pub use listener::Listener;
+pub use version::Version;
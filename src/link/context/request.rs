@@ -6,4 +6,5 @@ use serde::{Deserialize, Serialize};
 #[repr(u8)]
 pub(in crate::link::context) enum Request {
     Context(ContextId),
+    Contexts,
 }
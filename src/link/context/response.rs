@@ -1,8 +1,14 @@
+use crate::link::context::{ContextId, Version};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub(in crate::link::context) enum Response {
-    ContextAccepted,
+    ContextAccepted(Version),
     ContextRefused,
+    ContextBusy,
+    VersionMismatch(Vec<Version>),
+
+    Contexts(Vec<ContextId>),
 }
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Determines whether `self` can serve a peer that `requested` a
+    /// `Version`. Following semantic versioning, `self` must be at least
+    /// as recent as `requested` and share its major version (or, for
+    /// `0.x` versions, its minor version).
+    pub fn serves(&self, requested: &Version) -> bool {
+        let same_series = if self.major == 0 {
+            requested.major == 0 && self.minor == requested.minor
+        } else {
+            self.major == requested.major
+        };
+
+        same_series && self >= requested
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves() {
+        let requested = Version::new(1, 2, 3);
+
+        assert!(Version::new(1, 2, 3).serves(&requested));
+        assert!(Version::new(1, 2, 4).serves(&requested));
+        assert!(Version::new(1, 5, 0).serves(&requested));
+
+        assert!(!Version::new(1, 2, 2).serves(&requested));
+        assert!(!Version::new(1, 1, 9).serves(&requested));
+        assert!(!Version::new(2, 0, 0).serves(&requested));
+
+        let requested = Version::new(0, 2, 0);
+
+        assert!(Version::new(0, 2, 7).serves(&requested));
+        assert!(!Version::new(0, 3, 0).serves(&requested));
+        assert!(!Version::new(1, 2, 0).serves(&requested));
+    }
+}
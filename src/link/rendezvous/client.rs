@@ -1,15 +1,32 @@
 use crate::{
-    crypto::{Identity, KeyCard},
-    link::rendezvous::{ClientSettings, Request, Response, ShardId},
+    crypto::{Identity, KeyCard, KeyChain},
+    link::rendezvous::{ClientSettings, Command, Order, Request, Response, ShardId, ShardInfo},
     net::traits::TcpConnect,
+    sync::fuse::{Fuse, Relay},
+    time,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::{io, net::SocketAddr, vec::Vec};
+use std::{
+    collections::HashMap,
+    io, iter,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    vec::Vec,
+};
+
+use tokio::task::JoinHandle;
+
+type ClientHandle<T> = JoinHandle<Option<Result<T, Top<ClientError>>>>;
 
+#[derive(Clone)]
 pub struct Client {
-    server: Box<dyn TcpConnect>,
+    servers: Arc<Vec<Box<dyn TcpConnect>>>,
+    preferred: Arc<AtomicUsize>,
     settings: ClientSettings,
 }
 
@@ -19,18 +36,34 @@ pub enum ClientError {
     AddressUnknown,
     #[doom(description("Card is already published (shard: {:?})", shard))]
     AlreadyPublished { shard: Option<ShardId> },
+    #[doom(description("Attempts exhausted (attempts: {})", attempts))]
+    AttemptsExhausted { attempts: u32 },
     #[doom(description("Card unknown"))]
     CardUnknown,
+    #[doom(description("Command was already executed"))]
+    CommandReplayed,
+    #[doom(description("Deadline exceeded"))]
+    DeadlineExceeded,
+    #[doom(description("Shard is closed"))]
+    ShardClosed,
     #[doom(description("Shard is full"))]
     ShardFull,
     #[doom(description("Shard ID is invalid"))]
     ShardIdInvalid,
     #[doom(description("Shard is incomplete"))]
     ShardIncomplete,
+    #[doom(description("Shard size is invalid"))]
+    ShardSizeInvalid,
+    #[doom(description("Command is not authorized"))]
+    Unauthorized,
+    #[doom(description("Unexpected response to `{}`", request))]
+    UnexpectedResponse { request: &'static str },
 }
 
 #[derive(Doom)]
 enum AttemptError {
+    #[doom(description("Attempt timed out"))]
+    AttemptTimeout,
     #[doom(description("Failed to `connect`: {}", source))]
     #[doom(wrap(connect_failed))]
     ConnectFailed { source: io::Error },
@@ -43,8 +76,32 @@ impl Client {
     where
         S: 'static + TcpConnect,
     {
+        Client::with_servers(iter::once(server), settings)
+    }
+
+    /// Builds a `Client` that tries each of `servers` in turn, starting
+    /// from the last server that successfully responded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `servers` is empty.
+    pub fn with_servers<S, I>(servers: I, settings: ClientSettings) -> Self
+    where
+        S: 'static + TcpConnect,
+        I: IntoIterator<Item = S>,
+    {
+        let servers = servers
+            .into_iter()
+            .map(|server| Box::new(server) as Box<dyn TcpConnect>)
+            .collect::<Vec<_>>();
+
+        if servers.is_empty() {
+            panic!("called `Client::with_servers` without any server");
+        }
+
         Client {
-            server: Box::new(server),
+            servers: Arc::new(servers),
+            preferred: Arc::new(AtomicUsize::new(0)),
             settings,
         }
     }
@@ -54,72 +111,452 @@ impl Client {
         card: KeyCard,
         shard: Option<ShardId>,
     ) -> Result<(), Top<ClientError>> {
-        match self.perform(&Request::PublishCard(card, shard)).await {
+        match self.perform(&Request::PublishCard(card, shard)).await? {
             Response::AcknowledgeCard => Ok(()),
             Response::AlreadyPublished(shard) => {
                 ClientError::AlreadyPublished { shard }.fail().spot(here!())
             }
             Response::ShardIdInvalid => ClientError::ShardIdInvalid.fail().spot(here!()),
             Response::ShardFull => ClientError::ShardFull.fail().spot(here!()),
-            response => {
-                panic!("unexpected response to `publish_card`: {:?}", response)
+            Response::ShardClosed => ClientError::ShardClosed.fail().spot(here!()),
+            Response::CardUnknown => ClientError::CardUnknown.fail().spot(here!()),
+            _ => ClientError::UnexpectedResponse {
+                request: "publish_card",
             }
+            .fail()
+            .spot(here!()),
         }
     }
 
-    pub async fn advertise_port(&self, identity: Identity, port: u16) {
-        match self.perform(&Request::AdvertisePort(identity, port)).await {
-            Response::AcknowledgePort => (),
-            response => panic!("unexpected response to `advertise_port`: {:?}", response),
+    pub fn run_publish_card(
+        &self,
+        card: KeyCard,
+        shard: Option<ShardId>,
+        relay: Relay,
+    ) -> ClientHandle<()> {
+        let client = self.clone();
+        relay.run(async move { client.publish_card(card, shard).await })
+    }
+
+    pub fn spawn_publish_card(
+        &self,
+        card: KeyCard,
+        shard: Option<ShardId>,
+        fuse: &Fuse,
+    ) -> ClientHandle<()> {
+        self.run_publish_card(card, shard, fuse.relay())
+    }
+
+    pub async fn advertise_port(
+        &self,
+        identity: Identity,
+        port: u16,
+    ) -> Result<(), Top<ClientError>> {
+        match self
+            .perform(&Request::AdvertisePort(identity, port))
+            .await?
+        {
+            Response::AcknowledgePort => Ok(()),
+            _ => ClientError::UnexpectedResponse {
+                request: "advertise_port",
+            }
+            .fail()
+            .spot(here!()),
         }
     }
 
+    pub fn run_advertise_port(
+        &self,
+        identity: Identity,
+        port: u16,
+        relay: Relay,
+    ) -> ClientHandle<()> {
+        let client = self.clone();
+        relay.run(async move { client.advertise_port(identity, port).await })
+    }
+
+    pub fn spawn_advertise_port(
+        &self,
+        identity: Identity,
+        port: u16,
+        fuse: &Fuse,
+    ) -> ClientHandle<()> {
+        self.run_advertise_port(identity, port, fuse.relay())
+    }
+
     pub async fn get_shard(&self, shard: ShardId) -> Result<Vec<KeyCard>, Top<ClientError>> {
-        match self.perform(&Request::GetShard(shard)).await {
+        match self.perform(&Request::GetShard(shard)).await? {
             Response::Shard(shard) => Ok(shard),
             Response::ShardIdInvalid => ClientError::ShardIdInvalid.fail().spot(here!()),
             Response::ShardIncomplete => ClientError::ShardIncomplete.fail().spot(here!()),
-            response => {
-                panic!("unexpected response to `get_shard`: {:?}", response)
+            _ => ClientError::UnexpectedResponse {
+                request: "get_shard",
             }
+            .fail()
+            .spot(here!()),
         }
     }
 
+    pub fn run_get_shard(&self, shard: ShardId, relay: Relay) -> ClientHandle<Vec<KeyCard>> {
+        let client = self.clone();
+        relay.run(async move { client.get_shard(shard).await })
+    }
+
+    pub fn spawn_get_shard(&self, shard: ShardId, fuse: &Fuse) -> ClientHandle<Vec<KeyCard>> {
+        self.run_get_shard(shard, fuse.relay())
+    }
+
     pub async fn get_card(&self, identity: Identity) -> Result<KeyCard, Top<ClientError>> {
-        match self.perform(&Request::GetCard(identity)).await {
+        match self.perform(&Request::GetCard(identity)).await? {
             Response::Card(card) => Ok(card),
             Response::CardUnknown => ClientError::CardUnknown.fail().spot(here!()),
-            response => {
-                panic!("unexpected response to `get_card`: {:?}", response)
+            _ => ClientError::UnexpectedResponse {
+                request: "get_card",
             }
+            .fail()
+            .spot(here!()),
         }
     }
 
+    pub fn run_get_card(&self, identity: Identity, relay: Relay) -> ClientHandle<KeyCard> {
+        let client = self.clone();
+        relay.run(async move { client.get_card(identity).await })
+    }
+
+    pub fn spawn_get_card(&self, identity: Identity, fuse: &Fuse) -> ClientHandle<KeyCard> {
+        self.run_get_card(identity, fuse.relay())
+    }
+
     pub async fn get_address(&self, identity: Identity) -> Result<SocketAddr, Top<ClientError>> {
-        match self.perform(&Request::GetAddress(identity)).await {
+        match self.perform(&Request::GetAddress(identity)).await? {
             Response::Address(address) => Ok(address),
             Response::AddressUnknown => ClientError::AddressUnknown.fail().spot(here!()),
-            response => {
-                panic!("unexpected response to `get_address`: {:?}", response)
+            _ => ClientError::UnexpectedResponse {
+                request: "get_address",
             }
+            .fail()
+            .spot(here!()),
+        }
+    }
+
+    pub fn run_get_address(&self, identity: Identity, relay: Relay) -> ClientHandle<SocketAddr> {
+        let client = self.clone();
+        relay.run(async move { client.get_address(identity).await })
+    }
+
+    pub fn spawn_get_address(&self, identity: Identity, fuse: &Fuse) -> ClientHandle<SocketAddr> {
+        self.run_get_address(identity, fuse.relay())
+    }
+
+    pub async fn get_shards(
+        &self,
+        shards: Vec<ShardId>,
+    ) -> Result<Vec<Vec<KeyCard>>, Top<ClientError>> {
+        match self.perform(&Request::GetShards(shards)).await? {
+            Response::Shards(shards) => Ok(shards),
+            Response::ShardIdInvalid => ClientError::ShardIdInvalid.fail().spot(here!()),
+            Response::ShardIncomplete => ClientError::ShardIncomplete.fail().spot(here!()),
+            _ => ClientError::UnexpectedResponse {
+                request: "get_shards",
+            }
+            .fail()
+            .spot(here!()),
+        }
+    }
+
+    pub fn run_get_shards(
+        &self,
+        shards: Vec<ShardId>,
+        relay: Relay,
+    ) -> ClientHandle<Vec<Vec<KeyCard>>> {
+        let client = self.clone();
+        relay.run(async move { client.get_shards(shards).await })
+    }
+
+    pub fn spawn_get_shards(
+        &self,
+        shards: Vec<ShardId>,
+        fuse: &Fuse,
+    ) -> ClientHandle<Vec<Vec<KeyCard>>> {
+        self.run_get_shards(shards, fuse.relay())
+    }
+
+    pub async fn get_cards(
+        &self,
+        identities: Vec<Identity>,
+    ) -> Result<HashMap<Identity, KeyCard>, Top<ClientError>> {
+        match self.perform(&Request::GetCards(identities)).await? {
+            Response::Cards(cards) => Ok(cards
+                .into_iter()
+                .map(|card| (card.identity(), card))
+                .collect()),
+            _ => ClientError::UnexpectedResponse {
+                request: "get_cards",
+            }
+            .fail()
+            .spot(here!()),
+        }
+    }
+
+    pub fn run_get_cards(
+        &self,
+        identities: Vec<Identity>,
+        relay: Relay,
+    ) -> ClientHandle<HashMap<Identity, KeyCard>> {
+        let client = self.clone();
+        relay.run(async move { client.get_cards(identities).await })
+    }
+
+    pub fn spawn_get_cards(
+        &self,
+        identities: Vec<Identity>,
+        fuse: &Fuse,
+    ) -> ClientHandle<HashMap<Identity, KeyCard>> {
+        self.run_get_cards(identities, fuse.relay())
+    }
+
+    pub async fn get_addresses(
+        &self,
+        identities: Vec<Identity>,
+    ) -> Result<HashMap<Identity, SocketAddr>, Top<ClientError>> {
+        match self.perform(&Request::GetAddresses(identities)).await? {
+            Response::Addresses(addresses) => Ok(addresses.into_iter().collect()),
+            _ => ClientError::UnexpectedResponse {
+                request: "get_addresses",
+            }
+            .fail()
+            .spot(here!()),
+        }
+    }
+
+    pub fn run_get_addresses(
+        &self,
+        identities: Vec<Identity>,
+        relay: Relay,
+    ) -> ClientHandle<HashMap<Identity, SocketAddr>> {
+        let client = self.clone();
+        relay.run(async move { client.get_addresses(identities).await })
+    }
+
+    pub fn spawn_get_addresses(
+        &self,
+        identities: Vec<Identity>,
+        fuse: &Fuse,
+    ) -> ClientHandle<HashMap<Identity, SocketAddr>> {
+        self.run_get_addresses(identities, fuse.relay())
+    }
+
+    pub async fn list_shards(&self) -> Result<Vec<ShardInfo>, Top<ClientError>> {
+        match self.perform(&Request::ListShards).await? {
+            Response::ShardList(shards) => Ok(shards),
+            _ => ClientError::UnexpectedResponse {
+                request: "list_shards",
+            }
+            .fail()
+            .spot(here!()),
+        }
+    }
+
+    pub fn run_list_shards(&self, relay: Relay) -> ClientHandle<Vec<ShardInfo>> {
+        let client = self.clone();
+        relay.run(async move { client.list_shards().await })
+    }
+
+    pub fn spawn_list_shards(&self, fuse: &Fuse) -> ClientHandle<Vec<ShardInfo>> {
+        self.run_list_shards(fuse.relay())
+    }
+
+    pub async fn create_shard(
+        &self,
+        operator: &KeyChain,
+        size: usize,
+    ) -> Result<ShardId, Top<ClientError>> {
+        match self.command(operator, Order::CreateShard(size)).await? {
+            Response::ShardCreated(shard) => Ok(shard),
+            response => Client::command_failed(response, "create_shard"),
+        }
+    }
+
+    pub fn run_create_shard(
+        &self,
+        operator: KeyChain,
+        size: usize,
+        relay: Relay,
+    ) -> ClientHandle<ShardId> {
+        let client = self.clone();
+        relay.run(async move { client.create_shard(&operator, size).await })
+    }
+
+    pub fn spawn_create_shard(
+        &self,
+        operator: KeyChain,
+        size: usize,
+        fuse: &Fuse,
+    ) -> ClientHandle<ShardId> {
+        self.run_create_shard(operator, size, fuse.relay())
+    }
+
+    pub async fn resize_shard(
+        &self,
+        operator: &KeyChain,
+        shard: ShardId,
+        size: usize,
+    ) -> Result<(), Top<ClientError>> {
+        match self
+            .command(operator, Order::ResizeShard(shard, size))
+            .await?
+        {
+            Response::AcknowledgeCommand => Ok(()),
+            response => Client::command_failed(response, "resize_shard"),
+        }
+    }
+
+    pub fn run_resize_shard(
+        &self,
+        operator: KeyChain,
+        shard: ShardId,
+        size: usize,
+        relay: Relay,
+    ) -> ClientHandle<()> {
+        let client = self.clone();
+        relay.run(async move { client.resize_shard(&operator, shard, size).await })
+    }
+
+    pub fn spawn_resize_shard(
+        &self,
+        operator: KeyChain,
+        shard: ShardId,
+        size: usize,
+        fuse: &Fuse,
+    ) -> ClientHandle<()> {
+        self.run_resize_shard(operator, shard, size, fuse.relay())
+    }
+
+    pub async fn close_shard(
+        &self,
+        operator: &KeyChain,
+        shard: ShardId,
+    ) -> Result<(), Top<ClientError>> {
+        match self.command(operator, Order::CloseShard(shard)).await? {
+            Response::AcknowledgeCommand => Ok(()),
+            response => Client::command_failed(response, "close_shard"),
+        }
+    }
+
+    pub fn run_close_shard(
+        &self,
+        operator: KeyChain,
+        shard: ShardId,
+        relay: Relay,
+    ) -> ClientHandle<()> {
+        let client = self.clone();
+        relay.run(async move { client.close_shard(&operator, shard).await })
+    }
+
+    pub fn spawn_close_shard(
+        &self,
+        operator: KeyChain,
+        shard: ShardId,
+        fuse: &Fuse,
+    ) -> ClientHandle<()> {
+        self.run_close_shard(operator, shard, fuse.relay())
+    }
+
+    async fn command(
+        &self,
+        operator: &KeyChain,
+        order: Order,
+    ) -> Result<Response, Top<ClientError>> {
+        let command = Command::new(order);
+
+        // `Command` is a plain, serializable `struct`: signing cannot fail
+        let signature = operator.sign(&command).unwrap();
+
+        self.perform(&Request::Command(command, signature)).await
+    }
+
+    fn command_failed<T>(response: Response, request: &'static str) -> Result<T, Top<ClientError>> {
+        match response {
+            Response::Unauthorized => ClientError::Unauthorized.fail().spot(here!()),
+            Response::CommandReplayed => ClientError::CommandReplayed.fail().spot(here!()),
+            Response::ShardIdInvalid => ClientError::ShardIdInvalid.fail().spot(here!()),
+            Response::ShardClosed => ClientError::ShardClosed.fail().spot(here!()),
+            Response::ShardSizeInvalid => ClientError::ShardSizeInvalid.fail().spot(here!()),
+            _ => ClientError::UnexpectedResponse { request }
+                .fail()
+                .spot(here!()),
         }
     }
 
-    async fn perform(&self, request: &Request) -> Response {
+    async fn perform(&self, request: &Request) -> Result<Response, Top<ClientError>> {
+        time::optional_timeout(self.settings.deadline, self.retry(request))
+            .await
+            .pot(ClientError::DeadlineExceeded, here!())?
+    }
+
+    async fn retry(&self, request: &Request) -> Result<Response, Top<ClientError>> {
+        let exhausted = |attempts| {
+            self.settings
+                .max_attempts
+                .map_or(false, |max_attempts| attempts >= max_attempts)
+        };
+
         let mut sleep_agent = self.settings.sleep_schedule.agent();
+        let mut attempts = 0;
 
         loop {
-            if let Ok(response) = self.attempt(&request).await {
-                return response;
+            // Servers are tried in a round-robin fashion, starting
+            // from the last server that successfully responded
+            let preferred = self.preferred.load(Ordering::Relaxed);
+
+            for offset in 0..self.servers.len() {
+                if exhausted(attempts) {
+                    return ClientError::AttemptsExhausted { attempts }
+                        .fail()
+                        .spot(here!());
+                }
+
+                attempts += 1;
+
+                let index = (preferred + offset) % self.servers.len();
+
+                match self.attempt(index, request).await {
+                    // Shard writes are only served by the primary replica
+                    Ok(Response::NotPrimary) | Err(_) => {}
+                    Ok(response) => {
+                        self.preferred.store(index, Ordering::Relaxed);
+                        return Ok(response);
+                    }
+                }
+            }
+
+            // Checked here as well, in order not to sleep after the last attempt
+            if exhausted(attempts) {
+                return ClientError::AttemptsExhausted { attempts }
+                    .fail()
+                    .spot(here!());
             }
 
             sleep_agent.step().await;
         }
     }
 
-    async fn attempt(&self, request: &Request) -> Result<Response, Top<AttemptError>> {
-        let mut connection = self
-            .server
+    async fn attempt(
+        &self,
+        index: usize,
+        request: &Request,
+    ) -> Result<Response, Top<AttemptError>> {
+        time::optional_timeout(self.settings.attempt_timeout, self.exchange(index, request))
+            .await
+            .pot(AttemptError::AttemptTimeout, here!())?
+    }
+
+    async fn exchange(
+        &self,
+        index: usize,
+        request: &Request,
+    ) -> Result<Response, Top<AttemptError>> {
+        let mut connection = self.servers[index]
             .connect()
             .await
             .map_err(AttemptError::connect_failed)
@@ -127,7 +564,7 @@ impl Client {
             .spot(here!())?;
 
         connection
-            .send(&request)
+            .send(request)
             .await
             .pot(AttemptError::ConnectionError, here!())?;
 
@@ -146,17 +583,28 @@ mod tests {
 
     use crate::{
         crypto::KeyChain,
-        link::rendezvous::{Server, ServerSettings},
+        link::rendezvous::{Database, Server, ServerSettings},
+        net::test::TcpProxy,
+        time::sleep_schedules::Constant,
     };
 
     use std::time::Duration;
 
-    use tokio::time;
+    use tokio::{
+        net::TcpListener,
+        time::{self, Instant},
+    };
 
     async fn setup_server(address: &'static str, shard_sizes: Vec<usize>) -> Server {
-        Server::new(address, ServerSettings { shard_sizes })
-            .await
-            .unwrap()
+        Server::new(
+            address,
+            ServerSettings {
+                shard_sizes,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
     }
 
     async fn setup_clients(
@@ -206,26 +654,18 @@ mod tests {
         clients: Vec<Client>,
     ) {
         for j in 0..clients.len() {
-            for c in 0..clients.len() {
-                match clients[c].get_shard(0).await.unwrap_err().top() {
+            for client in clients.iter() {
+                match client.get_shard(0).await.unwrap_err().top() {
                     ClientError::ShardIncomplete => (),
                     error => panic!("unexpected error upon querying shard: {}", error),
                 }
 
                 for d in 0..j {
-                    assert_eq!(
-                        clients[c].get_card(identities[d].clone()).await.unwrap(),
-                        keycards[d]
-                    );
+                    assert_eq!(client.get_card(identities[d]).await.unwrap(), keycards[d]);
                 }
 
-                for d in j..clients.len() {
-                    match clients[c]
-                        .get_card(identities[d].clone())
-                        .await
-                        .unwrap_err()
-                        .top()
-                    {
+                for identity in &identities[j..] {
+                    match client.get_card(*identity).await.unwrap_err().top() {
                         ClientError::CardUnknown => (),
                         error => panic!("unexpected error upon querying card: {}", error),
                     }
@@ -246,7 +686,7 @@ mod tests {
 
             for d in 0..clients.len() {
                 assert_eq!(
-                    clients[c].get_card(identities[d].clone()).await.unwrap(),
+                    clients[c].get_card(identities[d]).await.unwrap(),
                     keycards[d]
                 );
             }
@@ -400,4 +840,660 @@ mod tests {
             ),
         }
     }
+
+    const SYNC_INTERVAL: Duration = Duration::from_millis(100);
+
+    fn replica_settings(
+        shard_sizes: Vec<usize>,
+        keychain: &KeyChain,
+        peers: Vec<(SocketAddr, &KeyChain)>,
+    ) -> ServerSettings {
+        ServerSettings {
+            shard_sizes,
+            keychain: keychain.clone(),
+            peers: peers
+                .into_iter()
+                .map(|(address, keychain)| (address, keychain.keycard()))
+                .collect(),
+            sync_interval: SYNC_INTERVAL,
+            ..Default::default()
+        }
+    }
+
+    // Replicas must know each other's address before serving: each binds
+    // to port 0 first, then serves on its listener (see `Server::from_listener`)
+    async fn bind() -> (TcpListener, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        (listener, address)
+    }
+
+    async fn wait_for_card(client: &Client, keycard: &KeyCard) {
+        while client.get_card(keycard.identity()).await.ok().as_ref() != Some(keycard) {
+            time::sleep(SYNC_INTERVAL).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn replicated_failover() {
+        let (alpha_listener, alpha_address) = bind().await;
+        let (beta_listener, beta_address) = bind().await;
+
+        let alpha_keychain = KeyChain::random();
+        let beta_keychain = KeyChain::random();
+
+        let alpha = Server::from_listener(
+            alpha_listener,
+            replica_settings(
+                vec![3],
+                &alpha_keychain,
+                vec![(beta_address, &beta_keychain)],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let _beta = Server::from_listener(
+            beta_listener,
+            replica_settings(
+                vec![3],
+                &beta_keychain,
+                vec![(alpha_address, &alpha_keychain)],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let keycards = (0..3)
+            .map(|_| KeyChain::random().keycard())
+            .collect::<Vec<_>>();
+
+        let client = Client::with_servers(vec![alpha_address, beta_address], Default::default());
+        let beta_client = Client::new(beta_address, Default::default());
+
+        for keycard in keycards.iter() {
+            client.publish_card(keycard.clone(), Some(0)).await.unwrap();
+        }
+
+        for keycard in keycards.iter() {
+            wait_for_card(&beta_client, keycard).await;
+        }
+
+        drop(alpha);
+
+        // `alpha` stops listening once its tasks are aborted
+        while alpha_address.connect().await.is_ok() {
+            time::sleep(SYNC_INTERVAL).await;
+        }
+
+        let shard = client.get_shard(0).await.unwrap();
+
+        assert!(shard.iter().all(|keycard| keycards.contains(keycard)));
+        assert!(keycards.iter().all(|keycard| shard.contains(keycard)));
+
+        let keycard = KeyChain::random().keycard();
+
+        client.publish_card(keycard.clone(), None).await.unwrap();
+
+        assert_eq!(client.get_card(keycard.identity()).await.unwrap(), keycard);
+    }
+
+    #[tokio::test]
+    async fn replicated_partition() {
+        let (alpha_listener, alpha_address) = bind().await;
+        let (beta_listener, beta_address) = bind().await;
+
+        let mut proxy = TcpProxy::new(beta_address).await;
+
+        let alpha_keychain = KeyChain::random();
+        let beta_keychain = KeyChain::random();
+
+        // `alpha` reaches `beta` only through `proxy`
+        let _alpha = Server::from_listener(
+            alpha_listener,
+            ServerSettings {
+                peers: vec![(proxy.address(), beta_keychain.keycard())],
+                ..replica_settings(vec![3], &alpha_keychain, vec![])
+            },
+        )
+        .await
+        .unwrap();
+
+        let _beta = Server::from_listener(
+            beta_listener,
+            replica_settings(
+                vec![3],
+                &beta_keychain,
+                vec![(alpha_address, &alpha_keychain)],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let alpha_client = Client::new(alpha_address, Default::default());
+        let beta_client = Client::new(beta_address, Default::default());
+
+        let before = KeyChain::random().keycard();
+        let during = KeyChain::random().keycard();
+
+        alpha_client
+            .publish_card(before.clone(), None)
+            .await
+            .unwrap();
+
+        wait_for_card(&beta_client, &before).await;
+
+        proxy.stop().await;
+
+        alpha_client
+            .publish_card(during.clone(), None)
+            .await
+            .unwrap();
+
+        // The absence of replication can only be observed over time:
+        // `alpha` gets several chances to synchronize in the meantime
+        time::sleep(5 * SYNC_INTERVAL).await;
+
+        match beta_client
+            .get_card(during.identity())
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::CardUnknown => (),
+            error => panic!("unexpected error upon querying partitioned card: {}", error),
+        }
+
+        proxy.start().await;
+
+        wait_for_card(&beta_client, &during).await;
+    }
+
+    #[tokio::test]
+    async fn replicated_shard_writes() {
+        let (alpha_listener, alpha_address) = bind().await;
+        let (beta_listener, beta_address) = bind().await;
+
+        let alpha_keychain = KeyChain::random();
+        let beta_keychain = KeyChain::random();
+
+        let operator = KeyChain::random();
+
+        let _alpha = Server::from_listener(
+            alpha_listener,
+            ServerSettings {
+                operator: Some(operator.keycard()),
+                ..replica_settings(
+                    Vec::new(),
+                    &alpha_keychain,
+                    vec![(beta_address, &beta_keychain)],
+                )
+            },
+        )
+        .await
+        .unwrap();
+
+        let _beta = Server::from_listener(
+            beta_listener,
+            ServerSettings {
+                operator: Some(operator.keycard()),
+                ..replica_settings(
+                    Vec::new(),
+                    &beta_keychain,
+                    vec![(alpha_address, &alpha_keychain)],
+                )
+            },
+        )
+        .await
+        .unwrap();
+
+        // Each client starts from a different replica
+        let alpha_client =
+            Client::with_servers(vec![alpha_address, beta_address], Default::default());
+        let beta_client =
+            Client::with_servers(vec![beta_address, alpha_address], Default::default());
+
+        // Both shards are created before the replicas get to synchronize
+        assert_eq!(alpha_client.create_shard(&operator, 2).await.unwrap(), 0);
+        assert_eq!(beta_client.create_shard(&operator, 2).await.unwrap(), 1);
+
+        let keycards = (0..4)
+            .map(|_| KeyChain::random().keycard())
+            .collect::<Vec<_>>();
+
+        let publications = keycards
+            .iter()
+            .enumerate()
+            .map(|(index, keycard)| {
+                let client = if index % 2 == 0 {
+                    alpha_client.clone()
+                } else {
+                    beta_client.clone()
+                };
+
+                let keycard = keycard.clone();
+
+                tokio::spawn(async move { client.publish_card(keycard, Some(0)).await.is_ok() })
+            })
+            .collect::<Vec<_>>();
+
+        let mut acknowledged = Vec::new();
+
+        for (keycard, publication) in keycards.iter().zip(publications) {
+            if publication.await.unwrap() {
+                acknowledged.push(keycard.clone());
+            }
+        }
+
+        assert_eq!(acknowledged.len(), 2);
+
+        // Every acknowledged card stays in the shard, on every replica
+        for client in [&alpha_client, &beta_client].iter() {
+            for keycard in acknowledged.iter() {
+                wait_for_card(client, keycard).await;
+            }
+
+            time::sleep(5 * SYNC_INTERVAL).await;
+
+            let mut shard = client.get_shard(0).await.unwrap();
+            shard.sort_by_key(KeyCard::identity);
+            acknowledged.sort_by_key(KeyCard::identity);
+
+            assert_eq!(shard, acknowledged);
+            assert_eq!(client.list_shards().await.unwrap().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn unauthorized_merge() {
+        const ADDRESS: &str = "127.0.0.1:1257";
+
+        let _server = setup_server(ADDRESS, vec![2]).await;
+        let address: SocketAddr = ADDRESS.parse().unwrap();
+
+        // A snapshot that sneaks a card into shard 0
+        let keycard = KeyChain::random().keycard();
+        let mut snapshot = Database::new(&[2]);
+
+        snapshot.shards[0].members.insert(keycard.identity());
+        snapshot.membership.insert(keycard.identity(), Some(0));
+        snapshot.cards.insert(keycard.identity(), keycard.clone());
+
+        let mut connection = address.connect().await.unwrap();
+
+        connection
+            .send(&Request::Merge(snapshot.clone()))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            connection.receive::<Response>().await.unwrap(),
+            Response::Unauthorized
+        ));
+
+        let mut connection = address.connect().await.unwrap();
+        connection.send(&Request::Replicate).await.unwrap();
+
+        let mut connection = connection.secure().await.unwrap();
+        connection.authenticate(&KeyChain::random()).await.unwrap();
+
+        connection.send(&Request::Merge(snapshot)).await.unwrap();
+
+        assert!(matches!(
+            connection.receive::<Response>().await.unwrap(),
+            Response::Unauthorized
+        ));
+
+        let client = Client::new(ADDRESS, Default::default());
+
+        match client.get_card(keycard.identity()).await.unwrap_err().top() {
+            ClientError::CardUnknown => (),
+            error => panic!("unexpected error upon querying forged card: {}", error),
+        }
+    }
+
+    fn bounded_settings(max_attempts: Option<u32>, deadline: Option<Duration>) -> ClientSettings {
+        ClientSettings {
+            sleep_schedule: Arc::new(Constant::new(BACKOFF)),
+            attempt_timeout: None,
+            max_attempts,
+            deadline,
+        }
+    }
+
+    const BACKOFF: Duration = Duration::from_secs(1);
+
+    #[tokio::test]
+    async fn attempts_exhausted() {
+        time::pause();
+
+        let client = Client::new("127.0.0.1:1244", bounded_settings(Some(3), None));
+        let identity = KeyChain::random().keycard().identity();
+
+        let start = Instant::now();
+
+        match client.get_card(identity).await.unwrap_err().top() {
+            ClientError::AttemptsExhausted { attempts: 3 } => (),
+            error => panic!("unexpected error upon exhausting attempts: {}", error),
+        }
+
+        // Two backoffs separate the three attempts: none follows the last
+        assert!(start.elapsed() < 3 * BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn attempts_exhausted_replicated() {
+        time::pause();
+
+        let client = Client::with_servers(
+            vec!["127.0.0.1:1245", "127.0.0.1:1246"],
+            bounded_settings(Some(4), None),
+        );
+
+        let identity = KeyChain::random().keycard().identity();
+
+        let start = Instant::now();
+
+        match client.get_card(identity).await.unwrap_err().top() {
+            ClientError::AttemptsExhausted { attempts: 4 } => (),
+            error => panic!("unexpected error upon exhausting attempts: {}", error),
+        }
+
+        // Attempts are exhausted at the end of the second round of
+        // servers, with one backoff in between
+        assert!(start.elapsed() < 2 * BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn deadline_exceeded() {
+        let client = Client::new(
+            "127.0.0.1:1247",
+            bounded_settings(None, Some(Duration::from_millis(200))),
+        );
+
+        let identity = KeyChain::random().keycard().identity();
+
+        match client.get_card(identity).await.unwrap_err().top() {
+            ClientError::DeadlineExceeded => (),
+            error => panic!("unexpected error upon exceeding deadline: {}", error),
+        }
+    }
+
+    #[tokio::test]
+    async fn relay_cancelled() {
+        let client = Client::new("127.0.0.1:1248", Default::default());
+        let identity = KeyChain::random().keycard().identity();
+
+        let fuse = Fuse::new();
+        let handle = client.spawn_get_card(identity, &fuse);
+
+        time::sleep(Duration::from_millis(100)).await;
+        fuse.burn();
+
+        assert!(handle.await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn batch_lookups() {
+        let (_server, _keychains, keycards, identities, clients) =
+            setup("127.0.0.1:1251", 6, vec![3, 3]).await;
+
+        for (index, keycard) in keycards.iter().enumerate() {
+            clients[index]
+                .publish_card(keycard.clone(), Some((index / 3) as ShardId))
+                .await
+                .unwrap();
+        }
+
+        for (index, identity) in identities.iter().take(4).enumerate() {
+            clients[index]
+                .advertise_port(*identity, 1000 + index as u16)
+                .await
+                .unwrap();
+        }
+
+        let unknown = KeyChain::random().keycard().identity();
+
+        let mut query = identities.clone();
+        query.push(unknown);
+
+        let cards = clients[0].get_cards(query.clone()).await.unwrap();
+
+        assert_eq!(cards.len(), 6);
+
+        for (identity, keycard) in identities.iter().zip(keycards.iter()) {
+            assert_eq!(cards[identity], *keycard);
+        }
+
+        let addresses = clients[0].get_addresses(query).await.unwrap();
+
+        assert_eq!(addresses.len(), 4);
+
+        for (index, identity) in identities.iter().take(4).enumerate() {
+            assert_eq!(addresses[identity].port(), 1000 + index as u16);
+        }
+
+        let shards = clients[0].get_shards(vec![1, 0]).await.unwrap();
+
+        assert_eq!(shards.len(), 2);
+        assert!(shards[0]
+            .iter()
+            .all(|keycard| keycards[3..].contains(keycard)));
+        assert!(shards[1]
+            .iter()
+            .all(|keycard| keycards[..3].contains(keycard)));
+
+        match clients[0].get_shards(vec![0, 2]).await.unwrap_err().top() {
+            ClientError::ShardIdInvalid => (),
+            error => panic!("unexpected error upon querying invalid shards: {}", error),
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_shards_incomplete() {
+        let (_server, _keychains, keycards, _identities, clients) =
+            setup("127.0.0.1:1252", 3, vec![2, 2]).await;
+
+        clients[0]
+            .publish_card(keycards[0].clone(), Some(0))
+            .await
+            .unwrap();
+
+        clients[1]
+            .publish_card(keycards[1].clone(), Some(0))
+            .await
+            .unwrap();
+
+        clients[2]
+            .publish_card(keycards[2].clone(), Some(1))
+            .await
+            .unwrap();
+
+        assert_eq!(clients[0].get_shards(vec![0]).await.unwrap()[0].len(), 2);
+
+        match clients[0].get_shards(vec![0, 1]).await.unwrap_err().top() {
+            ClientError::ShardIncomplete => (),
+            error => panic!(
+                "unexpected error upon querying incomplete shards: {}",
+                error
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn dynamic_shards() {
+        let operator = KeyChain::random();
+
+        let _server = Server::new(
+            "127.0.0.1:1254",
+            ServerSettings {
+                shard_sizes: Vec::new(),
+                operator: Some(operator.keycard()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let (_keychains, keycards, _identities, clients) = setup_clients("127.0.0.1:1254", 5).await;
+
+        assert_eq!(clients[0].list_shards().await.unwrap(), Vec::new());
+
+        assert_eq!(clients[0].create_shard(&operator, 3).await.unwrap(), 0);
+        assert_eq!(clients[0].create_shard(&operator, 3).await.unwrap(), 1);
+
+        for index in 0..2 {
+            clients[index]
+                .publish_card(keycards[index].clone(), Some(0))
+                .await
+                .unwrap();
+        }
+
+        clients[2]
+            .publish_card(keycards[2].clone(), Some(1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            clients[0].list_shards().await.unwrap(),
+            vec![
+                ShardInfo {
+                    id: 0,
+                    size: 3,
+                    filled: 2,
+                    closed: false
+                },
+                ShardInfo {
+                    id: 1,
+                    size: 3,
+                    filled: 1,
+                    closed: false
+                }
+            ]
+        );
+
+        match clients[0]
+            .resize_shard(&operator, 0, 1)
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::ShardSizeInvalid => (),
+            error => panic!("unexpected error upon shrinking shard: {}", error),
+        }
+
+        clients[0].resize_shard(&operator, 0, 2).await.unwrap();
+        assert_eq!(clients[0].get_shard(0).await.unwrap().len(), 2);
+
+        clients[0].close_shard(&operator, 1).await.unwrap();
+        assert_eq!(
+            clients[0].get_shard(1).await.unwrap(),
+            vec![keycards[2].clone()]
+        );
+
+        match clients[3]
+            .publish_card(keycards[3].clone(), Some(1))
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::ShardClosed => (),
+            error => panic!(
+                "unexpected error upon publishing to closed shard: {}",
+                error
+            ),
+        }
+
+        match clients[4]
+            .create_shard(&KeyChain::random(), 3)
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::Unauthorized => (),
+            error => panic!("unexpected error upon unauthorized command: {}", error),
+        }
+
+        assert_eq!(clients[0].list_shards().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn command_replayed() {
+        let operator = KeyChain::random();
+
+        let _server = Server::new(
+            "127.0.0.1:1258",
+            ServerSettings {
+                shard_sizes: Vec::new(),
+                operator: Some(operator.keycard()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let client = Client::new("127.0.0.1:1258", Default::default());
+
+        let stale = Command::new(Order::CreateShard(1));
+        let command = Command::new(Order::CreateShard(1));
+
+        let stale = Request::Command(stale.clone(), operator.sign(&stale).unwrap());
+        let command = Request::Command(command.clone(), operator.sign(&command).unwrap());
+
+        assert!(matches!(
+            client.perform(&command).await.unwrap(),
+            Response::ShardCreated(0)
+        ));
+
+        // Neither the same `Command` nor one issued before it is executed
+        assert!(matches!(
+            client.perform(&command).await.unwrap(),
+            Response::CommandReplayed
+        ));
+
+        assert!(matches!(
+            client.perform(&stale).await.unwrap(),
+            Response::CommandReplayed
+        ));
+
+        assert_eq!(client.list_shards().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn persistence() {
+        let path =
+            std::env::temp_dir().join(format!("talk-rendezvous-{}.db", rand::random::<u64>()));
+
+        let settings = ServerSettings {
+            shard_sizes: vec![2],
+            persistence: Some(path.clone()),
+            ..Default::default()
+        };
+
+        let server = Server::new("127.0.0.1:1255", settings.clone())
+            .await
+            .unwrap();
+
+        let (_keychains, keycards, _identities, clients) = setup_clients("127.0.0.1:1255", 2).await;
+
+        for (client, keycard) in clients.iter().zip(keycards.iter()) {
+            client.publish_card(keycard.clone(), Some(0)).await.unwrap();
+        }
+
+        server.shutdown().await.unwrap();
+
+        let _server = Server::new("127.0.0.1:1256", settings).await.unwrap();
+        let client = Client::new("127.0.0.1:1256", Default::default());
+
+        let mut shard = client.get_shard(0).await.unwrap();
+        shard.sort();
+
+        let mut expected = keycards.clone();
+        expected.sort();
+
+        assert_eq!(shard, expected);
+
+        std::fs::remove_file(path).unwrap();
+    }
 }
@@ -5,6 +5,9 @@ use std::{sync::Arc, time::Duration};
 #[derive(Debug, Clone)]
 pub struct ClientSettings {
     pub sleep_schedule: Arc<dyn SleepSchedule>,
+    pub attempt_timeout: Option<Duration>,
+    pub max_attempts: Option<u32>,
+    pub deadline: Option<Duration>,
 }
 
 impl Default for ClientSettings {
@@ -15,6 +18,9 @@ impl Default for ClientSettings {
                 2.,
                 Duration::from_secs(300),
             )),
+            attempt_timeout: Some(Duration::from_secs(10)),
+            max_attempts: None,
+            deadline: None,
         }
     }
 }
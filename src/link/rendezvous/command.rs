@@ -0,0 +1,60 @@
+use crate::{
+    crypto::{Scope, Statement, TalkHeader},
+    link::rendezvous::ShardId,
+};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    cmp,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::link::rendezvous) struct Command {
+    pub order: Order,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names)]
+pub(in crate::link::rendezvous) enum Order {
+    CreateShard(usize),
+    ResizeShard(ShardId, usize),
+    CloseShard(ShardId),
+}
+
+impl Command {
+    pub fn new(order: Order) -> Self {
+        Command {
+            order,
+            timestamp: Command::timestamp(),
+        }
+    }
+
+    // Microseconds since the epoch, strictly increasing within the process
+    // even if the clock stalls or two `Command`s are issued in a row
+    fn timestamp() -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as u64;
+
+        let previous = LAST_TIMESTAMP
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(cmp::max(now, last + 1))
+            })
+            .unwrap();
+
+        cmp::max(now, previous + 1)
+    }
+}
+
+impl Statement for Command {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::RendezvousCommand;
+}
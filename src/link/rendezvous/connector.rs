@@ -10,7 +10,7 @@ use doomstack::{here, Doom, ResultExt, Stack, Top};
 
 use parking_lot::Mutex;
 
-use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, io, iter, net::SocketAddr, sync::Arc};
 
 pub struct Connector {
     client: Client,
@@ -31,6 +31,8 @@ pub enum ConnectorError {
     #[doom(description("Failed to connect: {}", source))]
     #[doom(wrap(connect_failed))]
     ConnectFailed { source: io::Error },
+    #[doom(description("Failed to `prefetch` addresses"))]
+    PrefetchFailed,
     #[doom(description("Failed to `secure` connection"))]
     SecureFailed,
     #[doom(description("Unexpected remote: {:?}", remote))]
@@ -42,7 +44,15 @@ impl Connector {
     where
         S: 'static + TcpConnect,
     {
-        let client = Client::new(server, settings.client_settings);
+        Connector::with_servers(iter::once(server), keychain, settings)
+    }
+
+    pub fn with_servers<S, I>(servers: I, keychain: KeyChain, settings: ConnectorSettings) -> Self
+    where
+        S: 'static + TcpConnect,
+        I: IntoIterator<Item = S>,
+    {
+        let client = Client::with_servers(servers, settings.client_settings);
 
         let database = Arc::new(Mutex::new(Database {
             cache: HashMap::new(),
@@ -55,6 +65,21 @@ impl Connector {
         }
     }
 
+    pub async fn prefetch<I>(&self, identities: I) -> Result<(), Top<ConnectorError>>
+    where
+        I: IntoIterator<Item = Identity>,
+    {
+        let addresses = self
+            .client
+            .get_addresses(identities.into_iter().collect())
+            .await
+            .pot(ConnectorError::PrefetchFailed, here!())?;
+
+        self.database.lock().cache.extend(addresses);
+
+        Ok(())
+    }
+
     async fn attempt(&self, identity: Identity) -> Result<SecureConnection, Top<ConnectorError>> {
         let address = self
             .get_address(identity)
@@ -89,12 +114,7 @@ impl Connector {
 
     async fn refresh(&self, identity: Identity) -> bool {
         let stale = self.get_address(identity);
-        let fresh = self
-            .client
-            .get_address(identity)
-            .await
-            .ok()
-            .or(stale.clone());
+        let fresh = self.client.get_address(identity).await.ok().or(stale);
 
         if fresh != stale {
             self.cache_address(identity, fresh.unwrap()); // `fresh` can be `None` only if `stale` is `None` too
@@ -105,18 +125,11 @@ impl Connector {
     }
 
     fn get_address(&self, identity: Identity) -> Option<SocketAddr> {
-        self.database
-            .lock()
-            .cache
-            .get(&identity)
-            .map(Clone::clone)
+        self.database.lock().cache.get(&identity).copied()
     }
 
     fn cache_address(&self, identity: Identity, address: SocketAddr) {
-        self.database
-            .lock()
-            .cache
-            .insert(identity, address);
+        self.database.lock().cache.insert(identity, address);
     }
 }
 
@@ -155,7 +168,9 @@ mod tests {
         let alice_identity = alice_keychain.keycard().identity();
         let bob_identity = bob_keychain.keycard().identity();
 
-        let mut alice_listener = Listener::new(SERVER, alice_keychain, Default::default()).await;
+        let mut alice_listener = Listener::new(SERVER, alice_keychain, Default::default())
+            .await
+            .unwrap();
 
         let bob_connector = Connector::new(SERVER, bob_keychain, Default::default());
 
@@ -172,4 +187,58 @@ mod tests {
 
         alice_task.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn prefetch() {
+        const SERVER: &str = "127.0.0.1:1253";
+        const LISTENERS: usize = 4;
+
+        let _server = Server::new(SERVER, Default::default()).await.unwrap();
+
+        let keychains = (0..LISTENERS)
+            .map(|_| KeyChain::random())
+            .collect::<Vec<_>>();
+
+        let identities = keychains
+            .iter()
+            .map(|keychain| keychain.keycard().identity())
+            .collect::<Vec<_>>();
+
+        let mut listeners = Vec::new();
+
+        for keychain in keychains {
+            listeners.push(
+                Listener::new(SERVER, keychain, Default::default())
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let connector = Connector::new(SERVER, KeyChain::random(), Default::default());
+
+        connector.prefetch(identities.clone()).await.unwrap();
+
+        for identity in identities.iter() {
+            assert!(connector.get_address(*identity).is_some());
+        }
+
+        let handles = listeners
+            .into_iter()
+            .map(|mut listener| {
+                tokio::spawn(async move {
+                    let (_, mut connection) = listener.accept().await.unwrap();
+                    assert_eq!(connection.receive::<u32>().await.unwrap(), 42);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for identity in identities {
+            let mut connection = connector.connect(identity).await.unwrap();
+            connection.send(&42u32).await.unwrap();
+        }
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    }
 }
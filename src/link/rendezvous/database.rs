@@ -0,0 +1,362 @@
+use crate::{
+    crypto::{Identity, KeyCard},
+    link::rendezvous::{ShardId, ShardInfo},
+};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    cmp,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::link::rendezvous) struct Database {
+    pub shards: Vec<Shard>,
+    pub cards: HashMap<Identity, KeyCard>,
+    pub membership: HashMap<Identity, Option<ShardId>>,
+    pub addresses: HashMap<Identity, Advertisement>,
+    pub commands: HashMap<Identity, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::link::rendezvous) struct Shard {
+    pub size: usize,
+    pub members: HashSet<Identity>,
+    pub closed: bool,
+    pub version: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(in crate::link::rendezvous) struct Advertisement {
+    pub address: SocketAddr,
+    pub timestamp: u128,
+}
+
+impl Database {
+    pub fn new(shard_sizes: &[usize]) -> Self {
+        Database {
+            shards: shard_sizes.iter().map(|size| Shard::new(*size)).collect(),
+            cards: HashMap::new(),
+            membership: HashMap::new(),
+            addresses: HashMap::new(),
+            commands: HashMap::new(),
+        }
+    }
+
+    pub fn merge(&mut self, mut other: Database) {
+        other.sanitize();
+
+        // Shards are only ever appended, and only by the primary, hence shards
+        // unknown to `self` can be adopted. The primary bumps a shard's `version`
+        // whenever it changes its size or state: the most recent change prevails
+        for (index, shard) in other.shards.into_iter().enumerate() {
+            match self.shards.get_mut(index) {
+                Some(local) => {
+                    if shard.version > local.version {
+                        local.size = shard.size;
+                        local.closed = shard.closed;
+                        local.version = shard.version;
+                    }
+                }
+                None => {
+                    self.shards.push(Shard {
+                        members: HashSet::new(),
+                        ..shard
+                    });
+                }
+            }
+        }
+
+        for (identity, card) in other.cards {
+            self.cards.entry(identity).or_insert(card);
+        }
+
+        for (identity, membership) in other.membership {
+            match self.membership.entry(identity) {
+                Entry::Vacant(entry) => {
+                    entry.insert(membership);
+                }
+                Entry::Occupied(mut entry) => {
+                    if Database::prevails(membership, *entry.get()) {
+                        entry.insert(membership);
+                    }
+                }
+            }
+        }
+
+        for (identity, advertisement) in other.addresses {
+            match self.addresses.entry(identity) {
+                Entry::Vacant(entry) => {
+                    entry.insert(advertisement);
+                }
+                Entry::Occupied(mut entry) => {
+                    if advertisement.timestamp > entry.get().timestamp {
+                        entry.insert(advertisement);
+                    }
+                }
+            }
+        }
+
+        for (operator, timestamp) in other.commands {
+            let last = self.commands.entry(operator).or_insert(0);
+            *last = cmp::max(*last, timestamp);
+        }
+
+        self.rebuild_shards();
+    }
+
+    // A snapshot is only trusted as far as it is self-consistent: cards filed
+    // under another `Identity`, cards without a membership and memberships
+    // without a card are dropped before merging
+    fn sanitize(&mut self) {
+        self.cards
+            .retain(|identity, card| card.identity() == *identity);
+
+        let cards = &self.cards;
+        self.membership
+            .retain(|identity, _| cards.contains_key(identity));
+
+        let membership = &self.membership;
+        self.cards
+            .retain(|identity, _| membership.contains_key(identity));
+    }
+
+    // Only the primary seats cards in shards, but a card can still be published
+    // to no shard through another replica: a shard always prevails over no
+    // shard, so that no member the primary acknowledged is ever dropped. Among
+    // shards (only found in snapshots of replicas that disagree on the primary),
+    // a lower `ShardId` prevails over a higher one
+    fn prevails(candidate: Option<ShardId>, current: Option<ShardId>) -> bool {
+        match (candidate, current) {
+            (Some(candidate), Some(current)) => candidate < current,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    // As long as replicas agree on the primary, a shard is never overfilled,
+    // and a complete shard never changes. A snapshot from a replica that
+    // disagrees on the primary could still overfill it: if so, only the lowest
+    // `Identity`s are kept, and the others are demoted to no shard
+    fn rebuild_shards(&mut self) {
+        let mut shards = vec![Vec::new(); self.shards.len()];
+
+        for (identity, membership) in self.membership.iter() {
+            if let Some(shard) = membership {
+                if let Some(members) = shards.get_mut(*shard as usize) {
+                    members.push(*identity);
+                }
+            }
+        }
+
+        for (shard, mut members) in shards.into_iter().enumerate() {
+            members.sort();
+
+            let size = cmp::min(members.len(), self.shards[shard].size);
+
+            for demoted in members.split_off(size) {
+                self.membership.insert(demoted, None);
+            }
+
+            self.shards[shard].members = members.into_iter().collect();
+        }
+    }
+}
+
+impl Shard {
+    pub fn new(size: usize) -> Self {
+        Shard {
+            size,
+            members: HashSet::with_capacity(size),
+            closed: false,
+            version: 0,
+        }
+    }
+
+    pub fn full(&self) -> bool {
+        self.members.len() >= self.size
+    }
+
+    // A closed shard will not grow any further, and is therefore
+    // as complete as it will ever be
+    pub fn complete(&self) -> bool {
+        self.closed || self.full()
+    }
+
+    pub fn info(&self, id: ShardId) -> ShardInfo {
+        ShardInfo {
+            id,
+            size: self.size,
+            filled: self.members.len(),
+            closed: self.closed,
+        }
+    }
+}
+
+impl Advertisement {
+    pub fn now(address: SocketAddr) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_micros();
+
+        Advertisement { address, timestamp }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::crypto::KeyChain;
+
+    fn publish(database: &mut Database, card: &KeyCard, shard: Option<ShardId>) {
+        if let Some(shard) = shard {
+            database.shards[shard as usize]
+                .members
+                .insert(card.identity());
+        }
+
+        database.membership.insert(card.identity(), shard);
+        database.cards.insert(card.identity(), card.clone());
+    }
+
+    #[test]
+    fn merge_disjoint() {
+        let cards = (0..4)
+            .map(|_| KeyChain::random().keycard())
+            .collect::<Vec<_>>();
+
+        let mut alpha = Database::new(&[2, 2]);
+        let mut beta = Database::new(&[2, 2]);
+
+        publish(&mut alpha, &cards[0], Some(0));
+        publish(&mut alpha, &cards[1], Some(1));
+        publish(&mut beta, &cards[2], Some(0));
+        publish(&mut beta, &cards[3], None);
+
+        alpha.merge(beta);
+
+        assert_eq!(alpha.cards.len(), 4);
+        assert_eq!(alpha.shards[0].members.len(), 2);
+        assert_eq!(alpha.shards[1].members.len(), 1);
+        assert_eq!(alpha.membership[&cards[3].identity()], None);
+    }
+
+    #[test]
+    fn merge_overfilled() {
+        let cards = (0..4)
+            .map(|_| KeyChain::random().keycard())
+            .collect::<Vec<_>>();
+
+        let mut alpha = Database::new(&[2]);
+        let mut beta = Database::new(&[2]);
+
+        publish(&mut alpha, &cards[0], Some(0));
+        publish(&mut alpha, &cards[1], Some(0));
+        publish(&mut beta, &cards[2], Some(0));
+        publish(&mut beta, &cards[3], Some(0));
+
+        let mut gamma = alpha.clone();
+
+        alpha.merge(beta.clone());
+        beta.merge(gamma.clone());
+        gamma.merge(beta.clone());
+
+        assert_eq!(alpha.shards[0].members.len(), 2);
+        assert_eq!(alpha.shards[0].members, beta.shards[0].members);
+        assert_eq!(alpha.shards[0].members, gamma.shards[0].members);
+
+        let mut identities = cards.iter().map(|card| card.identity()).collect::<Vec<_>>();
+
+        identities.sort();
+
+        assert!(alpha.shards[0].members.contains(&identities[0]));
+        assert!(alpha.shards[0].members.contains(&identities[1]));
+        assert_eq!(alpha.membership[&identities[2]], None);
+        assert_eq!(alpha.membership[&identities[3]], None);
+    }
+
+    #[test]
+    fn merge_inconsistent() {
+        let cards = (0..3)
+            .map(|_| KeyChain::random().keycard())
+            .collect::<Vec<_>>();
+
+        let mut alpha = Database::new(&[3]);
+        let mut beta = Database::new(&[3]);
+
+        // A card without a membership, a membership without a card,
+        // and a card filed under another card's `Identity`
+        beta.cards.insert(cards[0].identity(), cards[0].clone());
+        beta.membership.insert(cards[1].identity(), Some(0));
+        beta.cards.insert(cards[2].identity(), cards[0].clone());
+        beta.membership.insert(cards[2].identity(), Some(0));
+
+        alpha.merge(beta);
+
+        assert!(alpha.cards.is_empty());
+        assert!(alpha.membership.is_empty());
+        assert!(alpha.shards[0].members.is_empty());
+    }
+
+    #[test]
+    fn merge_shard_changes() {
+        let mut alpha = Database::new(&[2]);
+        let mut beta = Database::new(&[2]);
+
+        alpha.shards[0].size = 4;
+        alpha.shards[0].version += 1;
+
+        beta.shards.push(Shard::new(3));
+        beta.shards[1].closed = true;
+
+        alpha.merge(beta.clone());
+        beta.merge(alpha.clone());
+
+        for database in [&alpha, &beta].iter() {
+            assert_eq!(database.shards.len(), 2);
+            assert_eq!(database.shards[0].size, 4);
+            assert_eq!(database.shards[1].size, 3);
+            assert!(database.shards[1].closed);
+        }
+    }
+
+    #[test]
+    fn merge_stale() {
+        let cards = (0..3)
+            .map(|_| KeyChain::random().keycard())
+            .collect::<Vec<_>>();
+
+        let mut primary = Database::new(&[2]);
+        let mut replica = primary.clone();
+
+        // `replica` publishes to no shard, while `primary` creates a
+        // shard and fills both shards, then resizes the first one
+        publish(&mut replica, &cards[2], None);
+
+        primary.shards.push(Shard::new(1));
+        publish(&mut primary, &cards[0], Some(0));
+        publish(&mut primary, &cards[1], Some(1));
+        publish(&mut primary, &cards[2], Some(0));
+        primary.shards[0].size = 3;
+        primary.shards[0].version += 1;
+
+        let stale = replica.clone();
+
+        replica.merge(primary.clone());
+        primary.merge(stale);
+
+        for database in [&primary, &replica].iter() {
+            assert_eq!(database.shards.len(), 2);
+            assert_eq!(database.shards[0].size, 3);
+            assert_eq!(database.shards[0].members.len(), 2);
+            assert!(database.shards[1].complete());
+            assert!(database.shards[1].members.contains(&cards[1].identity()));
+            assert_eq!(database.membership[&cards[2].identity()], Some(0));
+        }
+    }
+}
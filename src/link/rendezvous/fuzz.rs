@@ -0,0 +1,155 @@
+use crate::{
+    link::rendezvous::{Request, Response},
+    net::test::fuzz,
+};
+
+/// Feeds `data` to the deserialization of rendezvous `Request`s.
+pub fn request(data: &[u8]) {
+    let _ = fuzz::decode::<Request>(data);
+}
+
+/// Feeds `data` to the deserialization of rendezvous `Response`s.
+pub fn response(data: &[u8]) {
+    let _ = fuzz::decode::<Response>(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        crypto::{Identity, KeyCard, KeyChain},
+        link::rendezvous::{Advertisement, Command, Database, Order, Shard, ShardId},
+    };
+
+    use proptest::{collection, prelude::*};
+
+    use std::net::SocketAddr;
+
+    fn identity() -> impl Strategy<Value = Identity> {
+        any::<[u8; 32]>().prop_map(Identity::from_bytes)
+    }
+
+    fn address() -> impl Strategy<Value = SocketAddr> {
+        (any::<[u8; 4]>(), any::<u16>()).prop_map(SocketAddr::from)
+    }
+
+    fn keychain() -> impl Strategy<Value = KeyChain> {
+        any::<[u8; 32]>().prop_map(KeyChain::from_seed)
+    }
+
+    fn keycard() -> impl Strategy<Value = KeyCard> {
+        keychain().prop_map(|keychain| keychain.keycard())
+    }
+
+    fn command() -> impl Strategy<Value = Command> {
+        let order = prop_oneof![
+            any::<usize>().prop_map(Order::CreateShard),
+            (any::<ShardId>(), any::<usize>())
+                .prop_map(|(shard, size)| Order::ResizeShard(shard, size)),
+            any::<ShardId>().prop_map(Order::CloseShard),
+        ];
+
+        (order, any::<u64>()).prop_map(|(order, timestamp)| Command { order, timestamp })
+    }
+
+    // `HashMap`s and `HashSet`s hold at most one entry: otherwise, their
+    // re-encoding could legitimately differ from the original in order
+    fn database() -> impl Strategy<Value = Database> {
+        let shard = (
+            any::<usize>(),
+            collection::hash_set(identity(), 0..2),
+            any::<bool>(),
+            any::<u64>(),
+        )
+            .prop_map(|(size, members, closed, version)| Shard {
+                size,
+                members,
+                closed,
+                version,
+            });
+
+        let advertisement = (address(), any::<u128>())
+            .prop_map(|(address, timestamp)| Advertisement { address, timestamp });
+
+        (
+            collection::vec(shard, 0..4),
+            collection::vec(keycard(), 0..2),
+            collection::hash_map(identity(), any::<Option<ShardId>>(), 0..2),
+            collection::hash_map(identity(), advertisement, 0..2),
+            collection::hash_map(identity(), any::<u64>(), 0..2),
+        )
+            .prop_map(
+                |(shards, cards, membership, addresses, commands)| Database {
+                    shards,
+                    cards: cards
+                        .into_iter()
+                        .map(|card| (card.identity(), card))
+                        .collect(),
+                    membership,
+                    addresses,
+                    commands,
+                },
+            )
+    }
+
+    fn requests() -> impl Strategy<Value = Request> {
+        prop_oneof![
+            (keycard(), any::<Option<ShardId>>())
+                .prop_map(|(card, shard)| Request::PublishCard(card, shard)),
+            (identity(), any::<u16>())
+                .prop_map(|(identity, port)| Request::AdvertisePort(identity, port)),
+            any::<ShardId>().prop_map(Request::GetShard),
+            identity().prop_map(Request::GetCard),
+            collection::vec(any::<ShardId>(), 0..16).prop_map(Request::GetShards),
+            collection::vec(identity(), 0..16).prop_map(Request::GetCards),
+            Just(()).prop_map(|_| Request::ListShards),
+            (keychain(), command()).prop_map(|(keychain, command)| {
+                let signature = keychain.sign(&command).unwrap();
+                Request::Command(command, signature)
+            }),
+            database().prop_map(Request::Merge),
+        ]
+    }
+
+    fn responses() -> impl Strategy<Value = Response> {
+        prop_oneof![
+            Just(()).prop_map(|_| Response::AcknowledgeCard),
+            any::<ShardId>().prop_map(Response::ShardCreated),
+            any::<Option<ShardId>>().prop_map(Response::AlreadyPublished),
+            collection::vec((identity(), address()), 0..16).prop_map(Response::Addresses),
+            Just(()).prop_map(|_| Response::CommandReplayed),
+            Just(()).prop_map(|_| Response::NotPrimary),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn request_arbitrary(data in collection::vec(any::<u8>(), 0..1024)) {
+            request(&data);
+        }
+
+        #[test]
+        fn response_arbitrary(data in collection::vec(any::<u8>(), 0..1024)) {
+            response(&data);
+        }
+
+        #[test]
+        fn request_roundtrip(request in requests()) {
+            let encoding = bincode::serialize(&request).unwrap();
+            let decoded = fuzz::decode::<Request>(&encoding).unwrap();
+
+            prop_assert_eq!(bincode::serialize(&decoded).unwrap(), encoding.clone());
+            fuzz::truncations::<Request>(&encoding);
+        }
+
+        #[test]
+        fn response_roundtrip(response in responses()) {
+            let encoding = bincode::serialize(&response).unwrap();
+            let decoded = fuzz::decode::<Response>(&encoding).unwrap();
+
+            prop_assert_eq!(bincode::serialize(&decoded).unwrap(), encoding.clone());
+            fuzz::truncations::<Response>(&encoding);
+        }
+    }
+}
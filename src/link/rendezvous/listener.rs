@@ -9,7 +9,7 @@ use crate::{
 
 use doomstack::{here, Doom, ResultExt, Stack, Top};
 
-use std::net::Ipv4Addr;
+use std::{iter, net::Ipv4Addr, time::Duration};
 
 use tokio::{
     net::TcpListener,
@@ -17,6 +17,7 @@ use tokio::{
         mpsc,
         mpsc::{Receiver, Sender},
     },
+    time,
 };
 
 type Outlet = Receiver<(Identity, SecureConnection)>;
@@ -26,18 +27,42 @@ pub struct Listener {
     _fuse: Fuse,
 }
 
+#[derive(Doom)]
+pub enum ListenerError {
+    #[doom(description("Failed to `advertise_port`"))]
+    AdvertiseFailed,
+}
+
 #[derive(Doom)]
 enum ServeError {
     #[doom(description("Failed to `secure` the connection"))]
     SecureFailed,
     #[doom(description("Failed to `authenticate` the connection"))]
     AuthenticateFailed,
+    #[doom(description("`Listener` is busy"))]
+    ListenerBusy,
 }
 
 impl Listener {
-    pub async fn new<S>(server: S, keychain: KeyChain, settings: ListenerSettings) -> Self
+    pub async fn new<S>(
+        server: S,
+        keychain: KeyChain,
+        settings: ListenerSettings,
+    ) -> Result<Self, Top<ListenerError>>
     where
         S: 'static + TcpConnect,
+    {
+        Listener::with_servers(iter::once(server), keychain, settings).await
+    }
+
+    pub async fn with_servers<S, I>(
+        servers: I,
+        keychain: KeyChain,
+        settings: ListenerSettings,
+    ) -> Result<Self, Top<ListenerError>>
+    where
+        S: 'static + TcpConnect,
+        I: IntoIterator<Item = S>,
     {
         let listener = TcpListener::bind(
             (Ipv4Addr::UNSPECIFIED, 0), // TODO: Determine if `Ipv6Addr` can be used instead (problems with Docker?)
@@ -52,23 +77,30 @@ impl Listener {
 
         let (inlet, outlet) = mpsc::channel(settings.channel_capacity);
 
+        let delivery_timeout = settings.delivery_timeout;
+
         fuse.spawn(async move {
-            let _ = Listener::listen(keychain, listener, inlet).await;
+            let _ = Listener::listen(keychain, listener, inlet, delivery_timeout).await;
         });
 
-        let client = Client::new(server, settings.client_settings);
-        client.advertise_port(identity, port).await;
+        let client = Client::with_servers(servers, settings.client_settings);
 
-        Listener {
+        client
+            .advertise_port(identity, port)
+            .await
+            .pot(ListenerError::AdvertiseFailed, here!())?;
+
+        Ok(Listener {
             outlet,
             _fuse: fuse,
-        }
+        })
     }
 
     async fn listen(
         keychain: KeyChain,
         listener: TcpListener,
         inlet: Sender<(Identity, SecureConnection)>,
+        delivery_timeout: Option<Duration>,
     ) {
         let fuse = Fuse::new();
 
@@ -80,7 +112,7 @@ impl Listener {
                 let inlet = inlet.clone();
 
                 fuse.spawn(async move {
-                    let _ = Listener::serve(connection, keychain, inlet).await;
+                    let _ = Listener::serve(connection, keychain, inlet, delivery_timeout).await;
                 });
             }
         }
@@ -90,20 +122,36 @@ impl Listener {
         connection: PlainConnection,
         keychain: KeyChain,
         inlet: Sender<(Identity, SecureConnection)>,
+        delivery_timeout: Option<Duration>,
     ) -> Result<(), Top<ServeError>> {
         let mut connection = connection
             .secure()
             .await
             .pot(ServeError::SecureFailed, here!())?;
 
+        // Room for `connection` is reserved before authenticating: if the
+        // `Listener` is not keeping up, `connection` is dropped and the
+        // remote fails to `authenticate`, instead of losing its connection
+        // after believing it was accepted
+        let permit = match delivery_timeout {
+            Some(delivery_timeout) => time::timeout(delivery_timeout, inlet.reserve())
+                .await
+                .ok()
+                .and_then(Result::ok),
+            None => inlet.try_reserve().ok(),
+        };
+
+        let permit = match permit {
+            Some(permit) => permit,
+            None => return ServeError::ListenerBusy.fail().spot(here!()),
+        };
+
         let keycard = connection
             .authenticate(&keychain)
             .await
             .pot(ServeError::AuthenticateFailed, here!())?;
 
-        // This can only fail if the (local) receiving end is
-        // dropped, in which case we don't care about the error
-        let _ = inlet.try_send((keycard.identity(), connection));
+        permit.send((keycard.identity(), connection));
 
         Ok(())
     }
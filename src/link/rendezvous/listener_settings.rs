@@ -1,9 +1,12 @@
 use crate::link::rendezvous::ClientSettings;
 
+use std::time::Duration;
+
 #[derive(Debug, Clone)]
 pub struct ListenerSettings {
     pub client_settings: ClientSettings,
     pub channel_capacity: usize,
+    pub delivery_timeout: Option<Duration>,
 }
 
 impl Default for ListenerSettings {
@@ -11,6 +14,7 @@ impl Default for ListenerSettings {
         ListenerSettings {
             client_settings: Default::default(),
             channel_capacity: 32,
+            delivery_timeout: None,
         }
     }
 }
@@ -1,7 +1,9 @@
 mod client;
 mod client_settings;
+mod command;
 mod connector;
 mod connector_settings;
+mod database;
 mod listener;
 mod listener_settings;
 mod request;
@@ -9,7 +11,13 @@ mod response;
 mod server;
 mod server_settings;
 mod shard_id;
+mod shard_info;
 
+#[cfg(any(test, feature = "test_utilities"))]
+pub mod fuzz;
+
+use command::{Command, Order};
+use database::{Advertisement, Database, Shard};
 use request::Request;
 use response::Response;
 
@@ -17,8 +25,9 @@ pub use client::{Client, ClientError};
 pub use client_settings::ClientSettings;
 pub use connector::{Connector, ConnectorError};
 pub use connector_settings::ConnectorSettings;
-pub use listener::Listener;
+pub use listener::{Listener, ListenerError};
 pub use listener_settings::ListenerSettings;
 pub use server::{Server, ServerError};
 pub use server_settings::ServerSettings;
 pub use shard_id::ShardId;
+pub use shard_info::ShardInfo;
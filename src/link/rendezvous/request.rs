@@ -1,12 +1,13 @@
 use crate::{
-    crypto::{Identity, KeyCard},
-    link::rendezvous::ShardId,
+    crypto::{primitives::sign::Signature, Identity, KeyCard},
+    link::rendezvous::{Command, Database, ShardId},
 };
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(u8)]
+#[allow(clippy::large_enum_variant)]
 pub(in crate::link::rendezvous) enum Request {
     PublishCard(KeyCard, Option<ShardId>),
     AdvertisePort(Identity, u16),
@@ -14,4 +15,16 @@ pub(in crate::link::rendezvous) enum Request {
     GetShard(ShardId),
     GetCard(Identity),
     GetAddress(Identity),
+
+    GetShards(Vec<ShardId>),
+    GetCards(Vec<Identity>),
+    GetAddresses(Vec<Identity>),
+
+    ListShards,
+    Command(Command, Signature),
+
+    // `Merge` is only accepted over a connection that `Replicate`
+    // secured and authenticated as one of the server's peers
+    Merge(Database),
+    Replicate,
 }
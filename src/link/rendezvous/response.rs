@@ -1,4 +1,7 @@
-use crate::{crypto::KeyCard, link::rendezvous::ShardId};
+use crate::{
+    crypto::{Identity, KeyCard},
+    link::rendezvous::{ShardId, ShardInfo},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -6,18 +9,33 @@ use std::net::SocketAddr;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(u8)]
+#[allow(clippy::large_enum_variant)]
 pub(in crate::link::rendezvous) enum Response {
     AcknowledgeCard,
     AcknowledgePort,
+    AcknowledgeMerge,
+    AcknowledgeCommand,
 
     Shard(Vec<KeyCard>),
     Card(KeyCard),
     Address(SocketAddr),
 
+    Shards(Vec<Vec<KeyCard>>),
+    Cards(Vec<KeyCard>),
+    Addresses(Vec<(Identity, SocketAddr)>),
+
+    ShardList(Vec<ShardInfo>),
+    ShardCreated(ShardId),
+
     AlreadyPublished(Option<ShardId>),
     ShardFull,
     ShardIdInvalid,
     ShardIncomplete,
     CardUnknown,
     AddressUnknown,
+    ShardClosed,
+    ShardSizeInvalid,
+    Unauthorized,
+    CommandReplayed,
+    NotPrimary,
 }
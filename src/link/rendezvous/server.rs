@@ -1,47 +1,81 @@
 use crate::{
-    crypto::{Identity, KeyCard},
-    link::rendezvous::{Request, Response, ServerSettings, ShardId},
-    net::PlainConnection,
+    crypto::{KeyCard, KeyChain},
+    link::rendezvous::{
+        Advertisement, Command, Database, Order, Request, Response, ServerSettings, Shard, ShardId,
+    },
+    net::{traits::TcpConnect, PlainConnection},
     sync::fuse::Fuse,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
+use futures::stream::{FuturesUnordered, StreamExt};
+
 use parking_lot::Mutex;
 
 use std::{
-    collections::{HashMap, HashSet},
     net::SocketAddr,
+    path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use tokio::{
-    io,
+    fs, io,
     net::{TcpListener, ToSocketAddrs},
+    time,
 };
 
 pub struct Server {
-    _fuse: Fuse,
+    settings: ServerSettings,
+    database: Arc<Mutex<Database>>,
+    fuse: Fuse,
 }
 
 #[derive(Doom)]
 pub enum ServerError {
+    #[doom(description("Failed to deserialize database: {}", source))]
+    #[doom(wrap(deserialize_failed))]
+    DeserializeFailed { source: bincode::Error },
     #[doom(description("Failed to initialize server: {}", source))]
     #[doom(wrap(initialize_failed))]
     InitializeFailed { source: io::Error },
+    #[doom(description("Failed to read database: {}", source))]
+    #[doom(wrap(read_failed))]
+    ReadFailed { source: io::Error },
+    #[doom(description("Failed to serialize database: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+    #[doom(description("Failed to write database: {}", source))]
+    #[doom(wrap(write_failed))]
+    WriteFailed { source: io::Error },
 }
 
 #[derive(Doom)]
 enum ServeError {
+    #[doom(description("Failed to `authenticate` connection"))]
+    AuthenticateFailed,
     #[doom(description("connection error"))]
     ConnectionError,
+    #[doom(description("Failed to `secure` connection"))]
+    SecureFailed,
 }
 
-struct Database {
-    shards: Vec<HashSet<Identity>>,
-    cards: HashMap<Identity, KeyCard>,
-    membership: HashMap<Identity, Option<ShardId>>,
-    addresses: HashMap<Identity, SocketAddr>,
+#[derive(Doom)]
+enum SyncError {
+    #[doom(description("Failed to `authenticate` connection"))]
+    AuthenticateFailed,
+    #[doom(description("Failed to `connect`: {}", source))]
+    #[doom(wrap(connect_failed))]
+    ConnectFailed { source: io::Error },
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Failed to `secure` connection"))]
+    SecureFailed,
+    #[doom(description("Unexpected remote"))]
+    UnexpectedRemote,
+    #[doom(description("Unexpected response"))]
+    UnexpectedResponse,
 }
 
 impl Server {
@@ -49,30 +83,149 @@ impl Server {
     where
         A: ToSocketAddrs,
     {
-        let database = Arc::new(Mutex::new(Database {
-            shards: settings
-                .shard_sizes
-                .iter()
-                .map(|size| HashSet::with_capacity(*size))
-                .collect(),
-            cards: HashMap::new(),
-            membership: HashMap::new(),
-            addresses: HashMap::new(),
-        }));
+        let listener = TcpListener::bind(address)
+            .await
+            .map_err(ServerError::initialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        Server::from_listener(listener, settings).await
+    }
+
+    /// Serves on an already bound `listener`. Replicas can bind (e.g., to port 0)
+    /// and learn each other's addresses before any of them starts serving.
+    pub async fn from_listener(
+        listener: TcpListener,
+        settings: ServerSettings,
+    ) -> Result<Self, Top<ServerError>> {
+        let database = match &settings.persistence {
+            Some(path) => Server::load(path, settings.shard_sizes.as_slice()).await?,
+            None => Database::new(settings.shard_sizes.as_slice()),
+        };
+
+        let database = Arc::new(Mutex::new(database));
 
         let fuse = Fuse::new();
 
-        let listener = TcpListener::bind(address)
+        {
+            let settings = settings.clone();
+            let database = database.clone();
+
+            fuse.spawn(async move {
+                Server::synchronize(settings, database).await;
+            });
+        }
+
+        if let Some(path) = settings.persistence.clone() {
+            let interval = settings.persistence_interval;
+            let database = database.clone();
+
+            fuse.spawn(async move {
+                Server::keep_persisting(path, interval, database).await;
+            });
+        }
+
+        {
+            let settings = settings.clone();
+            let database = database.clone();
+
+            fuse.spawn(async move {
+                let _ = Server::listen(settings, database, listener).await;
+            });
+        }
+
+        Ok(Server {
+            settings,
+            database,
+            fuse,
+        })
+    }
+
+    /// Stops serving requests and, if `settings.persistence` is set,
+    /// persists the final state of the database before returning.
+    pub async fn shutdown(self) -> Result<(), Top<ServerError>> {
+        self.fuse.burn();
+
+        if let Some(path) = &self.settings.persistence {
+            Server::persist(path, &self.database).await?;
+            log::info!("Database persisted to {}", path.display());
+        }
+
+        Ok(())
+    }
+
+    async fn load(path: &Path, shard_sizes: &[usize]) -> Result<Database, Top<ServerError>> {
+        match fs::read(path).await {
+            Ok(bytes) => {
+                log::info!("Loading database from {}", path.display());
+
+                let database: Database = bincode::deserialize(bytes.as_slice())
+                    .map_err(ServerError::deserialize_failed)
+                    .map_err(Doom::into_top)
+                    .spot(here!())?;
+
+                let persisted = database
+                    .shards
+                    .iter()
+                    .map(|shard| shard.size)
+                    .collect::<Vec<_>>();
+
+                if persisted != shard_sizes {
+                    log::warn!(
+                        "Persisted shard sizes {:?} override configured `shard_sizes` {:?}",
+                        persisted,
+                        shard_sizes
+                    );
+                }
+
+                Ok(database)
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                log::info!(
+                    "No database found at {}: starting from scratch",
+                    path.display()
+                );
+
+                Ok(Database::new(shard_sizes))
+            }
+            Err(error) => ServerError::read_failed(error).fail().spot(here!()),
+        }
+    }
+
+    async fn keep_persisting(path: PathBuf, interval: Duration, database: Arc<Mutex<Database>>) {
+        loop {
+            time::sleep(interval).await;
+
+            if let Err(error) = Server::persist(path.as_path(), database.as_ref()).await {
+                log::warn!("Failed to persist database: {}", error.top());
+            }
+        }
+    }
+
+    async fn persist(path: &Path, database: &Mutex<Database>) -> Result<(), Top<ServerError>> {
+        let bytes = bincode::serialize(&*database.lock())
+            .map_err(ServerError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        // The database is first written to a temporary file, then moved into
+        // place: a crash mid-write never leaves a corrupted database behind
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        fs::write(&temporary, bytes)
             .await
-            .map_err(ServerError::initialize_failed)
+            .map_err(ServerError::write_failed)
             .map_err(Doom::into_top)
             .spot(here!())?;
 
-        fuse.spawn(async move {
-            let _ = Server::listen(settings, database, listener).await;
-        });
+        fs::rename(&temporary, path)
+            .await
+            .map_err(ServerError::write_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
 
-        Ok(Server { _fuse: fuse })
+        Ok(())
     }
 
     async fn listen(
@@ -83,18 +236,23 @@ impl Server {
         let fuse = Fuse::new();
 
         loop {
-            if let Ok((stream, address)) = listener.accept().await {
-                let settings = settings.clone();
-                let database = database.clone();
-
-                let connection: PlainConnection = stream.into();
-
-                fuse.spawn(async move {
-                    let _ = Server::serve(settings, database, connection, address).await;
-                });
+            match listener.accept().await {
+                Ok((stream, address)) => {
+                    let settings = settings.clone();
+                    let database = database.clone();
+
+                    let connection: PlainConnection = stream.into();
+
+                    fuse.spawn(async move {
+                        if let Err(error) =
+                            Server::serve(settings, database, connection, address).await
+                        {
+                            log::debug!("Failed to serve {}: {}", address, error.top());
+                        }
+                    });
+                }
+                Err(error) => log::warn!("Failed to accept connection: {}", error),
             }
-
-            // TODO: Log error case
         }
     }
 
@@ -102,98 +260,400 @@ impl Server {
         settings: ServerSettings,
         database: Arc<Mutex<Database>>,
         mut connection: PlainConnection,
-        mut address: SocketAddr,
+        address: SocketAddr,
     ) -> Result<(), Top<ServeError>> {
         let request: Request = connection
             .receive()
             .await
             .pot(ServeError::ConnectionError, here!())?;
 
-        let response = {
-            let mut database = database.lock();
+        if let Request::Replicate = request {
+            return Server::replicate(settings, database, connection, address).await;
+        }
 
-            match request {
-                Request::PublishCard(card, shard)
-                    if database.cards.contains_key(&card.identity()) =>
-                {
-                    let membership = database.membership[&card.identity()];
+        let response = Server::handle(&settings, &database, request, address);
 
-                    if membership == shard {
-                        Response::AcknowledgeCard
-                    } else {
-                        Response::AlreadyPublished(membership)
-                    }
-                }
-                Request::PublishCard(_, Some(shard))
-                    if (shard as usize) >= database.shards.len() =>
-                {
-                    Response::ShardIdInvalid
+        connection
+            .send(&response)
+            .await
+            .pot(ServeError::ConnectionError, here!())?;
+
+        Ok(())
+    }
+
+    async fn replicate(
+        settings: ServerSettings,
+        database: Arc<Mutex<Database>>,
+        connection: PlainConnection,
+        address: SocketAddr,
+    ) -> Result<(), Top<ServeError>> {
+        let mut connection = connection
+            .secure()
+            .await
+            .pot(ServeError::SecureFailed, here!())?;
+
+        let remote = connection
+            .authenticate(&settings.keychain)
+            .await
+            .pot(ServeError::AuthenticateFailed, here!())?;
+
+        let request: Request = connection
+            .receive()
+            .await
+            .pot(ServeError::ConnectionError, here!())?;
+
+        let response = match request {
+            Request::Merge(snapshot) if Server::is_peer(&settings, &remote) => {
+                database.lock().merge(snapshot);
+                Response::AcknowledgeMerge
+            }
+            _ => {
+                log::warn!("Rejected unauthorized replication from {}", address);
+                Response::Unauthorized
+            }
+        };
+
+        connection
+            .send(&response)
+            .await
+            .pot(ServeError::ConnectionError, here!())?;
+
+        Ok(())
+    }
+
+    fn is_peer(settings: &ServerSettings, keycard: &KeyCard) -> bool {
+        settings
+            .peers
+            .iter()
+            .any(|(_, peer)| peer.identity() == keycard.identity())
+    }
+
+    // The primary is the replica with the lowest `Identity`: as long as all
+    // replicas are configured as each other's `peers`, they agree on it
+    fn is_primary(settings: &ServerSettings) -> bool {
+        let identity = settings.keychain.keycard().identity();
+
+        settings
+            .peers
+            .iter()
+            .all(|(_, peer)| identity < peer.identity())
+    }
+
+    fn handle(
+        settings: &ServerSettings,
+        database: &Mutex<Database>,
+        request: Request,
+        mut address: SocketAddr,
+    ) -> Response {
+        let mut database = database.lock();
+
+        // Shards are only ever written by the primary: were two replicas to
+        // create or fill shards concurrently, they would hand out the same
+        // `ShardId`s and seats, and merging could not reconcile them without
+        // dropping acknowledged members. Other replicas refer clients to the
+        // primary, and learn its writes upon synchronizing
+        match request {
+            Request::PublishCard(card, shard) if database.cards.contains_key(&card.identity()) => {
+                match database.membership.get(&card.identity()) {
+                    Some(membership) if *membership == shard => Response::AcknowledgeCard,
+                    Some(membership) => Response::AlreadyPublished(*membership),
+                    None => Response::CardUnknown,
                 }
-                Request::PublishCard(_, Some(shard))
-                    if database.shards[shard as usize].len()
-                        >= settings.shard_sizes[shard as usize] =>
-                {
-                    Response::ShardFull
+            }
+            Request::PublishCard(_, Some(_)) if !Server::is_primary(settings) => {
+                Response::NotPrimary
+            }
+            Request::PublishCard(_, Some(shard)) if (shard as usize) >= database.shards.len() => {
+                Response::ShardIdInvalid
+            }
+            Request::PublishCard(_, Some(shard)) if database.shards[shard as usize].closed => {
+                Response::ShardClosed
+            }
+            Request::PublishCard(_, Some(shard)) if database.shards[shard as usize].full() => {
+                Response::ShardFull
+            }
+            Request::PublishCard(card, shard) => {
+                if let Some(shard) = shard {
+                    database.shards[shard as usize]
+                        .members
+                        .insert(card.identity());
                 }
-                Request::PublishCard(card, shard) => {
-                    if let Some(shard) = shard {
-                        database.shards[shard as usize].insert(card.identity());
-                    }
 
-                    database.membership.insert(card.identity(), shard);
-                    database.cards.insert(card.identity(), card);
+                database.membership.insert(card.identity(), shard);
+                database.cards.insert(card.identity(), card);
 
-                    Response::AcknowledgeCard
-                }
+                Response::AcknowledgeCard
+            }
 
-                Request::AdvertisePort(identity, port) => {
-                    address.set_port(port);
-                    database.addresses.insert(identity, address);
+            Request::AdvertisePort(identity, port) => {
+                address.set_port(port);
 
-                    Response::AcknowledgePort
+                database
+                    .addresses
+                    .insert(identity, Advertisement::now(address));
+
+                Response::AcknowledgePort
+            }
+
+            Request::GetShard(shard) if (shard as usize) >= database.shards.len() => {
+                Response::ShardIdInvalid
+            }
+            Request::GetShard(shard) if !database.shards[shard as usize].complete() => {
+                Response::ShardIncomplete
+            }
+            Request::GetShard(shard) => {
+                // A member without a card cannot be served
+                let shard = database.shards[shard as usize]
+                    .members
+                    .iter()
+                    .map(|key| database.cards.get(key).cloned())
+                    .collect::<Option<Vec<_>>>();
+
+                match shard {
+                    Some(shard) => Response::Shard(shard),
+                    None => Response::ShardIncomplete,
                 }
+            }
 
-                Request::GetShard(shard) if (shard as usize) >= database.shards.len() => {
-                    Response::ShardIdInvalid
+            Request::GetCard(identity) => {
+                if let Some(card) = database.cards.get(&identity) {
+                    Response::Card(card.clone())
+                } else {
+                    Response::CardUnknown
                 }
-                Request::GetShard(shard)
-                    if database.shards[shard as usize].len()
-                        < settings.shard_sizes[shard as usize] =>
-                {
-                    Response::ShardIncomplete
+            }
+
+            Request::GetAddress(identity) => {
+                if let Some(advertisement) = database.addresses.get(&identity) {
+                    Response::Address(advertisement.address)
+                } else {
+                    Response::AddressUnknown
                 }
-                Request::GetShard(shard) => {
-                    let shard = database.shards[shard as usize]
-                        .iter()
-                        .map(|key| database.cards[key].clone())
-                        .collect::<Vec<_>>();
+            }
 
-                    Response::Shard(shard)
+            Request::GetShards(shards)
+                if shards
+                    .iter()
+                    .any(|shard| (*shard as usize) >= database.shards.len()) =>
+            {
+                Response::ShardIdInvalid
+            }
+            Request::GetShards(shards)
+                if shards
+                    .iter()
+                    .any(|shard| !database.shards[*shard as usize].complete()) =>
+            {
+                Response::ShardIncomplete
+            }
+            Request::GetShards(shards) => {
+                let shards = shards
+                    .into_iter()
+                    .map(|shard| {
+                        database.shards[shard as usize]
+                            .members
+                            .iter()
+                            .map(|key| database.cards.get(key).cloned())
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .collect::<Option<Vec<_>>>();
+
+                match shards {
+                    Some(shards) => Response::Shards(shards),
+                    None => Response::ShardIncomplete,
                 }
+            }
 
-                Request::GetCard(identity) => {
-                    if let Some(card) = database.cards.get(&identity) {
-                        Response::Card(card.clone())
-                    } else {
-                        Response::CardUnknown
-                    }
+            Request::GetCards(identities) => {
+                let cards = identities
+                    .iter()
+                    .filter_map(|identity| database.cards.get(identity))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                Response::Cards(cards)
+            }
+
+            Request::GetAddresses(identities) => {
+                let addresses = identities
+                    .into_iter()
+                    .filter_map(|identity| {
+                        database
+                            .addresses
+                            .get(&identity)
+                            .map(|advertisement| (identity, advertisement.address))
+                    })
+                    .collect::<Vec<_>>();
+
+                Response::Addresses(addresses)
+            }
+
+            Request::ListShards => {
+                let shards = database
+                    .shards
+                    .iter()
+                    .enumerate()
+                    .map(|(id, shard)| shard.info(id as ShardId))
+                    .collect::<Vec<_>>();
+
+                Response::ShardList(shards)
+            }
+
+            Request::Command(..) if !Server::is_primary(settings) => Response::NotPrimary,
+            Request::Command(command, signature) => match &settings.operator {
+                Some(operator) if signature.verify(operator, &command).is_ok() => {
+                    Server::execute(&mut database, operator, command)
                 }
+                _ => {
+                    log::warn!("Rejected unauthorized command from {}", address);
+                    Response::Unauthorized
+                }
+            },
 
-                Request::GetAddress(identity) => {
-                    if let Some(address) = database.addresses.get(&identity) {
-                        Response::Address(address.clone())
-                    } else {
-                        Response::AddressUnknown
-                    }
+            // Replication requests are only accepted over an
+            // authenticated connection (see `replicate`)
+            Request::Merge(_) | Request::Replicate => {
+                log::warn!("Rejected unauthenticated replication from {}", address);
+                Response::Unauthorized
+            }
+        }
+    }
+
+    fn execute(database: &mut Database, operator: &KeyCard, command: Command) -> Response {
+        // Every `Command` is signed along with a timestamp, and each operator's
+        // timestamps must strictly increase: replaying a `Command` the operator
+        // already issued is not allowed, and only one timestamp per operator is kept
+        let last = database.commands.entry(operator.identity()).or_insert(0);
+
+        if command.timestamp <= *last {
+            return Response::CommandReplayed;
+        }
+
+        *last = command.timestamp;
+
+        match command.order {
+            Order::CreateShard(size) => {
+                database.shards.push(Shard::new(size));
+                Response::ShardCreated((database.shards.len() - 1) as ShardId)
+            }
+
+            Order::ResizeShard(shard, _) | Order::CloseShard(shard)
+                if (shard as usize) >= database.shards.len() =>
+            {
+                Response::ShardIdInvalid
+            }
+
+            Order::ResizeShard(shard, _) if database.shards[shard as usize].closed => {
+                Response::ShardClosed
+            }
+            Order::ResizeShard(shard, size)
+                if size < database.shards[shard as usize].members.len() =>
+            {
+                Response::ShardSizeInvalid
+            }
+            Order::ResizeShard(shard, size) => {
+                let shard = &mut database.shards[shard as usize];
+
+                shard.size = size;
+                shard.version += 1;
+
+                Response::AcknowledgeCommand
+            }
+
+            Order::CloseShard(shard) => {
+                let shard = &mut database.shards[shard as usize];
+
+                if !shard.closed {
+                    shard.closed = true;
+                    shard.version += 1;
                 }
+
+                Response::AcknowledgeCommand
             }
-        };
+        }
+    }
+
+    async fn synchronize(settings: ServerSettings, database: Arc<Mutex<Database>>) {
+        if settings.peers.is_empty() {
+            return;
+        }
+
+        loop {
+            time::sleep(settings.sync_interval).await;
+
+            // Every server periodically pushes its whole state to its peers, which
+            // merge it into their own: unreachable peers are simply skipped, and
+            // catch up on a later round once they become reachable again
+            let snapshot = database.lock().clone();
+
+            settings
+                .peers
+                .iter()
+                .map(|(peer, keycard)| {
+                    let request = Request::Merge(snapshot.clone());
+                    let settings = &settings;
+
+                    async move {
+                        let push = Server::push(&settings.keychain, *peer, keycard, request);
+
+                        match time::timeout(settings.sync_interval, push).await {
+                            Ok(Ok(())) => {}
+                            Ok(Err(error)) => {
+                                log::debug!("Failed to synchronize with {}: {}", peer, error.top())
+                            }
+                            Err(_) => log::debug!("Synchronization with {} timed out", peer),
+                        }
+                    }
+                })
+                .collect::<FuturesUnordered<_>>()
+                .collect::<Vec<_>>()
+                .await;
+        }
+    }
+
+    async fn push(
+        keychain: &KeyChain,
+        peer: SocketAddr,
+        keycard: &KeyCard,
+        request: Request,
+    ) -> Result<(), Top<SyncError>> {
+        let mut connection = peer
+            .connect()
+            .await
+            .map_err(SyncError::connect_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
 
         connection
-            .send(&response)
+            .send(&Request::Replicate)
             .await
-            .pot(ServeError::ConnectionError, here!())?;
+            .pot(SyncError::ConnectionError, here!())?;
 
-        Ok(())
+        let mut connection = connection
+            .secure()
+            .await
+            .pot(SyncError::SecureFailed, here!())?;
+
+        let remote = connection
+            .authenticate(keychain)
+            .await
+            .pot(SyncError::AuthenticateFailed, here!())?;
+
+        if remote.identity() != keycard.identity() {
+            return SyncError::UnexpectedRemote.fail().spot(here!());
+        }
+
+        connection
+            .send(&request)
+            .await
+            .pot(SyncError::ConnectionError, here!())?;
+
+        match connection
+            .receive()
+            .await
+            .pot(SyncError::ConnectionError, here!())?
+        {
+            Response::AcknowledgeMerge => Ok(()),
+            _ => SyncError::UnexpectedResponse.fail().spot(here!()),
+        }
     }
 }
@@ -1,12 +1,28 @@
+use crate::crypto::{KeyCard, KeyChain};
+
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
 #[derive(Debug, Clone)]
 pub struct ServerSettings {
     pub shard_sizes: Vec<usize>,
+    pub keychain: KeyChain,
+    pub peers: Vec<(SocketAddr, KeyCard)>,
+    pub sync_interval: Duration,
+    pub operator: Option<KeyCard>,
+    pub persistence: Option<PathBuf>,
+    pub persistence_interval: Duration,
 }
 
 impl Default for ServerSettings {
     fn default() -> Self {
         ServerSettings {
             shard_sizes: vec![4],
+            keychain: KeyChain::random(),
+            peers: Vec::new(),
+            sync_interval: Duration::from_secs(1),
+            operator: None,
+            persistence: None,
+            persistence_interval: Duration::from_secs(1),
         }
     }
 }
@@ -0,0 +1,11 @@
+use crate::link::rendezvous::ShardId;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardInfo {
+    pub id: ShardId,
+    pub size: usize,
+    pub filled: usize,
+    pub closed: bool,
+}
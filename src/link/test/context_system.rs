@@ -2,7 +2,9 @@ use crate::{
     crypto::Identity,
     link::context::{ConnectDispatcher, ContextId, ListenDispatcher},
     net::{
-        test::{ConnectionPair, System as NetSystem},
+        test::{
+            ConnectionPair, Scenario, SimulatedSystem, SimulationSettings, System as NetSystem,
+        },
         Connector, Listener,
     },
 };
@@ -33,10 +35,7 @@ impl ContextSystem {
             listeners,
         } = NetSystem::setup(peers).await;
 
-        let connectors = connectors
-            .into_iter()
-            .map(|connector| ConnectDispatcher::new(connector))
-            .collect();
+        let connectors = connectors.into_iter().map(ConnectDispatcher::new).collect();
 
         let listeners = listeners
             .into_iter()
@@ -46,6 +45,19 @@ impl ContextSystem {
         ContextSystem::new(keys, connectors, listeners)
     }
 
+    /// Builds dispatchers over a `SimulatedSystem` (see `setup_wrapped`).
+    pub fn setup_simulated(
+        peers: usize,
+        settings: SimulationSettings,
+    ) -> (ContextSystem, Scenario) {
+        let (keys, connectors, listeners, scenario) =
+            SimulatedSystem::setup_wrapped(peers, settings, ConnectDispatcher::new, |listener| {
+                ListenDispatcher::new(listener, Default::default())
+            });
+
+        (ContextSystem::new(keys, connectors, listeners), scenario)
+    }
+
     pub async fn connect(
         &mut self,
         source: usize,
@@ -2,33 +2,44 @@ mod context {
     use crate::{
         crypto::Identity,
         link::{
-            context::{ConnectDispatcher, ListenDispatcher},
+            context::{
+                ConnectDispatcher, ConnectDispatcherError, ConnectorError, ContextId,
+                ListenDispatcher, ListenDispatcherError, ListenDispatcherSettings, Version,
+            },
             test::ContextSystem,
         },
         net::{
-            test::{System as NetSystem, TestConnector},
+            test::{ConnectionPair, System as NetSystem, TestConnector},
             traits::TcpConnect,
             Connector, Listener, PlainConnection,
         },
         time::test::join,
     };
 
+    use std::time::Duration;
+
+    use tokio::time;
+
+    fn context(namespace: &str) -> ContextId {
+        ContextId::new(namespace, Version::new(1, 0, 0))
+    }
+
     #[tokio::test]
     #[should_panic(expected = "called `register` twice for the same `context`")]
     async fn connector_double_register() {
         let ContextSystem { connectors, .. } = ContextSystem::setup(1).await;
 
-        let _connector_1 = connectors[0].register(format!("Context 1"));
-        let _connector_2 = connectors[0].register(format!("Context 1"));
+        let _connector_1 = connectors[0].register(context("Context 1"));
+        let _connector_2 = connectors[0].register(context("Context 1"));
     }
 
     #[tokio::test]
     async fn connector_register_again() {
         let ContextSystem { connectors, .. } = ContextSystem::setup(1).await;
 
-        let _connector_1 = connectors[0].register(format!("Context 1"));
+        let _connector_1 = connectors[0].register(context("Context 1"));
         drop(_connector_1);
-        let _connector_2 = connectors[0].register(format!("Context 1"));
+        let _connector_2 = connectors[0].register(context("Context 1"));
     }
 
     #[tokio::test]
@@ -36,27 +47,89 @@ mod context {
     async fn listener_double_register() {
         let ContextSystem { listeners, .. } = ContextSystem::setup(1).await;
 
-        let _listener_1 = listeners[0].register(format!("Context 1"));
-        let _listener_2 = listeners[0].register(format!("Context 1"));
+        let _listener_1 = listeners[0].register(context("Context 1"));
+        let _listener_2 = listeners[0].register(context("Context 1"));
     }
 
     #[tokio::test]
     async fn listener_register_again() {
         let ContextSystem { listeners, .. } = ContextSystem::setup(1).await;
 
-        let listener_1 = listeners[0].register(format!("Context 1"));
+        let listener_1 = listeners[0].register(context("Context 1"));
+        drop(listener_1);
+        let _listeners_2 = listeners[0].register(context("Context 1"));
+    }
+
+    #[tokio::test]
+    async fn try_register() {
+        let ContextSystem {
+            connectors,
+            listeners,
+            ..
+        } = ContextSystem::setup(1).await;
+
+        let _connector = connectors[0].try_register(context("Context 1")).unwrap();
+
+        match connectors[0]
+            .try_register(context("Context 1"))
+            .err()
+            .unwrap()
+            .top()
+        {
+            ConnectDispatcherError::AlreadyRegistered { .. } => (),
+            error => panic!("unexpected error upon registering twice: {}", error),
+        }
+
+        let _listener = listeners[0].try_register(context("Context 1")).unwrap();
+
+        match listeners[0]
+            .try_register(context("Context 1"))
+            .err()
+            .unwrap()
+            .top()
+        {
+            ListenDispatcherError::AlreadyRegistered { .. } => (),
+        }
+
+        let _other_version = listeners[0]
+            .try_register(ContextId::new("Context 1", Version::new(1, 1, 0)))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn contexts() {
+        let ContextSystem {
+            keys,
+            connectors,
+            listeners,
+        } = ContextSystem::setup(2).await;
+
+        assert!(connectors[0].contexts(keys[1]).await.unwrap().is_empty());
+
+        let listener_1 = listeners[1].register(context("Context 1"));
+        let _listener_2 = listeners[1].register(context("Context 2"));
+
+        let expected = vec![context("Context 1"), context("Context 2")];
+
+        assert_eq!(listeners[1].contexts(), expected);
+        assert_eq!(connectors[0].contexts(keys[1]).await.unwrap(), expected);
+
         drop(listener_1);
-        let _listeners_2 = listeners[0].register(format!("Context 1"));
+
+        assert_eq!(
+            connectors[0].contexts(keys[1]).await.unwrap(),
+            vec![context("Context 2")]
+        );
     }
 
     #[tokio::test]
     async fn simple() {
-        let mut system: ContextSystem = ContextSystem::setup(2).await.into();
+        let mut system: ContextSystem = ContextSystem::setup(2).await;
 
         let sent = 42;
 
         let received = system
-            .connect(0, 1, format!("Context 1"))
+            .connect(0, 1, context("Context 1"))
             .await
             .transmit(&sent)
             .await
@@ -69,13 +142,13 @@ mod context {
     async fn stress() {
         let peer = 10;
 
-        let mut system: ContextSystem = ContextSystem::setup(peer).await.into();
+        let mut system: ContextSystem = ContextSystem::setup(peer).await;
 
         let handles = system
-            .connection_matrix(format!("Context"))
+            .connection_matrix(context("Context"))
             .await
             .into_iter()
-            .map(|row| {
+            .flat_map(|row| {
                 row.into_iter().map(|mut pair| {
                     tokio::spawn(async move {
                         let sent: u32 = 42;
@@ -84,8 +157,7 @@ mod context {
                         assert_eq!(received, sent);
                     })
                 })
-            })
-            .flatten();
+            });
 
         join(handles).await.unwrap();
     }
@@ -94,7 +166,7 @@ mod context {
 
     impl SlowLoris {
         async fn connect(&self, identity: Identity) -> PlainConnection {
-            let address = self.0.peers.get(&identity).unwrap().clone();
+            let address = self.0.peers.get(&identity).unwrap();
             address.connect().await.unwrap()
 
             // does not complete (no `secure` or `authenticate`)
@@ -107,10 +179,10 @@ mod context {
             keys,
             mut connectors,
             mut listeners,
-        } = NetSystem::setup(2).await.into();
+        } = NetSystem::setup(2).await;
 
         let mut listener = ListenDispatcher::new(listeners.remove(1), Default::default())
-            .register(format!("Context"));
+            .register(context("Context"));
 
         let accept_handle = tokio::spawn(async move {
             let _connection = listener.accept().await.unwrap();
@@ -119,7 +191,7 @@ mod context {
         let slow_loris = SlowLoris(connectors.remove(0));
         let _slow_connection = slow_loris.connect(keys[1]).await;
 
-        let connector = ConnectDispatcher::new(connectors.remove(0)).register(format!("Context"));
+        let connector = ConnectDispatcher::new(connectors.remove(0)).register(context("Context"));
 
         let connect_handle = tokio::spawn(async move {
             let _connection = connector.connect(keys[1]).await.unwrap();
@@ -129,4 +201,167 @@ mod context {
             .await
             .expect("Stuck handling a slow loris");
     }
+
+    #[tokio::test]
+    async fn version_negotiation() {
+        let ContextSystem {
+            keys,
+            connectors,
+            listeners,
+        } = ContextSystem::setup(2).await;
+
+        let mut registered = [
+            Version::new(1, 2, 0),
+            Version::new(1, 5, 0),
+            Version::new(2, 0, 0),
+        ]
+        .iter()
+        .map(|version| listeners[1].register(ContextId::new("Context", *version)))
+        .collect::<Vec<_>>();
+
+        let connector = connectors[0].register(ContextId::new("Context", Version::new(1, 3, 0)));
+
+        let (version, _connection) = connector.negotiate(keys[1]).await.unwrap();
+        assert_eq!(version, Version::new(1, 5, 0));
+
+        let _connection = registered[1].accept().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn version_mismatch() {
+        let ContextSystem {
+            keys,
+            connectors,
+            listeners,
+        } = ContextSystem::setup(2).await;
+
+        let _listener = listeners[1].register(ContextId::new("Context", Version::new(1, 0, 0)));
+
+        let connector = connectors[0].register(ContextId::new("Context", Version::new(1, 1, 0)));
+
+        match connector.negotiate(keys[1]).await.err().unwrap().top() {
+            ConnectorError::VersionMismatch { available } => {
+                assert_eq!(*available, vec![Version::new(1, 0, 0)])
+            }
+            error => panic!("unexpected error upon negotiating version: {}", error),
+        }
+
+        let connector = connectors[0].register(context("Other context"));
+
+        match connector.negotiate(keys[1]).await.err().unwrap().top() {
+            ConnectorError::ContextRefused => (),
+            error => panic!(
+                "unexpected error upon connecting to missing context: {}",
+                error
+            ),
+        }
+    }
+
+    async fn setup_bounded(
+        delivery_timeout: Option<Duration>,
+    ) -> (Vec<Identity>, ConnectDispatcher, ListenDispatcher) {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(2).await;
+
+        let connector = ConnectDispatcher::new(connectors.remove(0));
+
+        let listener = ListenDispatcher::new(
+            listeners.remove(1),
+            ListenDispatcherSettings {
+                channel_capacity: 1,
+                delivery_timeout,
+            },
+        );
+
+        (keys, connector, listener)
+    }
+
+    #[tokio::test]
+    async fn context_busy() {
+        let (keys, connector, listener) = setup_bounded(None).await;
+
+        let mut listener = listener.register(context("Context"));
+        let connector = connector.register(context("Context"));
+
+        let _first = connector.connect(keys[1]).await.unwrap();
+
+        let error = connector.negotiate(keys[1]).await.err().unwrap();
+
+        match error.top() {
+            ConnectorError::ContextBusy => assert!(error.top().is_retryable()),
+            error => panic!(
+                "unexpected error upon connecting to busy context: {}",
+                error
+            ),
+        }
+
+        let _accepted = listener.accept().await.unwrap();
+        let _second = connector.connect(keys[1]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn delivery_timeout() {
+        let (keys, connector, listener) = setup_bounded(Some(Duration::from_secs(5))).await;
+
+        let mut listener = listener.register(context("Context"));
+        let connector = connector.register(context("Context"));
+
+        let _first = connector.connect(keys[1]).await.unwrap();
+
+        let accept_handle = tokio::spawn(async move {
+            time::sleep(Duration::from_millis(100)).await;
+
+            for _ in 0..2 {
+                let _connection = listener.accept().await.unwrap();
+            }
+        });
+
+        let _second = connector.connect(keys[1]).await.unwrap();
+
+        accept_handle.await.unwrap();
+    }
+
+    async fn transmit<C, L>(connector: &C, listener: &mut L, remote: Identity) -> u32
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let (connection, accepted) = futures::join!(connector.connect(remote), listener.accept());
+
+        ConnectionPair::new(connection.unwrap(), accepted.unwrap().1)
+            .transmit(&42u32)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn partition_and_disconnect() {
+        time::pause();
+
+        let (system, scenario) = ContextSystem::setup_simulated(2, Default::default());
+
+        let connector = system.connectors[0].register(context("Context"));
+        let mut listener = system.listeners[1].register(context("Context"));
+
+        scenario.partition(vec![0], vec![1]);
+        assert!(connector.connect(system.keys[1]).await.is_err());
+
+        scenario.heal();
+        assert_eq!(
+            transmit(&connector, &mut listener, system.keys[1]).await,
+            42u32
+        );
+
+        scenario.disconnect(1);
+        assert!(connector.connect(system.keys[1]).await.is_err());
+
+        scenario.reconnect(1);
+        assert_eq!(
+            transmit(&connector, &mut listener, system.keys[1]).await,
+            42u32
+        );
+    }
 }
@@ -1,7 +1,7 @@
-use crate::net::{ReceiverSettings, SenderSettings};
+use crate::net::{receiver_settings::MAX_FRAME_SIZE_DEFAULT, ReceiverSettings, SenderSettings};
 
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
     time::Duration,
 };
 
@@ -9,6 +9,7 @@ use std::{
 pub struct ConnectionSettings {
     pub send_timeout: Option<Duration>,
     pub receive_timeout: Option<Duration>,
+    pub max_frame_size: usize,
 }
 
 const SEND_TIMEOUT_DEFAULT: u64 = 0;
@@ -16,6 +17,7 @@ const RECEIVE_TIMEOUT_DEFAULT: u64 = 0;
 
 static SEND_TIMEOUT: AtomicU64 = AtomicU64::new(SEND_TIMEOUT_DEFAULT);
 static RECEIVE_TIMEOUT: AtomicU64 = AtomicU64::new(RECEIVE_TIMEOUT_DEFAULT);
+static MAX_FRAME_SIZE: AtomicUsize = AtomicUsize::new(MAX_FRAME_SIZE_DEFAULT);
 
 impl Default for ConnectionSettings {
     fn default() -> Self {
@@ -38,6 +40,7 @@ impl Default for ConnectionSettings {
         ConnectionSettings {
             send_timeout,
             receive_timeout,
+            max_frame_size: MAX_FRAME_SIZE.load(Ordering::Relaxed),
         }
     }
 }
@@ -50,6 +53,7 @@ impl ConnectionSettings {
             },
             ReceiverSettings {
                 receive_timeout: self.receive_timeout,
+                max_frame_size: self.max_frame_size,
             },
         )
     }
@@ -79,7 +83,12 @@ impl ConnectionSettings {
             0
         };
 
+        if settings.max_frame_size == 0 {
+            panic!("called `ConnectionSettings::set_default` with a null `max_frame_size`")
+        }
+
         SEND_TIMEOUT.store(send_timeout, Ordering::Relaxed);
         RECEIVE_TIMEOUT.store(receive_timeout, Ordering::Relaxed);
+        MAX_FRAME_SIZE.store(settings.max_frame_size, Ordering::Relaxed);
     }
 }
@@ -38,12 +38,15 @@ impl PlainReceiver {
     where
         M: for<'de> Deserialize<'de>,
     {
-        time::optional_timeout(self.settings.receive_timeout, self.unit_receiver.receive())
-            .await
-            .pot(PlainConnectionError::ReceiveTimeout, here!())?
-            .map_err(PlainConnectionError::read_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())?;
+        time::optional_timeout(
+            self.settings.receive_timeout,
+            self.unit_receiver.receive(self.settings.max_frame_size),
+        )
+        .await
+        .pot(PlainConnectionError::ReceiveTimeout, here!())?
+        .map_err(PlainConnectionError::read_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
 
         bincode::deserialize(self.unit_receiver.as_slice())
             .map_err(PlainConnectionError::deserialize_failed)
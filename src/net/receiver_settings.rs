@@ -2,15 +2,23 @@ use crate::net::ConnectionSettings;
 
 use std::time::Duration;
 
+// Frames are length-prefixed by a `u32`: without a bound, a single
+// malformed prefix could cause the allocation of up to 4 GiB
+pub(in crate::net) const MAX_FRAME_SIZE_DEFAULT: usize = 64 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct ReceiverSettings {
     pub receive_timeout: Option<Duration>,
+    pub max_frame_size: usize,
 }
 
 impl Default for ReceiverSettings {
     fn default() -> Self {
+        let settings = ConnectionSettings::default();
+
         ReceiverSettings {
-            receive_timeout: ConnectionSettings::default().receive_timeout,
+            receive_timeout: settings.receive_timeout,
+            max_frame_size: settings.max_frame_size,
         }
     }
 }
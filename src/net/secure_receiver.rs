@@ -35,12 +35,15 @@ impl SecureReceiver {
     where
         M: for<'de> Deserialize<'de>,
     {
-        time::optional_timeout(self.settings.receive_timeout, self.unit_receiver.receive())
-            .await
-            .pot(SecureConnectionError::ReceiveTimeout, here!())?
-            .map_err(SecureConnectionError::read_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())?;
+        time::optional_timeout(
+            self.settings.receive_timeout,
+            self.unit_receiver.receive(self.settings.max_frame_size),
+        )
+        .await
+        .pot(SecureConnectionError::ReceiveTimeout, here!())?
+        .map_err(SecureConnectionError::read_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
 
         self.channel_receiver
             .decrypt_in_place(self.unit_receiver.as_vec())
@@ -51,12 +54,15 @@ impl SecureReceiver {
     where
         M: for<'de> Deserialize<'de>,
     {
-        time::optional_timeout(self.settings.receive_timeout, self.unit_receiver.receive())
-            .await
-            .pot(SecureConnectionError::ReceiveTimeout, here!())?
-            .map_err(SecureConnectionError::read_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())?;
+        time::optional_timeout(
+            self.settings.receive_timeout,
+            self.unit_receiver.receive(self.settings.max_frame_size),
+        )
+        .await
+        .pot(SecureConnectionError::ReceiveTimeout, here!())?
+        .map_err(SecureConnectionError::read_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
 
         self.channel_receiver
             .authenticate(self.unit_receiver.as_vec())
@@ -67,12 +73,15 @@ impl SecureReceiver {
     where
         M: for<'de> Deserialize<'de>,
     {
-        time::optional_timeout(self.settings.receive_timeout, self.unit_receiver.receive())
-            .await
-            .pot(SecureConnectionError::ReceiveTimeout, here!())?
-            .map_err(SecureConnectionError::read_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())?;
+        time::optional_timeout(
+            self.settings.receive_timeout,
+            self.unit_receiver.receive(self.settings.max_frame_size),
+        )
+        .await
+        .pot(SecureConnectionError::ReceiveTimeout, here!())?
+        .map_err(SecureConnectionError::read_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
 
         bincode::deserialize(self.unit_receiver.as_slice())
             .map_err(SecureConnectionError::deserialize_failed)
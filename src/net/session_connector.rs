@@ -33,6 +33,7 @@ struct Pool {
     connections: HashMap<Identity, Vec<Arc<AtomicLender<State>>>>,
 }
 
+#[allow(clippy::large_enum_variant)]
 enum State {
     Healthy(SecureConnection),
     Broken,
@@ -83,31 +84,28 @@ impl SessionConnector {
             let mut pool = self.pool.lock();
 
             // Try to get a `Healthy` connection from `pool`
-            pool.connections
-                .get_mut(&remote)
-                .map(|states| {
-                    // This contains the `State`s which could not be `try_take`n
-                    // because they're currently being pinged by `keep_alive`
-                    let mut restore = Vec::new();
-
-                    let connection = loop {
-                        let state = match states.pop() {
-                            Some(state) => state,
-                            None => break None, // `states` exhausted, no connection available
-                        };
-
-                        match state.try_take() {
-                            Some(State::Healthy(connection)) => break Some(connection),
-                            Some(State::Broken) => {} // If `state` is `Broken`, garbage collect
-                            None => restore.push(state), // Currently pinging, store in `restore` (see above)
-                        }
+            pool.connections.get_mut(&remote).and_then(|states| {
+                // This contains the `State`s which could not be `try_take`n
+                // because they're currently being pinged by `keep_alive`
+                let mut restore = Vec::new();
+
+                let connection = loop {
+                    let state = match states.pop() {
+                        Some(state) => state,
+                        None => break None, // `states` exhausted, no connection available
                     };
 
-                    // Flush `restore` back in `states`
-                    states.extend(restore);
-                    connection
-                })
-                .flatten()
+                    match state.try_take() {
+                        Some(State::Healthy(connection)) => break Some(connection),
+                        Some(State::Broken) => {} // If `state` is `Broken`, garbage collect
+                        None => restore.push(state), // Currently pinging, store in `restore` (see above)
+                    }
+                };
+
+                // Flush `restore` back in `states`
+                states.extend(restore);
+                connection
+            })
         };
 
         let connection = if let Some(mut connection) = connection {
@@ -131,10 +129,7 @@ impl SessionConnector {
                     let state = state.clone();
                     let mut pool = pool.lock();
 
-                    pool.connections
-                        .entry(remote)
-                        .or_insert(Vec::new())
-                        .push(state);
+                    pool.connections.entry(remote).or_default().push(state);
                 }
 
                 fuse.spawn(async move {
@@ -205,7 +200,10 @@ impl SessionConnector {
 mod tests {
     use super::*;
 
-    use crate::net::{test::System, SessionListener};
+    use crate::net::{
+        test::{SimulatedSystem, System},
+        SessionListener,
+    };
 
     use futures::stream::{FuturesUnordered, StreamExt};
 
@@ -307,7 +305,7 @@ mod tests {
 
         let connectors = connectors
             .into_iter()
-            .map(|connector| SessionConnector::new(connector))
+            .map(SessionConnector::new)
             .collect::<Vec<_>>();
 
         let mut listener = SessionListener::new(listeners.remove(0));
@@ -325,7 +323,7 @@ mod tests {
             .into_iter()
             .zip(keys.clone())
             .map(|(connector, identity)| {
-                let remote = keys[0].clone();
+                let remote = keys[0];
 
                 async move {
                     for _ in 0..10 {
@@ -359,7 +357,7 @@ mod tests {
 
         let listeners = listeners
             .into_iter()
-            .map(|listener| SessionListener::new(listener))
+            .map(SessionListener::new)
             .collect::<Vec<_>>();
 
         tokio::spawn(async move {
@@ -379,7 +377,7 @@ mod tests {
         });
 
         let connector = Arc::new(SessionConnector::new(connectors.remove(0)));
-        let identity = keys[0].clone();
+        let identity = keys[0];
 
         keys.into_iter()
             .map(|remote| {
@@ -422,7 +420,7 @@ mod tests {
 
         let listeners = listeners
             .into_iter()
-            .map(|listener| SessionListener::new(listener))
+            .map(SessionListener::new)
             .collect::<Vec<_>>();
 
         tokio::spawn(async move {
@@ -482,13 +480,15 @@ mod tests {
     }
 
     #[tokio::test]
-    #[ignore]
     async fn keepalive_sequence() {
-        let System {
+        time::pause();
+
+        let SimulatedSystem {
             mut connectors,
             mut listeners,
             keys,
-        } = System::setup(2).await;
+            ..
+        } = SimulatedSystem::setup(2, Default::default());
 
         let connector = SessionConnector::new(connectors.remove(0));
         let mut listener = SessionListener::new(listeners.remove(1));
@@ -517,4 +517,60 @@ mod tests {
             assert_eq!(connections.len(), 1);
         }
     }
+
+    #[tokio::test]
+    async fn pooled_connection_after_partition() {
+        time::pause();
+
+        let system = SimulatedSystem::setup(2, Default::default());
+        let scenario = system.scenario();
+
+        let SimulatedSystem {
+            mut connectors,
+            mut listeners,
+            keys,
+            ..
+        } = system;
+
+        let connector = SessionConnector::new(connectors.remove(0));
+        let mut listener = SessionListener::new(listeners.remove(1));
+
+        tokio::spawn(async move {
+            loop {
+                let (_, mut session) = listener.accept().await;
+                assert_eq!(session.receive::<u32>().await.unwrap(), 42u32);
+                session.send(&43u32).await.unwrap();
+                session.end();
+            }
+        });
+
+        let connector = &connector;
+        let keys = &keys;
+
+        let exchange = || async move {
+            let mut session = connector.connect(keys[1]).await.unwrap();
+            session.send(&42u32).await.unwrap();
+            assert_eq!(session.receive::<u32>().await.unwrap(), 43u32);
+            session.end();
+
+            // Give `session` time to return to the pool
+            time::sleep(Duration::from_millis(10)).await;
+        };
+
+        exchange().await;
+
+        scenario.partition(vec![0], vec![1]);
+
+        // The pooled connection is broken by the partition
+        assert!(connector.connect(keys[1]).await.is_err());
+
+        scenario.heal();
+
+        // The broken connection is discarded, and a new one established
+        exchange().await;
+
+        for connections in connector.pool.lock().connections.values() {
+            assert_eq!(connections.len(), 1);
+        }
+    }
 }
@@ -69,7 +69,9 @@ impl SessionListener {
     {
         loop {
             if let Ok((remote, connection)) = listener.accept().await {
-                let _ = connection_inlet.try_send((remote, connection));
+                // Waiting for room (instead of dropping `connection`) stops
+                // accepting from `listener`, which then pushes back on its remotes
+                let _ = connection_inlet.send((remote, connection)).await;
             }
         }
     }
@@ -110,7 +112,9 @@ impl SessionListener {
 
             match control {
                 SessionControl::Connect => {
-                    let _ = connection_inlet.try_send((remote, connection));
+                    // The remote is already using `connection`: it must be
+                    // delivered, even if that means waiting for room
+                    let _ = connection_inlet.send((remote, connection)).await;
                     return Ok(());
                 }
                 SessionControl::KeepAlive => {
@@ -0,0 +1,8 @@
+/// Direction of the traffic through a `TcpProxy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the client to the server
+    Upstream,
+    /// From the server to the client
+    Downstream,
+}
@@ -0,0 +1,38 @@
+use std::{num::NonZeroU64, ops::Range, time::Duration};
+
+/// Faults injected by a `TcpProxy` in one `Direction`:
+/// - Every chunk read is forwarded after `latency`, plus up to `jitter`,
+///   without ever being reordered.
+/// - If `bandwidth` (in bytes per second) is set, forwarding is throttled accordingly.
+///   Bytes awaiting forwarding are bounded: past that, the proxy stops reading.
+/// - Each byte forwarded has one bit flipped with probability `corruption`.
+/// - Each byte whose offset (counting every byte read in that `Direction`
+///   since the connection was opened) falls in `corrupt_range` has its lowest
+///   bit flipped.
+/// - If `truncate_after` is set, the connection is closed (on both ends)
+///   once that many bytes were forwarded, even in the middle of a frame.
+/// - If `blackhole` is set, everything read is silently dropped.
+#[derive(Debug, Clone)]
+pub struct Faults {
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub bandwidth: Option<NonZeroU64>,
+    pub corruption: f64,
+    pub corrupt_range: Option<Range<usize>>,
+    pub truncate_after: Option<usize>,
+    pub blackhole: bool,
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Faults {
+            latency: Duration::from_millis(0),
+            jitter: Duration::from_millis(0),
+            bandwidth: None,
+            corruption: 0.,
+            corrupt_range: None,
+            truncate_after: None,
+            blackhole: false,
+        }
+    }
+}
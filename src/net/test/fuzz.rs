@@ -0,0 +1,339 @@
+use crate::{
+    crypto::primitives::{
+        channel::{self, ChannelError, Receiver as ChannelReceiver, Sender as ChannelSender},
+        exchange::KeyPair,
+    },
+    net::{PlainConnection, PlainConnectionError, Socket},
+};
+
+use futures::executor;
+
+use serde::Deserialize;
+
+use std::{
+    io::{self, Cursor, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+// A `Socket` backed by an in-memory buffer, kept private so that
+// `Cursor<Vec<u8>>` does not implement `Socket` outside of this module
+struct Feed(Cursor<Vec<u8>>);
+
+impl Feed {
+    fn connection(data: &[u8]) -> PlainConnection {
+        let socket: Box<dyn Socket> = Box::new(Feed(Cursor::new(data.to_vec())));
+        PlainConnection::from(socket)
+    }
+}
+
+impl AsyncRead for Feed {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Feed {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+impl Socket for Feed {}
+
+/// Feeds `data` to a `PlainConnection`, `receive`ing frames until
+/// `data` is exhausted. Returns the number of frames decoded.
+/// Panics if decoding fails with an unexpected error.
+pub fn frames(data: &[u8]) -> usize {
+    let mut connection = Feed::connection(data);
+
+    let mut decoded = 0;
+
+    loop {
+        match executor::block_on(connection.receive::<Vec<u8>>()) {
+            Ok(_) => decoded += 1,
+            Err(error) => {
+                match error.top() {
+                    PlainConnectionError::ReadFailed { source } => assert!(
+                        matches!(
+                            source.kind(),
+                            ErrorKind::UnexpectedEof | ErrorKind::InvalidData
+                        ),
+                        "unexpected read error: {}",
+                        source
+                    ),
+                    PlainConnectionError::DeserializeFailed { .. } => {}
+                    error => panic!("unexpected error upon `receive`ing: {}", error),
+                }
+
+                return decoded;
+            }
+        }
+    }
+}
+
+/// Deserializes `data` as an `M`, as connections do upon `receive`ing.
+/// Panics if deserialization fails with an unexpected error.
+pub fn decode<M>(data: &[u8]) -> Option<M>
+where
+    M: for<'de> Deserialize<'de>,
+{
+    match bincode::deserialize(data) {
+        Ok(message) => Some(message),
+        Err(error) => {
+            match *error {
+                bincode::ErrorKind::Io(ref source) => {
+                    assert_eq!(source.kind(), ErrorKind::UnexpectedEof)
+                }
+                bincode::ErrorKind::InvalidUtf8Encoding(_)
+                | bincode::ErrorKind::InvalidBoolEncoding(_)
+                | bincode::ErrorKind::InvalidCharEncoding
+                | bincode::ErrorKind::InvalidTagEncoding(_)
+                | bincode::ErrorKind::Custom(_) => {}
+                ref error => panic!("unexpected error upon deserializing: {}", error),
+            }
+
+            None
+        }
+    }
+}
+
+/// Panics unless every strict prefix of `encoding` fails to deserialize
+/// as an `M` with `UnexpectedEof`.
+pub fn truncations<M>(encoding: &[u8])
+where
+    M: for<'de> Deserialize<'de>,
+{
+    for cut in 0..encoding.len() {
+        match bincode::deserialize::<M>(&encoding[..cut]) {
+            Ok(_) => panic!("truncated encoding was deserialized"),
+            Err(error) => match *error {
+                bincode::ErrorKind::Io(ref source) => {
+                    assert_eq!(source.kind(), ErrorKind::UnexpectedEof)
+                }
+                ref error => panic!("unexpected error upon deserializing: {}", error),
+            },
+        }
+    }
+}
+
+/// Feeds `data` to a fresh `channel::Receiver::decrypt`.
+/// Panics unless decryption fails with `ChannelError::DecryptFailed`.
+pub fn decrypt(data: &[u8]) {
+    let (_, mut receiver) = channels();
+
+    match receiver.decrypt::<Vec<u8>>(data) {
+        Ok(_) => panic!("forged ciphertext was decrypted"),
+        Err(error) => match error.top() {
+            ChannelError::DecryptFailed => {}
+            error => panic!("unexpected error upon `decrypt`ing: {}", error),
+        },
+    }
+}
+
+/// Feeds `data` to a fresh `channel::Receiver::authenticate`.
+/// Panics unless authentication fails with `ChannelError::AuthenticateFailed`.
+pub fn authenticate(data: &[u8]) {
+    let (_, mut receiver) = channels();
+
+    match receiver.authenticate::<Vec<u8>>(data) {
+        Ok(_) => panic!("forged tag was authenticated"),
+        Err(error) => match error.top() {
+            ChannelError::AuthenticateFailed => {}
+            error => panic!("unexpected error upon `authenticate`ing: {}", error),
+        },
+    }
+}
+
+fn channels() -> (ChannelSender, ChannelReceiver) {
+    let alice = KeyPair::random();
+    let bob = KeyPair::random();
+
+    let bob_public = bob.public();
+    let alice_public = alice.public();
+
+    let (alice_key, alice_role) = alice.exchange(bob_public);
+    let (bob_key, bob_role) = bob.exchange(alice_public);
+
+    let (sender, _) = channel::channel(alice_key, alice_role);
+    let (_, receiver) = channel::channel(bob_key, bob_role);
+
+    (sender, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::receiver_settings::MAX_FRAME_SIZE_DEFAULT;
+
+    use proptest::{collection, prelude::*};
+
+    use std::mem;
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let payload = bincode::serialize(payload).unwrap();
+
+        let mut frame = (payload.len() as u32).to_le_bytes().to_vec();
+        frame.extend_from_slice(&payload);
+        frame
+    }
+
+    #[derive(Debug, Clone)]
+    enum Corruption {
+        None,
+        Flip(usize, u8),
+        Truncate(usize),
+        Replace(Vec<u8>),
+    }
+
+    fn corruption() -> impl Strategy<Value = Corruption> {
+        prop_oneof![
+            2 => Just(Corruption::None),
+            1 => (any::<usize>(), 1..=u8::MAX).prop_map(|(at, mask)| Corruption::Flip(at, mask)),
+            1 => any::<usize>().prop_map(Corruption::Truncate),
+            1 => collection::vec(any::<u8>(), 0..64).prop_map(Corruption::Replace),
+        ]
+    }
+
+    impl Corruption {
+        // Returns `None` if `bytes` are left untouched
+        fn apply(&self, mut bytes: Vec<u8>) -> Option<Vec<u8>> {
+            match self {
+                Corruption::None => return None,
+                Corruption::Flip(at, mask) => {
+                    let at = at % bytes.len();
+                    bytes[at] ^= mask;
+                }
+                Corruption::Truncate(at) => bytes.truncate(at % bytes.len()),
+                Corruption::Replace(replacement) => bytes = replacement.clone(),
+            }
+
+            Some(bytes)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn frames_arbitrary(data in collection::vec(any::<u8>(), 0..4096)) {
+            frames(&data);
+        }
+
+        #[test]
+        fn frames_sequence(
+            payloads in collection::vec(collection::vec(any::<u8>(), 0..256), 0..16),
+            tail in collection::vec(any::<u8>(), 0..64),
+        ) {
+            let mut data = payloads
+                .iter()
+                .flat_map(|payload| frame(payload))
+                .collect::<Vec<_>>();
+
+            data.extend_from_slice(&tail);
+
+            // Every well-formed frame is decoded before `tail` is reached
+            prop_assert!(frames(&data) >= payloads.len());
+        }
+
+        #[test]
+        fn frames_oversized(size in (MAX_FRAME_SIZE_DEFAULT as u32 + 1)..=u32::MAX) {
+            let data = size.to_le_bytes();
+
+            let mut connection = Feed::connection(&data);
+
+            let error = executor::block_on(connection.receive::<Vec<u8>>()).unwrap_err();
+
+            match error.top() {
+                PlainConnectionError::ReadFailed { source } => {
+                    prop_assert_eq!(source.kind(), ErrorKind::InvalidData)
+                }
+                error => panic!("unexpected error upon `receive`ing: {}", error),
+            }
+        }
+
+        #[test]
+        fn frames_truncated(payload in collection::vec(any::<u8>(), 1..256), cut in any::<usize>()) {
+            let data = frame(&payload);
+            let cut = mem::size_of::<u32>() + cut % (data.len() - mem::size_of::<u32>());
+
+            prop_assert_eq!(frames(&data[..cut]), 0);
+        }
+
+        #[test]
+        fn decrypt_arbitrary(data in collection::vec(any::<u8>(), 0..1024)) {
+            decrypt(&data);
+        }
+
+        #[test]
+        fn authenticate_arbitrary(data in collection::vec(any::<u8>(), 0..1024)) {
+            authenticate(&data);
+        }
+
+        #[test]
+        fn decrypt_sequence(
+            steps in collection::vec((collection::vec(any::<u8>(), 0..256), corruption()), 0..32)
+        ) {
+            let (mut sender, mut receiver) = channels();
+
+            // Corrupted ciphertexts are rejected without affecting the
+            // decryption of subsequent ciphertexts
+            for (message, corruption) in steps {
+                let ciphertext = sender.encrypt(&message).unwrap();
+
+                match corruption.apply(ciphertext.clone()) {
+                    None => {
+                        prop_assert_eq!(receiver.decrypt::<Vec<u8>>(&ciphertext).unwrap(), message)
+                    }
+                    Some(corrupted) => match receiver.decrypt::<Vec<u8>>(&corrupted) {
+                        Err(error) => {
+                            prop_assert!(matches!(error.top(), ChannelError::DecryptFailed))
+                        }
+                        Ok(_) => prop_assert_eq!(corrupted, ciphertext),
+                    },
+                }
+            }
+        }
+
+        #[test]
+        fn authenticate_sequence(
+            steps in collection::vec((collection::vec(any::<u8>(), 0..256), corruption()), 0..32)
+        ) {
+            let (mut sender, mut receiver) = channels();
+
+            for (message, corruption) in steps {
+                let authenticated = sender.authenticate(&message).unwrap();
+
+                match corruption.apply(authenticated.clone()) {
+                    None => prop_assert_eq!(
+                        receiver.authenticate::<Vec<u8>>(&authenticated).unwrap(),
+                        message
+                    ),
+                    Some(corrupted) => match receiver.authenticate::<Vec<u8>>(&corrupted) {
+                        Err(error) => {
+                            prop_assert!(matches!(error.top(), ChannelError::AuthenticateFailed))
+                        }
+                        Ok(_) => prop_assert_eq!(corrupted, authenticated),
+                    },
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Behaviour of a simulated, unidirectional link. Each write is delivered
+/// after `latency`, plus up to `jitter`. With probability `loss`, a write is
+/// lost and retransmitted, delaying it by a further `retransmission_delay`.
+/// Writes are never reordered.
+#[derive(Debug, Clone)]
+pub struct LinkSettings {
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub loss: f64,
+    pub retransmission_delay: Duration,
+}
+
+impl Default for LinkSettings {
+    fn default() -> Self {
+        LinkSettings {
+            latency: Duration::from_millis(10),
+            jitter: Duration::from_millis(0),
+            loss: 0.,
+            retransmission_delay: Duration::from_millis(200),
+        }
+    }
+}
@@ -1,10 +1,32 @@
+mod direction;
+mod faults;
+mod link_settings;
 mod pair;
+mod scenario;
+mod simulated_connector;
+mod simulated_listener;
+mod simulated_network;
+mod simulated_socket;
+mod simulated_system;
+mod simulation_settings;
 mod system;
 mod tcp_proxy;
 mod test_connector;
 mod test_listener;
 
+pub mod fuzz;
+
+pub use direction::Direction;
+pub use faults::Faults;
+pub use link_settings::LinkSettings;
 pub use pair::ConnectionPair;
+pub use scenario::Scenario;
+pub use simulated_connector::{SimulatedConnector, SimulatedConnectorError};
+pub use simulated_listener::SimulatedListener;
+pub use simulated_network::SimulatedNetwork;
+pub use simulated_socket::SimulatedSocket;
+pub use simulated_system::SimulatedSystem;
+pub use simulation_settings::SimulationSettings;
 pub use system::System;
 pub use tcp_proxy::TcpProxy;
 pub use test_connector::{TestConnector, TestConnectorError};
@@ -0,0 +1,93 @@
+use crate::{
+    crypto::{Identity, KeyChain},
+    net::test::{SimulatedConnector, SimulatedListener, SimulatedNetwork},
+};
+
+use std::time::Duration;
+
+/// Failure scenario over a `SimulatedNetwork`, addressing peers by their
+/// index in `keys`. A disconnected peer keeps running (and its state), but
+/// is unreachable until `reconnect`ed. A crashed peer, instead, loses its
+/// connections and endpoints: its state is lost along with whatever was built
+/// upon them (which should be dropped), and it comes back with fresh
+/// endpoints upon `restart`.
+#[derive(Clone)]
+pub struct Scenario {
+    keychains: Vec<KeyChain>,
+    keys: Vec<Identity>,
+    network: SimulatedNetwork,
+}
+
+impl Scenario {
+    pub fn new(keychains: Vec<KeyChain>, network: SimulatedNetwork) -> Self {
+        let keys = keychains
+            .iter()
+            .map(|keychain| keychain.keycard().identity())
+            .collect();
+
+        Scenario {
+            keychains,
+            keys,
+            network,
+        }
+    }
+
+    pub fn keys(&self) -> &[Identity] {
+        self.keys.as_slice()
+    }
+
+    pub fn network(&self) -> &SimulatedNetwork {
+        &self.network
+    }
+
+    pub fn partition<S, O>(&self, side: S, other: O)
+    where
+        S: IntoIterator<Item = usize>,
+        O: IntoIterator<Item = usize>,
+    {
+        self.network
+            .partition(self.identities(side), self.identities(other));
+    }
+
+    /// Partitions `peer` from every other peer.
+    pub fn isolate(&self, peer: usize) {
+        self.network
+            .isolate(vec![self.keys[peer]], Duration::from_secs(0), None);
+    }
+
+    pub fn heal(&self) {
+        self.network.heal();
+    }
+
+    pub fn disconnect(&self, peer: usize) {
+        self.network.disconnect(self.keys[peer]);
+    }
+
+    pub fn reconnect(&self, peer: usize) {
+        self.network.reconnect(self.keys[peer]);
+    }
+
+    pub fn crash(&self, peer: usize) {
+        self.network.crash(self.keys[peer]);
+    }
+
+    /// Returns fresh endpoints for `peer` (typically, once `crash`ed), to
+    /// rebuild its stack upon. Older endpoints of `peer` stop working.
+    pub fn restart(&self, peer: usize) -> (SimulatedConnector, SimulatedListener) {
+        self.network.crash(self.keys[peer]);
+
+        let keychain = self.keychains[peer].clone();
+
+        let listener = SimulatedListener::new(keychain.clone(), &self.network);
+        let connector = SimulatedConnector::new(keychain, self.network.clone());
+
+        (connector, listener)
+    }
+
+    fn identities<P>(&self, peers: P) -> Vec<Identity>
+    where
+        P: IntoIterator<Item = usize>,
+    {
+        peers.into_iter().map(|peer| self.keys[peer]).collect()
+    }
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+
+use crate::{
+    crypto::{Identity, KeyChain},
+    net::{
+        test::{SimulatedNetwork, SimulatedSocket},
+        Connector, PlainConnection, SecureConnection,
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Stack};
+
+use tokio::time::Instant;
+
+pub struct SimulatedConnector {
+    pub keychain: KeyChain,
+    pub network: SimulatedNetwork,
+    incarnation: u64,
+}
+
+#[derive(Doom)]
+pub enum SimulatedConnectorError {
+    #[doom(description("Address unknown"))]
+    AddressUnknown,
+    #[doom(description("Failed to `authenticate` connection"))]
+    AuthenticateFailed,
+    #[doom(description("Connection refused"))]
+    ConnectionRefused,
+    #[doom(description("Local peer crashed"))]
+    Crashed,
+    #[doom(description("Remote partitioned"))]
+    Partitioned,
+    #[doom(description("Failed to `secure` connection"))]
+    SecureFailed,
+    #[doom(description("Unexpected remote: {:?}", remote))]
+    UnexpectedRemote { remote: Identity },
+}
+
+impl SimulatedConnector {
+    pub fn new(keychain: KeyChain, network: SimulatedNetwork) -> Self {
+        let incarnation = network.incarnation(keychain.keycard().identity());
+
+        SimulatedConnector {
+            keychain,
+            network,
+            incarnation,
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for SimulatedConnector {
+    async fn connect(&self, identity: Identity) -> Result<SecureConnection, Stack> {
+        let local = self.keychain.keycard().identity();
+
+        if self.network.incarnation(local) != self.incarnation {
+            return SimulatedConnectorError::Crashed
+                .fail()
+                .spot(here!())
+                .map_err(Into::into);
+        }
+
+        if self.network.partitioned(local, identity, Instant::now()) {
+            return SimulatedConnectorError::Partitioned
+                .fail()
+                .spot(here!())
+                .map_err(Into::into);
+        }
+
+        let socket_inlet = self
+            .network
+            .listener(identity)
+            .ok_or(SimulatedConnectorError::AddressUnknown.into_stack())
+            .spot(here!())?;
+
+        let (socket, remote_socket) = SimulatedSocket::pair(self.network.clone(), local, identity);
+
+        socket_inlet
+            .send(remote_socket)
+            .await
+            .map_err(|_| SimulatedConnectorError::ConnectionRefused.into_stack())
+            .spot(here!())?;
+
+        let mut connection = PlainConnection::from(socket)
+            .secure()
+            .await
+            .pot(SimulatedConnectorError::SecureFailed, here!())?;
+
+        let keycard = connection
+            .authenticate(&self.keychain)
+            .await
+            .pot(SimulatedConnectorError::AuthenticateFailed, here!())?;
+
+        if keycard.identity() == identity {
+            Ok(connection)
+        } else {
+            SimulatedConnectorError::UnexpectedRemote {
+                remote: keycard.identity(),
+            }
+            .fail()
+            .spot(here!())
+            .map_err(Into::into)
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+
+use crate::{
+    crypto::{Identity, KeyChain},
+    net::{
+        test::{SimulatedNetwork, SimulatedSocket},
+        Listener, PlainConnection, SecureConnection,
+    },
+    sync::fuse::Fuse,
+};
+
+use doomstack::{here, Doom, ResultExt, Stack, Top};
+
+use std::future;
+
+use tokio::sync::{
+    mpsc,
+    mpsc::{Receiver, Sender},
+};
+
+const CHANNEL_CAPACITY: usize = 32;
+
+type Outlet = Receiver<(Identity, SecureConnection)>;
+
+pub struct SimulatedListener {
+    outlet: Outlet,
+    _fuse: Fuse,
+}
+
+#[derive(Doom)]
+enum ServeError {
+    #[doom(description("Failed to `secure` the connection"))]
+    SecureFailed,
+    #[doom(description("Failed to `authenticate` the connection"))]
+    AuthenticateFailed,
+}
+
+impl SimulatedListener {
+    pub fn new(keychain: KeyChain, network: &SimulatedNetwork) -> Self {
+        let fuse = Fuse::new();
+
+        let (socket_inlet, socket_outlet) = mpsc::channel(CHANNEL_CAPACITY);
+        let (inlet, outlet) = mpsc::channel(CHANNEL_CAPACITY);
+
+        network.register(keychain.keycard().identity(), socket_inlet);
+
+        fuse.spawn(async move {
+            SimulatedListener::listen(keychain, socket_outlet, inlet).await;
+        });
+
+        SimulatedListener {
+            outlet,
+            _fuse: fuse,
+        }
+    }
+
+    async fn listen(
+        keychain: KeyChain,
+        mut socket_outlet: Receiver<SimulatedSocket>,
+        inlet: Sender<(Identity, SecureConnection)>,
+    ) {
+        let fuse = Fuse::new();
+
+        while let Some(socket) = socket_outlet.recv().await {
+            let keychain = keychain.clone();
+            let inlet = inlet.clone();
+
+            fuse.spawn(async move {
+                let _ = SimulatedListener::serve(socket.into(), keychain, inlet).await;
+            });
+        }
+    }
+
+    async fn serve(
+        connection: PlainConnection,
+        keychain: KeyChain,
+        inlet: Sender<(Identity, SecureConnection)>,
+    ) -> Result<(), Top<ServeError>> {
+        let mut connection = connection
+            .secure()
+            .await
+            .pot(ServeError::SecureFailed, here!())?;
+
+        let keycard = connection
+            .authenticate(&keychain)
+            .await
+            .pot(ServeError::AuthenticateFailed, here!())?;
+
+        // This can only fail if the (local) receiving end is
+        // dropped, in which case we don't care about the error
+        let _ = inlet.try_send((keycard.identity(), connection));
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Listener for SimulatedListener {
+    async fn accept(&mut self) -> Result<(Identity, SecureConnection), Stack> {
+        // `inlet` is dropped only when `fuse` burns, or once the local peer
+        // crashed (see `SimulatedNetwork::crash`): as the `Listener` cannot
+        // be dropped while `accept` is being called, a crashed `Listener`
+        // is the only one to ever run out of connections
+        match self.outlet.recv().await {
+            Some(accepted) => Ok(accepted),
+            None => future::pending().await,
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    crypto::Identity,
+    net::test::{LinkSettings, SimulatedSocket, SimulationSettings},
+};
+
+use parking_lot::Mutex;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{sync::mpsc::Sender as MpscSender, time::Instant};
+
+type SocketInlet = MpscSender<SimulatedSocket>;
+
+/// In-memory network shared by `SimulatedConnector`s and `SimulatedListener`s.
+/// Delays are measured in `tokio` time: under `tokio::time::pause`, the clock
+/// only advances when every task is idle, so that simulated latencies elapse
+/// instantly. All randomness is drawn from an RNG seeded by `SimulationSettings`,
+/// making runs on a single-threaded runtime deterministic.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    rng: StdRng,
+    settings: SimulationSettings,
+    links: HashMap<(Identity, Identity), LinkSettings>,
+    partitions: Vec<Partition>,
+    disconnected: HashSet<Identity>,
+    incarnations: HashMap<Identity, u64>,
+    listeners: HashMap<Identity, SocketInlet>,
+}
+
+// Separates `side` from `other` (or, if `None`, from every peer not in `side`)
+struct Partition {
+    side: HashSet<Identity>,
+    other: Option<HashSet<Identity>>,
+    start: Instant,
+    end: Option<Instant>,
+}
+
+impl SimulatedNetwork {
+    pub fn new(settings: SimulationSettings) -> Self {
+        let state = State {
+            rng: StdRng::seed_from_u64(settings.seed),
+            settings,
+            links: HashMap::new(),
+            partitions: Vec::new(),
+            disconnected: HashSet::new(),
+            incarnations: HashMap::new(),
+            listeners: HashMap::new(),
+        };
+
+        SimulatedNetwork {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Overrides `SimulationSettings::link_settings` for the link from `source` to `destination`.
+    pub fn set_link(&self, source: Identity, destination: Identity, settings: LinkSettings) {
+        self.state
+            .lock()
+            .links
+            .insert((source, destination), settings);
+    }
+
+    /// Schedules a partition isolating `side` from every other peer, starting
+    /// `start` from now and lasting `duration` (forever, if `None`). Connections
+    /// across the partition are reset as soon as they are used.
+    pub fn isolate<S>(&self, side: S, start: Duration, duration: Option<Duration>)
+    where
+        S: IntoIterator<Item = Identity>,
+    {
+        let start = Instant::now() + start;
+        let end = duration.map(|duration| start + duration);
+
+        self.state.lock().partitions.push(Partition {
+            side: side.into_iter().collect(),
+            other: None,
+            start,
+            end,
+        });
+    }
+
+    /// Partitions `side` from `other`, effective immediately and until `heal`ed.
+    pub fn partition<S, O>(&self, side: S, other: O)
+    where
+        S: IntoIterator<Item = Identity>,
+        O: IntoIterator<Item = Identity>,
+    {
+        self.state.lock().partitions.push(Partition {
+            side: side.into_iter().collect(),
+            other: Some(other.into_iter().collect()),
+            start: Instant::now(),
+            end: None,
+        });
+    }
+
+    /// Removes all partitions, including scheduled ones. Disconnected peers stay disconnected.
+    pub fn heal(&self) {
+        self.state.lock().partitions.clear();
+    }
+
+    /// Makes `peer` unreachable from every other peer, until `reconnect`ed.
+    pub fn disconnect(&self, peer: Identity) {
+        self.state.lock().disconnected.insert(peer);
+    }
+
+    pub fn reconnect(&self, peer: Identity) {
+        self.state.lock().disconnected.remove(&peer);
+    }
+
+    /// Crashes `peer`: its connections are reset as soon as they are used, and
+    /// its `SimulatedConnector`s and `SimulatedListener`s stop working for good.
+    /// `peer` can then restart with new ones.
+    pub fn crash(&self, peer: Identity) {
+        let mut state = self.state.lock();
+
+        *state.incarnations.entry(peer).or_insert(0) += 1;
+        state.listeners.remove(&peer);
+    }
+
+    pub(in crate::net::test) fn incarnation(&self, peer: Identity) -> u64 {
+        self.state
+            .lock()
+            .incarnations
+            .get(&peer)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn partitioned(&self, source: Identity, destination: Identity, at: Instant) -> bool {
+        let state = self.state.lock();
+
+        if source != destination
+            && (state.disconnected.contains(&source) || state.disconnected.contains(&destination))
+        {
+            return true;
+        }
+
+        state
+            .partitions
+            .iter()
+            .any(|partition| partition.separates(source, destination, at))
+    }
+
+    pub(in crate::net::test) fn register(&self, identity: Identity, socket_inlet: SocketInlet) {
+        self.state.lock().listeners.insert(identity, socket_inlet);
+    }
+
+    pub(in crate::net::test) fn listener(&self, identity: Identity) -> Option<SocketInlet> {
+        self.state.lock().listeners.get(&identity).cloned()
+    }
+
+    pub(in crate::net::test) fn delay(&self, source: Identity, destination: Identity) -> Duration {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+
+        let link = state
+            .links
+            .get(&(source, destination))
+            .unwrap_or(&state.settings.link_settings);
+
+        let mut delay = link.latency + link.jitter.mul_f64(state.rng.gen::<f64>());
+
+        if state.rng.gen::<f64>() < link.loss {
+            delay += link.retransmission_delay;
+        }
+
+        delay
+    }
+}
+
+impl Partition {
+    fn separates(&self, source: Identity, destination: Identity, at: Instant) -> bool {
+        let active = self.start <= at && self.end.map_or(true, |end| at < end);
+
+        let across = |from: Identity, to: Identity| {
+            self.side.contains(&from)
+                && self
+                    .other
+                    .as_ref()
+                    .map_or(!self.side.contains(&to), |other| other.contains(&to))
+        };
+
+        active && (across(source, destination) || across(destination, source))
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{
+    crypto::Identity,
+    net::{test::SimulatedNetwork, Socket},
+};
+
+use parking_lot::Mutex;
+
+use std::{
+    cmp,
+    collections::VecDeque,
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{self, Instant, Sleep},
+};
+
+/// One end of a simulated connection between `local` and `remote`.
+pub struct SimulatedSocket {
+    network: SimulatedNetwork,
+    local: Identity,
+    remote: Identity,
+    incarnations: (u64, u64),
+    inbound: Arc<Mutex<Pipe>>,
+    outbound: Arc<Mutex<Pipe>>,
+    sleep: Mutex<Option<Pin<Box<Sleep>>>>,
+}
+
+// Unidirectional byte stream, made of segments that become readable at their `arrival`
+struct Pipe {
+    segments: VecDeque<Segment>,
+    last_arrival: Option<Instant>,
+    reader: Option<Waker>,
+    write_closed: bool,
+    read_closed: bool,
+    reset: bool,
+}
+
+struct Segment {
+    arrival: Instant,
+    data: Vec<u8>,
+}
+
+impl SimulatedSocket {
+    pub(in crate::net::test) fn pair(
+        network: SimulatedNetwork,
+        local: Identity,
+        remote: Identity,
+    ) -> (Self, Self) {
+        let forward = Arc::new(Mutex::new(Pipe::new()));
+        let backward = Arc::new(Mutex::new(Pipe::new()));
+
+        let incarnations = (network.incarnation(local), network.incarnation(remote));
+
+        let local_socket = SimulatedSocket {
+            network: network.clone(),
+            local,
+            remote,
+            incarnations,
+            inbound: backward.clone(),
+            outbound: forward.clone(),
+            sleep: Mutex::new(None),
+        };
+
+        let remote_socket = SimulatedSocket {
+            network,
+            local: remote,
+            remote: local,
+            incarnations: (incarnations.1, incarnations.0),
+            inbound: forward,
+            outbound: backward,
+            sleep: Mutex::new(None),
+        };
+
+        (local_socket, remote_socket)
+    }
+
+    fn reset(&self) {
+        self.inbound.lock().reset();
+        self.outbound.lock().reset();
+    }
+
+    // A connection is severed by a partition, or by either end crashing
+    fn severed(&self, source: Identity, destination: Identity, at: Instant) -> bool {
+        self.network.partitioned(source, destination, at)
+            || self.network.incarnation(self.local) != self.incarnations.0
+            || self.network.incarnation(self.remote) != self.incarnations.1
+    }
+}
+
+impl Pipe {
+    fn new() -> Self {
+        Pipe {
+            segments: VecDeque::new(),
+            last_arrival: None,
+            reader: None,
+            write_closed: false,
+            read_closed: false,
+            reset: false,
+        }
+    }
+
+    fn wake(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            reader.wake();
+        }
+    }
+
+    fn close_write(&mut self) {
+        self.write_closed = true;
+        self.wake();
+    }
+
+    fn reset(&mut self) {
+        self.reset = true;
+        self.wake();
+    }
+}
+
+impl AsyncRead for SimulatedSocket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let socket = self.get_mut();
+
+        loop {
+            if socket.severed(socket.remote, socket.local, Instant::now()) {
+                socket.reset();
+            }
+
+            let arrival = {
+                let mut inbound = socket.inbound.lock();
+
+                if inbound.reset {
+                    return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+                }
+
+                match inbound.segments.front_mut() {
+                    Some(segment) if segment.arrival <= Instant::now() => {
+                        let length = cmp::min(buf.remaining(), segment.data.len());
+                        buf.put_slice(&segment.data[..length]);
+                        segment.data.drain(..length);
+
+                        if segment.data.is_empty() {
+                            inbound.segments.pop_front();
+                        }
+
+                        return Poll::Ready(Ok(()));
+                    }
+                    Some(segment) => segment.arrival,
+                    None => {
+                        if inbound.write_closed {
+                            // End of stream
+                            return Poll::Ready(Ok(()));
+                        }
+
+                        inbound.reader = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            };
+
+            // Segments are ordered by `arrival`: wait for the first to arrive
+            let sleep = socket.sleep.get_mut();
+
+            let stale = sleep
+                .as_ref()
+                .map_or(true, |sleep| sleep.deadline() != arrival);
+
+            if stale {
+                *sleep = Some(Box::pin(time::sleep_until(arrival)));
+            }
+
+            match sleep.as_mut().unwrap().as_mut().poll(cx) {
+                Poll::Ready(()) => {
+                    *sleep = None;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for SimulatedSocket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let now = Instant::now();
+
+        if self.severed(self.local, self.remote, now) {
+            self.reset();
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+
+        let delay = self.network.delay(self.local, self.remote);
+
+        let mut outbound = self.outbound.lock();
+
+        if outbound.reset {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+
+        if outbound.write_closed || outbound.read_closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        // A segment never overtakes the segments written before it
+        let arrival = now + delay;
+        let arrival = outbound
+            .last_arrival
+            .map_or(arrival, |last_arrival| cmp::max(last_arrival, arrival));
+
+        outbound.last_arrival = Some(arrival);
+
+        outbound.segments.push_back(Segment {
+            arrival,
+            data: buf.to_vec(),
+        });
+
+        outbound.wake();
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.outbound.lock().close_write();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Socket for SimulatedSocket {}
+
+impl Drop for SimulatedSocket {
+    fn drop(&mut self) {
+        self.outbound.lock().close_write();
+        self.inbound.lock().read_closed = true;
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{
+    crypto::{Identity, KeyChain},
+    net::test::{
+        Scenario, SimulatedConnector, SimulatedListener, SimulatedNetwork, SimulationSettings,
+    },
+};
+
+/// Counterpart of `System` over a `SimulatedNetwork`. Call `tokio::time::pause`
+/// beforehand for simulated time to elapse instantly.
+pub struct SimulatedSystem {
+    pub keys: Vec<Identity>,
+    pub connectors: Vec<SimulatedConnector>,
+    pub listeners: Vec<SimulatedListener>,
+    pub network: SimulatedNetwork,
+    keychains: Vec<KeyChain>,
+}
+
+impl SimulatedSystem {
+    pub fn setup(peers: usize, settings: SimulationSettings) -> SimulatedSystem {
+        SimulatedSystem::setup_with_keychains((0..peers).map(|_| KeyChain::random()), settings)
+    }
+
+    pub fn setup_with_keychains<I>(keychains: I, settings: SimulationSettings) -> SimulatedSystem
+    where
+        I: IntoIterator<Item = KeyChain>,
+    {
+        let network = SimulatedNetwork::new(settings);
+
+        let keychains = keychains.into_iter().collect::<Vec<_>>();
+
+        let keys = keychains
+            .iter()
+            .map(|keychain| keychain.keycard().identity())
+            .collect::<Vec<_>>();
+
+        let listeners = keychains
+            .iter()
+            .map(|keychain| SimulatedListener::new(keychain.clone(), &network))
+            .collect::<Vec<_>>();
+
+        let connectors = keychains
+            .iter()
+            .map(|keychain| SimulatedConnector::new(keychain.clone(), network.clone()))
+            .collect::<Vec<_>>();
+
+        SimulatedSystem {
+            keys,
+            connectors,
+            listeners,
+            network,
+            keychains,
+        }
+    }
+
+    pub fn scenario(&self) -> Scenario {
+        Scenario::new(self.keychains.clone(), self.network.clone())
+    }
+
+    /// Sets up a `SimulatedSystem`, wrapping each of its connectors and listeners
+    /// with `connector` and `listener`, respectively. Test systems built over
+    /// `Connector`s and `Listener`s use this to run on a `SimulatedNetwork`.
+    pub fn setup_wrapped<C, L, FC, FL>(
+        peers: usize,
+        settings: SimulationSettings,
+        connector: FC,
+        listener: FL,
+    ) -> (Vec<Identity>, Vec<C>, Vec<L>, Scenario)
+    where
+        FC: FnMut(SimulatedConnector) -> C,
+        FL: FnMut(SimulatedListener) -> L,
+    {
+        let system = SimulatedSystem::setup(peers, settings);
+        let scenario = system.scenario();
+
+        let connectors = system.connectors.into_iter().map(connector).collect();
+        let listeners = system.listeners.into_iter().map(listener).collect();
+
+        (system.keys, connectors, listeners, scenario)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::{test::LinkSettings, Connector, Listener};
+
+    use std::time::Duration;
+
+    use tokio::time::{self, Instant};
+
+    // Connects peer 0 to peer 1, then sends `messages` values from 0 to 1,
+    // returning the (simulated) time elapsed until each value was received
+    async fn transmit(system: &mut SimulatedSystem, messages: u32) -> Vec<Duration> {
+        let start = Instant::now();
+
+        let (connection, accepted) = futures::join!(
+            system.connectors[0].connect(system.keys[1]),
+            system.listeners[1].accept()
+        );
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        let mut elapsed = Vec::new();
+
+        for message in 0..messages {
+            connection.send(&message).await.unwrap();
+            assert_eq!(accepted.receive::<u32>().await.unwrap(), message);
+            elapsed.push(start.elapsed());
+        }
+
+        elapsed
+    }
+
+    #[tokio::test]
+    async fn latency() {
+        time::pause();
+
+        let mut system = SimulatedSystem::setup(
+            2,
+            SimulationSettings {
+                link_settings: LinkSettings {
+                    latency: Duration::from_secs(10),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let wall = std::time::Instant::now();
+        let elapsed = transmit(&mut system, 10).await;
+
+        // Each value takes (at least) one latency to be delivered
+        for (previous, next) in elapsed.iter().zip(elapsed.iter().skip(1)) {
+            assert!(*next - *previous >= Duration::from_secs(10));
+        }
+
+        // ... but simulated time elapses instantly
+        assert!(wall.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn deterministic() {
+        time::pause();
+
+        let settings = SimulationSettings {
+            seed: 42,
+            link_settings: LinkSettings {
+                latency: Duration::from_millis(10),
+                jitter: Duration::from_millis(50),
+                loss: 0.2,
+                retransmission_delay: Duration::from_millis(200),
+            },
+        };
+
+        let keychains = (0..2).map(|_| KeyChain::random()).collect::<Vec<_>>();
+
+        let mut first = SimulatedSystem::setup_with_keychains(keychains.clone(), settings.clone());
+        let first = transmit(&mut first, 32).await;
+
+        let mut second = SimulatedSystem::setup_with_keychains(keychains, settings);
+        let second = transmit(&mut second, 32).await;
+
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn partition_schedule() {
+        time::pause();
+
+        let mut system = SimulatedSystem::setup(2, Default::default());
+
+        let (connection, accepted) = futures::join!(
+            system.connectors[0].connect(system.keys[1]),
+            system.listeners[1].accept()
+        );
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        system.network.isolate(
+            vec![system.keys[1]],
+            Duration::from_secs(1),
+            Some(Duration::from_secs(1)),
+        );
+
+        connection.send(&42u32).await.unwrap();
+        assert_eq!(accepted.receive::<u32>().await.unwrap(), 42);
+
+        time::sleep(Duration::from_secs(1)).await;
+
+        // Connections across the partition are reset, and new ones refused
+        assert!(connection.send(&43u32).await.is_err());
+        assert!(system.connectors[0].connect(system.keys[1]).await.is_err());
+
+        time::sleep(Duration::from_secs(1)).await;
+
+        let (connection, accepted) = futures::join!(
+            system.connectors[0].connect(system.keys[1]),
+            system.listeners[1].accept()
+        );
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        connection.send(&44u32).await.unwrap();
+        assert_eq!(accepted.receive::<u32>().await.unwrap(), 44);
+    }
+
+    #[tokio::test]
+    async fn crash_restart() {
+        time::pause();
+
+        let mut system = SimulatedSystem::setup(2, Default::default());
+        let scenario = system.scenario();
+
+        let (connection, accepted) = futures::join!(
+            system.connectors[0].connect(system.keys[1]),
+            system.listeners[1].accept()
+        );
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        scenario.crash(1);
+
+        // The crashed peer's connections are reset, and its endpoints are gone
+        assert!(connection.send(&42u32).await.is_err());
+        assert!(accepted.receive::<u32>().await.is_err());
+        assert!(system.connectors[0].connect(system.keys[1]).await.is_err());
+        assert!(system.connectors[1].connect(system.keys[0]).await.is_err());
+
+        let (connector, mut listener) = scenario.restart(1);
+
+        let (connection, accepted) = futures::join!(
+            system.connectors[0].connect(system.keys[1]),
+            listener.accept()
+        );
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        connection.send(&43u32).await.unwrap();
+        assert_eq!(accepted.receive::<u32>().await.unwrap(), 43);
+
+        let (connection, accepted) = futures::join!(
+            connector.connect(system.keys[0]),
+            system.listeners[0].accept()
+        );
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        connection.send(&44u32).await.unwrap();
+        assert_eq!(accepted.receive::<u32>().await.unwrap(), 44);
+    }
+}
@@ -0,0 +1,7 @@
+use crate::net::test::LinkSettings;
+
+#[derive(Debug, Default, Clone)]
+pub struct SimulationSettings {
+    pub seed: u64,
+    pub link_settings: LinkSettings,
+}
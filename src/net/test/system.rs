@@ -4,7 +4,6 @@ use crate::{
         test::{ConnectionPair, TestConnector, TestListener},
         Connector, Listener,
     },
-    time::test::join,
 };
 
 use futures::stream::{FuturesOrdered, StreamExt};
@@ -54,11 +53,8 @@ impl System {
             .into_iter()
             .unzip();
 
-        let peers: HashMap<Identity, SocketAddr> = identities
-            .clone()
-            .into_iter()
-            .zip(addresses.into_iter())
-            .collect();
+        let peers: HashMap<Identity, SocketAddr> =
+            identities.clone().into_iter().zip(addresses).collect();
 
         let connectors: Vec<TestConnector> = keychains
             .into_iter()
@@ -98,6 +94,8 @@ impl System {
 mod tests {
     use super::*;
 
+    use crate::time::test::join;
+
     #[tokio::test]
     async fn example_setup() {
         let mut system = System::setup(8).await;
@@ -106,7 +104,7 @@ mod tests {
             .connection_matrix()
             .await
             .into_iter()
-            .map(|row| {
+            .flat_map(|row| {
                 row.into_iter().map(|mut pair| {
                     tokio::spawn(async move {
                         let sent: u32 = 42;
@@ -115,8 +113,7 @@ mod tests {
                         assert_eq!(received, sent);
                     })
                 })
-            })
-            .flatten();
+            });
 
         join(handles).await.unwrap();
     }
@@ -1,13 +1,24 @@
-use crate::sync::fuse::Fuse;
+use crate::{
+    net::test::{Direction, Faults},
+    sync::fuse::Fuse,
+};
+
+use parking_lot::Mutex;
+
+use rand::Rng;
 
 use std::{
+    cmp,
+    collections::VecDeque,
+    future,
     net::{Ipv4Addr, SocketAddr},
     sync::Arc,
+    time::Duration,
 };
 
 use tokio::{
     io,
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::{TcpListener, TcpStream, ToSocketAddrs},
     sync::{
         mpsc,
@@ -16,6 +27,7 @@ use tokio::{
         watch::{Receiver as WatchReceiver, Sender as WatchSender},
         RwLock,
     },
+    time::{self, Instant},
 };
 
 type StateInlet = WatchSender<State>;
@@ -24,12 +36,16 @@ type StateOutlet = WatchReceiver<State>;
 type ResetInlet = MpscSender<()>;
 type ResetOutlet = MpscReceiver<()>;
 
+// Bytes a `Link` holds before it stops reading from its source
+const MAX_PENDING: usize = 65536;
+
 pub struct TcpProxy {
     address: SocketAddr,
     state_inlet: StateInlet,
     reset_inlet: ResetInlet,
     on_lock: Arc<RwLock<()>>,
     off_lock: Arc<RwLock<()>>,
+    faults: Arc<Mutex<FaultTable>>,
     _fuse: Fuse,
 }
 
@@ -39,6 +55,21 @@ enum State {
     Off,
 }
 
+#[derive(Default)]
+struct FaultTable {
+    upstream: Faults,
+    downstream: Faults,
+}
+
+// Chunks read in one `Direction`, waiting to be forwarded
+struct Link {
+    pending: VecDeque<(Instant, Vec<u8>)>,
+    pending_bytes: usize,
+    available: Instant,
+    read: usize,
+    forwarded: usize,
+}
+
 impl TcpProxy {
     pub async fn new<A>(server: A) -> Self
     where
@@ -54,11 +85,14 @@ impl TcpProxy {
         let on_lock = Arc::new(RwLock::new(()));
         let off_lock = Arc::new(RwLock::new(()));
 
+        let faults = Arc::new(Mutex::new(FaultTable::default()));
+
         let fuse = Fuse::new();
 
         {
             let on_lock = on_lock.clone();
             let off_lock = off_lock.clone();
+            let faults = faults.clone();
 
             fuse.spawn(async move {
                 let _ = TcpProxy::listen(
@@ -68,6 +102,7 @@ impl TcpProxy {
                     reset_outlet,
                     on_lock,
                     off_lock,
+                    faults,
                 )
                 .await;
             });
@@ -79,6 +114,7 @@ impl TcpProxy {
             reset_inlet,
             on_lock,
             off_lock,
+            faults,
             _fuse: fuse,
         }
     }
@@ -89,18 +125,32 @@ impl TcpProxy {
 
     pub async fn start(&mut self) {
         let _ = self.state_inlet.send(State::On);
-        self.off_lock.write().await;
+        let _ = self.off_lock.write().await;
     }
 
     pub async fn stop(&mut self) {
         let _ = self.state_inlet.send(State::Off);
-        self.on_lock.write().await;
+        let _ = self.on_lock.write().await;
     }
 
     pub async fn reset(&mut self) {
         let _ = self.reset_inlet.send(()).await;
     }
 
+    pub fn faults(&self, direction: Direction) -> Faults {
+        self.faults.lock().get(direction).clone()
+    }
+
+    /// Sets the `Faults` injected in `direction`, effective immediately
+    /// on all connections, including established ones.
+    pub fn set_faults(&self, direction: Direction, faults: Faults) {
+        *self.faults.lock().get_mut(direction) = faults;
+    }
+
+    pub fn clear_faults(&self) {
+        *self.faults.lock() = FaultTable::default();
+    }
+
     async fn listen<A>(
         listener: TcpListener,
         server: A,
@@ -108,6 +158,7 @@ impl TcpProxy {
         mut reset_outlet: ResetOutlet,
         on_lock: Arc<RwLock<()>>,
         off_lock: Arc<RwLock<()>>,
+        faults: Arc<Mutex<FaultTable>>,
     ) where
         A: 'static + Send + Sync + Clone + ToSocketAddrs,
     {
@@ -128,6 +179,7 @@ impl TcpProxy {
                     let off_lock = off_lock.clone();
 
                     let state_outlet = state_outlet.clone();
+                    let faults = faults.clone();
 
                     fuse.spawn(async move {
                         let _ = TcpProxy::forward(
@@ -136,6 +188,7 @@ impl TcpProxy {
                             state_outlet,
                             on_lock,
                             off_lock,
+                            faults,
                         )
                         .await;
                     });
@@ -150,6 +203,7 @@ impl TcpProxy {
         mut state_outlet: StateOutlet,
         on_lock: Arc<RwLock<()>>,
         off_lock: Arc<RwLock<()>>,
+        faults: Arc<Mutex<FaultTable>>,
     ) -> Result<(), io::Error>
     where
         A: 'static + Send + Sync + Clone + ToSocketAddrs,
@@ -162,6 +216,9 @@ impl TcpProxy {
         let mut client_buffer = [0u8; 1024];
         let mut server_buffer = [0u8; 1024];
 
+        let mut upstream = Link::new();
+        let mut downstream = Link::new();
+
         let mut _on_guard = Some(on_lock.read().await);
         let mut _off_guard = Some(off_lock.read().await);
 
@@ -173,6 +230,9 @@ impl TcpProxy {
                     _off_guard = None;
 
                     loop {
+                        let upstream_due = upstream.next_due();
+                        let downstream_due = downstream.next_due();
+
                         tokio::select! {
                             biased;
 
@@ -180,14 +240,42 @@ impl TcpProxy {
                                 break;
                             },
 
-                            result = client_read.read(&mut client_buffer) => {
+                            result = client_read.read(&mut client_buffer), if upstream.readable() => {
                                 let written = result?;
-                                server_write.write_all(&client_buffer[0..written]).await?;
+
+                                if written == 0 {
+                                    return Ok(()); // `client` closed the connection
+                                }
+
+                                let faults = faults.lock().upstream.clone();
+                                upstream.push(&client_buffer[0..written], &faults);
                             }
 
-                            result = server_read.read(&mut server_buffer) => {
+                            result = server_read.read(&mut server_buffer), if downstream.readable() => {
                                 let written = result?;
-                                client_write.write_all(&server_buffer[0..written]).await?;
+
+                                if written == 0 {
+                                    return Ok(()); // `server` closed the connection
+                                }
+
+                                let faults = faults.lock().downstream.clone();
+                                downstream.push(&server_buffer[0..written], &faults);
+                            }
+
+                            _ = Link::due(upstream_due) => {
+                                let faults = faults.lock().upstream.clone();
+
+                                if !upstream.flush(&mut server_write, &faults).await? {
+                                    return Ok(()); // Truncated
+                                }
+                            }
+
+                            _ = Link::due(downstream_due) => {
+                                let faults = faults.lock().downstream.clone();
+
+                                if !downstream.flush(&mut client_write, &faults).await? {
+                                    return Ok(()); // Truncated
+                                }
                             }
                         }
                     }
@@ -205,3 +293,348 @@ impl TcpProxy {
         }
     }
 }
+
+impl FaultTable {
+    fn get(&self, direction: Direction) -> &Faults {
+        match direction {
+            Direction::Upstream => &self.upstream,
+            Direction::Downstream => &self.downstream,
+        }
+    }
+
+    fn get_mut(&mut self, direction: Direction) -> &mut Faults {
+        match direction {
+            Direction::Upstream => &mut self.upstream,
+            Direction::Downstream => &mut self.downstream,
+        }
+    }
+}
+
+impl Link {
+    fn new() -> Self {
+        Link {
+            pending: VecDeque::new(),
+            pending_bytes: 0,
+            available: Instant::now(),
+            read: 0,
+            forwarded: 0,
+        }
+    }
+
+    // A `Link` stops reading while too many bytes await forwarding (e.g.,
+    // because of a low `bandwidth`), so that the source is pushed back on
+    fn readable(&self) -> bool {
+        self.pending_bytes < MAX_PENDING
+    }
+
+    fn push(&mut self, chunk: &[u8], faults: &Faults) {
+        let offset = self.read;
+        self.read += chunk.len();
+
+        if faults.blackhole {
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+
+        let mut due = Instant::now() + faults.latency + faults.jitter.mul_f64(rng.gen::<f64>());
+
+        // Chunks are never reordered
+        if let Some((last, _)) = self.pending.back() {
+            due = cmp::max(due, *last);
+        }
+
+        if let Some(bandwidth) = faults.bandwidth {
+            due = cmp::max(due, self.available);
+            self.available =
+                due + Duration::from_secs_f64(chunk.len() as f64 / bandwidth.get() as f64);
+        }
+
+        let chunk = chunk
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| {
+                let byte = if rng.gen::<f64>() < faults.corruption {
+                    byte ^ (1u8 << rng.gen_range(0u8, 8))
+                } else {
+                    *byte
+                };
+
+                match &faults.corrupt_range {
+                    Some(range) if range.contains(&(offset + index)) => byte ^ 1,
+                    _ => byte,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.pending_bytes += chunk.len();
+        self.pending.push_back((due, chunk));
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.pending.front().map(|(due, _)| *due)
+    }
+
+    async fn due(due: Option<Instant>) {
+        match due {
+            Some(due) => time::sleep_until(due).await,
+            None => future::pending().await,
+        }
+    }
+
+    // Writes all chunks that are due, returning `false` if the
+    // connection was truncated as per `faults.truncate_after`
+    async fn flush<W>(&mut self, write: &mut W, faults: &Faults) -> Result<bool, io::Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        while self.next_due().map_or(false, |due| due <= Instant::now()) {
+            let (_, chunk) = self.pending.pop_front().unwrap();
+            self.pending_bytes -= chunk.len();
+
+            let room = faults
+                .truncate_after
+                .map_or(usize::MAX, |limit| limit.saturating_sub(self.forwarded));
+
+            let length = cmp::min(chunk.len(), room);
+
+            write.write_all(&chunk[0..length]).await?;
+            self.forwarded += length;
+
+            if faults
+                .truncate_after
+                .map_or(false, |limit| self.forwarded >= limit)
+            {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::net::{test::System, Connector, Listener, SecureConnectionError};
+
+    use std::num::NonZeroU64;
+
+    // Returns a client connected, through a `TcpProxy`, to a server
+    async fn setup() -> (TcpProxy, TcpStream, TcpStream) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let proxy = TcpProxy::new(listener.local_addr().unwrap()).await;
+
+        let client = TcpStream::connect(proxy.address()).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        (proxy, client, server)
+    }
+
+    #[tokio::test]
+    async fn latency() {
+        let (proxy, mut client, mut server) = setup().await;
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                latency: Duration::from_millis(200),
+                ..Default::default()
+            },
+        );
+
+        let start = Instant::now();
+
+        client.write_all(&[42]).await.unwrap();
+        assert_eq!(server.read_u8().await.unwrap(), 42);
+
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn bandwidth() {
+        let (proxy, mut client, mut server) = setup().await;
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                bandwidth: NonZeroU64::new(20000),
+                ..Default::default()
+            },
+        );
+
+        let start = Instant::now();
+
+        client.write_all(&[42; 10000]).await.unwrap();
+
+        let mut buffer = [0u8; 10000];
+        server.read_exact(&mut buffer).await.unwrap();
+
+        // The last chunk is forwarded once (at least) 9 KB were forwarded
+        assert!(start.elapsed() >= Duration::from_millis(450));
+    }
+
+    #[tokio::test]
+    async fn bandwidth_backpressure() {
+        let (proxy, mut client, _server) = setup().await;
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                bandwidth: NonZeroU64::new(1000),
+                ..Default::default()
+            },
+        );
+
+        // Once `MAX_PENDING` bytes await forwarding, the proxy stops reading:
+        // socket buffers fill up, until `client` cannot write any further
+        let stalled = async {
+            while time::timeout(Duration::from_millis(200), client.write_all(&[42; 1024]))
+                .await
+                .is_ok()
+            {}
+        };
+
+        assert!(time::timeout(Duration::from_secs(10), stalled)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn corruption() {
+        let (proxy, mut client, mut server) = setup().await;
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                corruption: 1.,
+                ..Default::default()
+            },
+        );
+
+        client.write_all(&[42; 16]).await.unwrap();
+
+        let mut buffer = [0u8; 16];
+        server.read_exact(&mut buffer).await.unwrap();
+
+        assert!(buffer.iter().all(|byte| *byte != 42));
+    }
+
+    #[tokio::test]
+    async fn corrupt_range() {
+        let (proxy, mut client, mut server) = setup().await;
+
+        client.write_all(&[42; 4]).await.unwrap();
+
+        let mut buffer = [0u8; 4];
+        server.read_exact(&mut buffer).await.unwrap();
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                corrupt_range: Some(6..8),
+                ..Default::default()
+            },
+        );
+
+        client.write_all(&[42; 8]).await.unwrap();
+
+        let mut buffer = [0u8; 8];
+        server.read_exact(&mut buffer).await.unwrap();
+
+        assert_eq!(buffer, [42, 42, 43, 43, 42, 42, 42, 42]);
+    }
+
+    #[tokio::test]
+    async fn truncation() {
+        let (proxy, mut client, mut server) = setup().await;
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                truncate_after: Some(4),
+                ..Default::default()
+            },
+        );
+
+        client.write_all(&[42; 8]).await.unwrap();
+
+        let mut buffer = Vec::new();
+        server.read_to_end(&mut buffer).await.unwrap();
+
+        assert_eq!(buffer, vec![42; 4]);
+    }
+
+    #[tokio::test]
+    async fn one_directional_blackhole() {
+        let (proxy, mut client, mut server) = setup().await;
+
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                blackhole: true,
+                ..Default::default()
+            },
+        );
+
+        client.write_all(&[42]).await.unwrap();
+
+        assert!(time::timeout(Duration::from_millis(200), server.read_u8())
+            .await
+            .is_err());
+
+        server.write_all(&[43]).await.unwrap();
+        assert_eq!(client.read_u8().await.unwrap(), 43);
+
+        proxy.clear_faults();
+
+        client.write_all(&[44]).await.unwrap();
+        assert_eq!(server.read_u8().await.unwrap(), 44);
+    }
+
+    #[tokio::test]
+    async fn corrupted_secure_connection() {
+        let System {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = System::setup(2).await;
+
+        let proxy = TcpProxy::new(connectors[0].peers[&keys[1]]).await;
+        connectors[0].peers.insert(keys[1], proxy.address());
+
+        let (connection, accepted) =
+            futures::join!(connectors[0].connect(keys[1]), listeners[1].accept());
+
+        let mut connection = connection.unwrap();
+        let (_, mut accepted) = accepted.unwrap();
+
+        connection.send(&42u32).await.unwrap();
+        assert_eq!(accepted.receive::<u32>().await.unwrap(), 42);
+
+        // Only one byte is corrupted, well past the handshake and the first
+        // message: the length prefix of the next message is spared, while
+        // its (much longer) payload is not
+        proxy.set_faults(
+            Direction::Upstream,
+            Faults {
+                corrupt_range: Some((1 << 17)..((1 << 17) + 1)),
+                ..Default::default()
+            },
+        );
+
+        let message = vec![43u8; 1 << 18];
+
+        let (sent, received) = futures::join!(
+            connection.send(&message),
+            time::timeout(Duration::from_secs(30), accepted.receive::<Vec<u8>>())
+        );
+
+        sent.unwrap();
+
+        // A corrupted message is never delivered
+        let error = received.unwrap().unwrap_err();
+        assert!(matches!(error.top(), SecureConnectionError::DecryptFailed));
+    }
+}
@@ -38,12 +38,11 @@ impl TestConnector {
 #[async_trait]
 impl Connector for TestConnector {
     async fn connect(&self, identity: Identity) -> Result<SecureConnection, Stack> {
-        let address = self
+        let address = *self
             .peers
             .get(&identity)
             .ok_or(TestConnectorError::AddressUnknown.into_stack())
-            .spot(here!())?
-            .clone();
+            .spot(here!())?;
 
         let mut connection = address
             .connect()
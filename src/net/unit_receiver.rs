@@ -32,8 +32,17 @@ impl UnitReceiver {
         &mut self.buffer
     }
 
-    pub async fn receive(&mut self) -> io::Result<()> {
+    pub async fn receive(&mut self, max_frame_size: usize) -> io::Result<()> {
         let size = self.receive_size().await?;
+
+        // Reject oversized frames before allocating for them
+        if size > max_frame_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame exceeds `max_frame_size`",
+            ));
+        }
+
         self.buffer.resize(size, 0);
         self.read_half.read_exact(&mut self.buffer[..]).await?;
 
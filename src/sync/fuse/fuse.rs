@@ -34,6 +34,12 @@ impl Fuse {
     }
 }
 
+impl Default for Fuse {
+    fn default() -> Self {
+        Fuse::new()
+    }
+}
+
 impl Drop for Fuse {
     fn drop(&mut self) {
         let _ = self.sender.send(());
@@ -24,7 +24,7 @@ impl<Inner> AtomicLender<Inner> {
 
     pub fn take(self: &Arc<Self>) -> Inner {
         let guard = self.state.lock().unwrap();
-        let mut guard = (*self)
+        let mut guard = self
             .condvar
             .wait_while(guard, |state| match state {
                 State::Available(_) => false,
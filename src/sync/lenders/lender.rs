@@ -59,7 +59,7 @@ impl<Inner> Lender<Inner> {
 impl<Inner> Borrow<Inner> for Lender<Inner> {
     fn borrow(&self) -> &Inner {
         match &self.state {
-            State::Available(inner) => &inner,
+            State::Available(inner) => inner,
             State::Lent => panic!("attempted to `borrow` `Lender` without `Lender::restore`"),
         }
     }
@@ -8,7 +8,7 @@ pub trait SleepSchedule: Debug + Send + Sync {
 }
 
 impl dyn SleepSchedule {
-    pub fn agent(&self) -> SleepAgent {
+    pub fn agent(&self) -> SleepAgent<'_> {
         SleepAgent::new(self)
     }
 }
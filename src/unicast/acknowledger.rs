@@ -1,20 +1,28 @@
-use crate::unicast::{Acknowledgement, Response};
+use crate::unicast::{Acknowledgement, Message as UnicastMessage, Response};
 
-use tokio::sync::mpsc::Sender;
+use tokio::{
+    runtime::Handle,
+    sync::mpsc::{error::TrySendError, Sender},
+};
 
-type ResponseInlet = Sender<Response>;
+type ResponseInlet<Reply> = Sender<Response<Reply>>;
 
-pub struct Acknowledger {
+pub struct Acknowledger<Reply: UnicastMessage = ()> {
     sequence: u32,
     acknowledgement: Acknowledgement,
-    response_inlet: ResponseInlet,
+    reply: Option<Reply>,
+    response_inlet: ResponseInlet<Reply>,
 }
 
-impl Acknowledger {
-    pub(in crate::unicast) fn new(sequence: u32, response_inlet: ResponseInlet) -> Self {
+impl<Reply> Acknowledger<Reply>
+where
+    Reply: UnicastMessage,
+{
+    pub(in crate::unicast) fn new(sequence: u32, response_inlet: ResponseInlet<Reply>) -> Self {
         Acknowledger {
             sequence,
             acknowledgement: Acknowledgement::Weak,
+            reply: None,
             response_inlet,
         }
     }
@@ -33,13 +41,44 @@ impl Acknowledger {
         self.acknowledgement = Acknowledgement::Strong;
         // `Drop::drop` is called here
     }
+
+    pub fn reply(self, reply: Reply) {
+        self.reply_with(Acknowledgement::Strong, reply);
+    }
+
+    pub fn reply_with(mut self, acknowledgement: Acknowledgement, reply: Reply) {
+        self.acknowledgement = acknowledgement;
+        self.reply = Some(reply);
+        // `Drop::drop` is called here
+    }
 }
 
-impl Drop for Acknowledger {
+impl<Reply> Drop for Acknowledger<Reply>
+where
+    Reply: UnicastMessage,
+{
     fn drop(&mut self) {
-        let _ = self.response_inlet.try_send(Response::Acknowledgement(
-            self.sequence,
-            self.acknowledgement,
-        ));
+        let response =
+            Response::Acknowledgement(self.sequence, self.acknowledgement, self.reply.take());
+
+        // Should `response_inlet` be full (e.g., because many `Acknowledger`s
+        // were dropped at once), `response` is sent as soon as room is made.
+        // Outside of a runtime, however, there is no task to wait for room:
+        // `response` is then dropped, and its message will be retried by the
+        // remote `Sender` (if its `PushSettings` allow it)
+        if let Err(TrySendError::Full(response)) = self.response_inlet.try_send(response) {
+            if let Ok(handle) = Handle::try_current() {
+                let response_inlet = self.response_inlet.clone();
+
+                handle.spawn(async move {
+                    let _ = response_inlet.send(response).await;
+                });
+            } else {
+                log::warn!(
+                    "Dropped acknowledgement {} (response channel full, no runtime)",
+                    self.sequence
+                );
+            }
+        }
     }
 }
@@ -2,7 +2,10 @@ use crate::{
     crypto::Identity,
     net::{Connector, SecureReceiver, SecureSender},
     sync::fuse::Fuse,
-    unicast::{Acknowledgement, CasterSettings, Message as UnicastMessage, Request, Response},
+    unicast::{
+        Acknowledgement, CasterSettings, Lanes, Message as UnicastMessage, Priority, Request,
+        Response,
+    },
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
@@ -11,43 +14,61 @@ use parking_lot::Mutex;
 
 use std::sync::Arc;
 
-use tokio::sync::{
-    mpsc,
-    mpsc::{error::TrySendError, Receiver as MpscReceiver, Sender as MpscSender},
-    oneshot,
-    oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender},
+use tokio::{
+    sync::{
+        mpsc,
+        mpsc::{error::SendError, Receiver as MpscReceiver, Sender as MpscSender},
+        oneshot,
+        oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender},
+        Notify, Semaphore,
+    },
+    time::{self, Instant},
 };
 
-use std::collections::HashMap;
+use std::{
+    cmp,
+    collections::{HashMap, VecDeque},
+};
+
+// Maximum number of permits a `Semaphore` can hold (see `Semaphore::MAX_PERMITS`)
+const MAX_CREDITS: usize = usize::MAX >> 3;
 
-type RequestInlet<Message> = MpscSender<(Request<Message>, AcknowledgementInlet)>;
-type RequestOutlet<Message> = MpscReceiver<(Request<Message>, AcknowledgementInlet)>;
+type RequestEntry<Message, Reply> = (Request<Message>, AcknowledgementInlet<Reply>);
 
-type AcknowledgementInlet = OneshotSender<Result<Acknowledgement, Top<CasterError>>>;
-type AcknowledgementOutlet = OneshotReceiver<Result<Acknowledgement, Top<CasterError>>>;
+type RequestInlet<Message, Reply> = MpscSender<RequestEntry<Message, Reply>>;
+type RequestOutlet<Message, Reply> = MpscReceiver<RequestEntry<Message, Reply>>;
 
-pub(in crate::unicast) struct Caster<Message: UnicastMessage> {
-    state: Arc<Mutex<State<Message>>>,
+type AcknowledgementInlet<Reply> =
+    OneshotSender<Result<(Acknowledgement, Option<Reply>), Top<CasterError>>>;
+type AcknowledgementOutlet<Reply> =
+    OneshotReceiver<Result<(Acknowledgement, Option<Reply>), Top<CasterError>>>;
+
+pub(in crate::unicast) struct Caster<Message: UnicastMessage, Reply: UnicastMessage> {
+    state: Arc<Mutex<State<Message, Reply>>>,
+    keepalive: Arc<Notify>,
     _fuse: Fuse,
 }
 
-enum State<Message: UnicastMessage> {
-    Running(RequestInlet<Message>),
+enum State<Message: UnicastMessage, Reply: UnicastMessage> {
+    Running(Lanes<RequestInlet<Message, Reply>>),
     Terminated,
 }
 
-struct Database {
-    acknowledgement_inlets: HashMap<u32, AcknowledgementInlet>,
+struct Database<Reply> {
+    acknowledgement_inlets: HashMap<u32, AcknowledgementInlet<Reply>>,
 }
 
+pub(in crate::unicast) struct CasterInlet<Message: UnicastMessage, Reply: UnicastMessage>(
+    Lanes<RequestInlet<Message, Reply>>,
+);
+
 pub(in crate::unicast) struct CasterTerminated<Message: UnicastMessage>(pub Request<Message>);
 
 #[derive(Clone, Doom)]
+#[allow(clippy::enum_variant_names)]
 pub(in crate::unicast) enum CasterError {
     #[doom(description("Failed to connect"))]
     ConnectFailed,
-    #[doom(description("`Caster` congested"))]
-    CasterCongested,
     #[doom(description("`drive_in` failed"))]
     DriveInFailed,
     #[doom(description("`drive_out` failed"))]
@@ -64,70 +85,99 @@ enum DriveInError {
 enum DriveOutError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Failed to serialize request"))]
+    SerializeFailed,
 }
 
-impl<Message> Caster<Message>
+impl<Message, Reply> Caster<Message, Reply>
 where
     Message: UnicastMessage,
+    Reply: UnicastMessage,
 {
     pub fn new(connector: Arc<dyn Connector>, remote: Identity, settings: CasterSettings) -> Self {
-        let (request_inlet, request_outlet) = mpsc::channel(settings.request_channel_capacity);
-
-        let state = Arc::new(Mutex::new(State::Running(request_inlet)));
+        // Each `Priority` has its own request queue, so that a flood of
+        // low-priority requests cannot fill up the queue of higher priorities
+        let (control_inlet, control_outlet) = mpsc::channel(settings.request_channel_capacity);
+        let (normal_inlet, normal_outlet) = mpsc::channel(settings.request_channel_capacity);
+        let (bulk_inlet, bulk_outlet) = mpsc::channel(settings.request_channel_capacity);
+
+        let request_inlets = Lanes {
+            control: control_inlet,
+            normal: normal_inlet,
+            bulk: bulk_inlet,
+        };
+
+        let request_outlets = Lanes {
+            control: control_outlet,
+            normal: normal_outlet,
+            bulk: bulk_outlet,
+        };
+
+        let state = Arc::new(Mutex::new(State::Running(request_inlets)));
+        let keepalive = Arc::new(Notify::new());
         let fuse = Fuse::new();
 
         {
             let state = state.clone();
+            let keepalive = keepalive.clone();
 
             fuse.spawn(async move {
-                Caster::run(connector, remote, request_outlet, state).await;
+                Caster::run(
+                    connector,
+                    remote,
+                    request_outlets,
+                    keepalive,
+                    state,
+                    settings,
+                )
+                .await;
             });
         }
 
-        Caster { state, _fuse: fuse }
+        Caster {
+            state,
+            keepalive,
+            _fuse: fuse,
+        }
     }
 
-    pub fn post(
-        &self,
-        request: Request<Message>,
-    ) -> Result<AcknowledgementOutlet, CasterTerminated<Message>> {
+    pub fn inlet(&self) -> Option<CasterInlet<Message, Reply>> {
         match &*self.state.lock() {
-            State::Running(request_inlet) => {
-                let (acknowledgement_inlet, acknowledgement_outlet) = oneshot::channel();
-
-                match request_inlet.try_send((request, acknowledgement_inlet)) {
-                    Ok(()) => {}
-                    Err(error) => {
-                        let acknowledgement_inlet = match error {
-                            TrySendError::Full((_, acknowledgement_inlet)) => acknowledgement_inlet,
-                            TrySendError::Closed(_) => {
-                                // This is unreachable because `acknowledgement_outlet` is owned by `self`:
-                                // if `acknowledgement_outlet` was dropped, it would be impossible to call
-                                // `self.push` in the first place
-                                unreachable!()
-                            }
-                        };
-
-                        let _ = acknowledgement_inlet.send(CasterError::CasterCongested.fail());
-                    }
-                }
+            State::Running(request_inlets) => Some(CasterInlet(request_inlets.clone())),
+            State::Terminated => None,
+        }
+    }
 
-                Ok(acknowledgement_outlet)
+    // `Request::KeepAlive`s bypass the request queues (hence they cannot be
+    // congested), and are dequeued ahead of any `Control` request. Pending
+    // `Request::KeepAlive`s are coalesced. Returns `false` if `self` terminated.
+    pub fn keep_alive(&self) -> bool {
+        match &*self.state.lock() {
+            State::Running(_) => {
+                self.keepalive.notify_one();
+                true
             }
-            State::Terminated => Err(CasterTerminated(request)),
+            State::Terminated => false,
         }
     }
 
     async fn run(
         connector: Arc<dyn Connector>,
         remote: Identity,
-        mut request_outlet: RequestOutlet<Message>,
-        state: Arc<Mutex<State<Message>>>,
+        mut request_outlets: Lanes<RequestOutlet<Message, Reply>>,
+        keepalive: Arc<Notify>,
+        state: Arc<Mutex<State<Message, Reply>>>,
+        settings: CasterSettings,
     ) {
         let database = Arc::new(Mutex::new(Database {
             acknowledgement_inlets: HashMap::new(),
         }));
 
+        // `Request::Message`s can only be sent as long as the remote `Receiver`
+        // has granted credit for them: no credit is available until the remote
+        // grants its initial window
+        let credits = Semaphore::new(0);
+
         let result: Result<(), Top<CasterError>> = async {
             let connection = connector
                 .connect(remote)
@@ -138,14 +188,21 @@ where
 
             let result = tokio::try_join!(
                 async {
-                    Caster::<Message>::drive_in(&*database, receiver)
+                    Caster::<Message, Reply>::drive_in(&*database, &credits, receiver)
                         .await
                         .pot(CasterError::DriveInFailed, here!())
                 },
                 async {
-                    Caster::<Message>::drive_out(&*database, sender, &mut request_outlet)
-                        .await
-                        .pot(CasterError::DriveOutFailed, here!())
+                    Caster::<Message, Reply>::drive_out(
+                        &*database,
+                        &credits,
+                        sender,
+                        &mut request_outlets,
+                        &keepalive,
+                        &settings,
+                    )
+                    .await
+                    .pot(CasterError::DriveOutFailed, here!())
                 }
             );
 
@@ -156,58 +213,215 @@ where
         let error = result.unwrap_err();
         *state.lock() = State::Terminated;
 
-        Caster::<Message>::clean(database, request_outlet, error).await;
+        Caster::<Message, Reply>::clean(database, request_outlets, error).await;
     }
 
     async fn drive_in(
-        database: &Mutex<Database>,
+        database: &Mutex<Database<Reply>>,
+        credits: &Semaphore,
         mut receiver: SecureReceiver,
     ) -> Result<(), Top<DriveInError>> {
         loop {
-            let response = receiver
+            let response: Response<Reply> = receiver
                 .receive()
                 .await
                 .pot(DriveInError::ConnectionError, here!())?;
 
             match response {
-                Response::Acknowledgement(sequence, acknowledgement) => {
-                    if let Some(acknowledgement_inlet) = database
-                        .lock()
-                        .acknowledgement_inlets
-                        .remove(&sequence)
+                Response::Acknowledgement(sequence, acknowledgement, reply) => {
+                    if let Some(acknowledgement_inlet) =
+                        database.lock().acknowledgement_inlets.remove(&sequence)
                     {
-                        let _ = acknowledgement_inlet.send(Ok(acknowledgement));
+                        let _ = acknowledgement_inlet.send(Ok((acknowledgement, reply)));
                     }
                 }
+                Response::Credit(credit) => {
+                    // A misbehaving remote could grant more credit than `credits` can hold
+                    let room = MAX_CREDITS.saturating_sub(credits.available_permits());
+                    credits.add_permits(cmp::min(credit as usize, room));
+                }
             }
         }
     }
 
     async fn drive_out(
-        database: &Mutex<Database>,
+        database: &Mutex<Database<Reply>>,
+        credits: &Semaphore,
         mut sender: SecureSender,
-        request_outlet: &mut RequestOutlet<Message>,
+        request_outlets: &mut Lanes<RequestOutlet<Message, Reply>>,
+        keepalive: &Notify,
+        settings: &CasterSettings,
     ) -> Result<(), Top<DriveOutError>> {
-        for sequence in 0..u32::MAX {
-            if let Some((request, acknowledgement_inlet)) = request_outlet.recv().await {
+        let mut sequences = 0..u32::MAX;
+
+        // A request that did not fit in the previous batch, carried over to the next
+        let mut carry = None;
+
+        // `Control` messages dequeued while no credit was available
+        let mut stalled = VecDeque::new();
+
+        // Number of `Normal` or `Bulk` requests dequeued so far
+        let mut turns = 0;
+
+        loop {
+            let mut batch = Vec::new();
+            let mut batch_bytes = 0;
+            let mut deadline = Instant::now();
+
+            loop {
+                let (request, acknowledgement_inlet) = match carry.take() {
+                    Some(entry) => entry,
+                    None if batch.is_empty() => {
+                        match Caster::receive(
+                            request_outlets,
+                            keepalive,
+                            credits,
+                            &mut stalled,
+                            &mut turns,
+                            settings,
+                        )
+                        .await
+                        {
+                            Some(entry) => entry,
+                            None => return Ok(()),
+                        }
+                    }
+                    // The remote `Receiver` might be waiting for the current
+                    // batch to grant more credit: flush before waiting
+                    None if credits.available_permits() == 0 => break,
+                    // `timeout_at` polls `receive()` before checking `deadline`: requests
+                    // that are already queued are batched even if `max_linger` is zero
+                    None => {
+                        match time::timeout_at(
+                            deadline,
+                            Caster::receive(
+                                request_outlets,
+                                keepalive,
+                                credits,
+                                &mut stalled,
+                                &mut turns,
+                                settings,
+                            ),
+                        )
+                        .await
+                        {
+                            Ok(Some(entry)) => entry,
+                            _ => break,
+                        }
+                    }
+                };
+
+                let request_bytes = bincode::serialized_size(&request)
+                    .map_err(|_| DriveOutError::SerializeFailed.into_top())
+                    .spot(here!())? as usize;
+
+                if !batch.is_empty() && batch_bytes + request_bytes > settings.max_batch_bytes {
+                    carry = Some((request, acknowledgement_inlet));
+                    break;
+                }
+
+                // `Request::KeepAlive`s are not queued by the remote `Receiver`,
+                // and therefore consume no credit. `receive` only returns a
+                // `Request::Message` when credit is available, and only
+                // `drive_out` spends credit: `try_acquire` cannot fail
+                if let Request::Message(_) = request {
+                    credits.try_acquire().unwrap().forget();
+                }
+
+                let sequence = match sequences.next() {
+                    Some(sequence) => sequence,
+                    None => return Ok(()),
+                };
+
                 database
                     .lock()
                     .acknowledgement_inlets
                     .insert(sequence, acknowledgement_inlet);
 
-                sender
-                    .send(&request)
-                    .await
-                    .pot(DriveOutError::ConnectionError, here!())?;
+                if batch.is_empty() {
+                    deadline = Instant::now() + settings.max_linger;
+                }
+
+                batch.push(request);
+                batch_bytes += request_bytes;
             }
+
+            sender
+                .send(&batch)
+                .await
+                .pot(DriveOutError::ConnectionError, here!())?;
         }
+    }
+
+    // `Control` requests are dequeued by strict priority. To keep `Bulk` from
+    // starving, `Normal` requests are preferred over `Bulk` requests except one
+    // in every `bulk_share` turns, where `Bulk` is preferred over `Normal`.
+    // While no credit is available,
+    // only the `Control` lane is dequeued, so that `Request::KeepAlive`s keep
+    // flowing: `Control` messages are set aside in `stalled` until credit is
+    // granted, while `Normal` and `Bulk` requests are left in their lanes.
+    // `Request::KeepAlive`s signalled on `keepalive` are dequeued first.
+    // Returns a `Request::Message` only if credit is available.
+    async fn receive(
+        request_outlets: &mut Lanes<RequestOutlet<Message, Reply>>,
+        keepalive: &Notify,
+        credits: &Semaphore,
+        stalled: &mut VecDeque<RequestEntry<Message, Reply>>,
+        turns: &mut u64,
+        settings: &CasterSettings,
+    ) -> Option<RequestEntry<Message, Reply>> {
+        let Lanes {
+            control,
+            normal,
+            bulk,
+        } = request_outlets;
 
-        Ok(())
+        loop {
+            let credited = credits.available_permits() > 0;
+
+            if credited && !stalled.is_empty() {
+                return stalled.pop_front();
+            }
+
+            let unstalled = stalled.is_empty();
+            let room = stalled.len() < settings.request_channel_capacity;
+
+            let (first, second) = if *turns % (settings.bulk_share.max(1) as u64) == 0 {
+                (&mut *bulk, &mut *normal)
+            } else {
+                (&mut *normal, &mut *bulk)
+            };
+
+            tokio::select! {
+                biased;
+                _ = keepalive.notified() => {
+                    // Nobody waits for the acknowledgement of a `Request::KeepAlive`
+                    let (acknowledgement_inlet, _) = oneshot::channel();
+                    return Some((Request::KeepAlive, acknowledgement_inlet));
+                }
+                Some(entry) = control.recv(), if room => match entry {
+                    (Request::Message(_), _) if !credited => stalled.push_back(entry),
+                    entry => return Some(entry),
+                },
+                Some(entry) = first.recv(), if credited && unstalled => {
+                    *turns += 1;
+                    return Some(entry);
+                }
+                Some(entry) = second.recv(), if credited && unstalled => {
+                    *turns += 1;
+                    return Some(entry);
+                }
+                // `credits` is never closed, hence `acquire` cannot fail. The
+                // permit is returned immediately: this only waits for credit
+                _ = credits.acquire(), if !credited => {}
+                else => return None,
+            }
+        }
     }
 
     async fn clean(
-        database: Arc<Mutex<Database>>,
-        mut request_outlet: RequestOutlet<Message>,
+        database: Arc<Mutex<Database<Reply>>>,
+        mut request_outlets: Lanes<RequestOutlet<Message, Reply>>,
         error: Top<CasterError>,
     ) {
         let mut database = database.lock();
@@ -216,8 +430,36 @@ where
             let _ = acknowledgement_inlet.send(Err(error.clone()));
         }
 
-        while let Ok((_, acknowledgement_inlet)) = request_outlet.try_recv() {
-            let _ = acknowledgement_inlet.send(Err(error.clone()));
+        for request_outlet in request_outlets.iter_mut() {
+            // Closing `request_outlet` first wakes up any `CasterInlet::post` waiting
+            // for room, and guarantees that no request is enqueued after draining
+            request_outlet.close();
+
+            while let Ok((_, acknowledgement_inlet)) = request_outlet.try_recv() {
+                let _ = acknowledgement_inlet.send(Err(error.clone()));
+            }
+        }
+    }
+}
+
+impl<Message, Reply> CasterInlet<Message, Reply>
+where
+    Message: UnicastMessage,
+    Reply: UnicastMessage,
+{
+    // This waits for room in the request queue of `priority`
+    pub async fn post(
+        &self,
+        request: Request<Message>,
+        priority: Priority,
+    ) -> Result<AcknowledgementOutlet<Reply>, CasterTerminated<Message>> {
+        let (acknowledgement_inlet, acknowledgement_outlet) = oneshot::channel();
+
+        let request_inlet = self.0.lane(priority);
+
+        match request_inlet.send((request, acknowledgement_inlet)).await {
+            Ok(()) => Ok(acknowledgement_outlet),
+            Err(SendError((request, _))) => Err(CasterTerminated(request)),
         }
     }
 }
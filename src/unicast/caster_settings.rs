@@ -1,14 +1,22 @@
 use crate::unicast::SenderSettings;
 
+use std::time::Duration;
+
 #[derive(Debug, Clone)]
 pub(in crate::unicast) struct CasterSettings {
     pub request_channel_capacity: usize,
+    pub max_batch_bytes: usize,
+    pub max_linger: Duration,
+    pub bulk_share: u32,
 }
 
 impl CasterSettings {
     pub fn from_sender_settings(settings: &SenderSettings) -> Self {
         CasterSettings {
             request_channel_capacity: settings.request_channel_capacity,
+            max_batch_bytes: settings.max_batch_bytes,
+            max_linger: settings.max_linger,
+            bulk_share: settings.bulk_share,
         }
     }
 }
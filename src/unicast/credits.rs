@@ -0,0 +1,61 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use tokio::sync::Notify;
+
+// Credit accounting for a single connection of a `Receiver`: `available`
+// counts the credits granted to the remote `Caster` and not yet spent, while
+// `pending` counts the credits that the application freed up (by consuming
+// messages) but that were not yet granted to the remote `Caster`
+pub(in crate::unicast) struct Credits {
+    available: AtomicU32,
+    pending: AtomicU32,
+    notify: Notify,
+}
+
+// Returns its credit to `Credits` when dropped
+pub(in crate::unicast) struct Credit(Arc<Credits>);
+
+impl Credits {
+    pub fn new() -> Self {
+        Credits {
+            available: AtomicU32::new(0),
+            pending: AtomicU32::new(0),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn spend(self: &Arc<Self>) -> Option<Credit> {
+        self.available
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |available| {
+                available.checked_sub(1)
+            })
+            .ok()
+            .map(|_| Credit(self.clone()))
+    }
+
+    pub async fn grantable(&self) -> u32 {
+        loop {
+            let pending = self.pending.swap(0, Ordering::AcqRel);
+
+            if pending > 0 {
+                return pending;
+            }
+
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn grant(&self, credit: u32) {
+        self.available.fetch_add(credit, Ordering::AcqRel);
+    }
+}
+
+impl Drop for Credit {
+    fn drop(&mut self) {
+        self.0.pending.fetch_add(1, Ordering::AcqRel);
+        self.0.notify.notify_one();
+    }
+}
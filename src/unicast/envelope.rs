@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::unicast) struct Envelope {
+    pub tag: String,
+    pub payload: Vec<u8>,
+}
@@ -2,28 +2,64 @@ mod acknowledgement;
 mod acknowledger;
 mod caster;
 mod caster_settings;
+mod credits;
+mod envelope;
 mod message;
+mod packet;
+mod priority;
+mod push_handle;
+mod push_outcome;
 mod push_settings;
 mod receiver;
 mod receiver_settings;
+mod rejection;
+mod reliable_receiver;
+mod reliable_receiver_settings;
+mod reliable_sender;
+mod reliable_sender_settings;
 mod request;
 mod response;
+mod router;
+mod router_settings;
 mod sender;
 mod sender_settings;
+mod tagged_message;
+mod typed_acknowledger;
+mod typed_receiver;
+mod typed_sender;
 
 #[cfg(any(test, feature = "test_utilities"))]
 pub mod test;
 
 use caster::{Caster, CasterError, CasterTerminated};
 use caster_settings::CasterSettings;
+use credits::{Credit, Credits};
+use envelope::Envelope;
+use packet::Packet;
+use priority::Lanes;
+use push_handle::PushTracker;
+use rejection::Rejection;
 use request::Request;
 use response::Response;
 
 pub use acknowledgement::Acknowledgement;
 pub use acknowledger::Acknowledger;
 pub use message::Message;
+pub use priority::Priority;
+pub use push_handle::{PushHandle, PushProgress};
+pub use push_outcome::{PushOutcome, PushTermination};
 pub use push_settings::{PartialPushSettings, PushSettings};
 pub use receiver::Receiver;
 pub use receiver_settings::ReceiverSettings;
+pub use reliable_receiver::ReliableReceiver;
+pub use reliable_receiver_settings::ReliableReceiverSettings;
+pub use reliable_sender::ReliableSender;
+pub use reliable_sender_settings::ReliableSenderSettings;
+pub use router::{Router, RouterError};
+pub use router_settings::RouterSettings;
 pub use sender::{Sender, SenderError};
 pub use sender_settings::SenderSettings;
+pub use tagged_message::TaggedMessage;
+pub use typed_acknowledger::TypedAcknowledger;
+pub use typed_receiver::TypedReceiver;
+pub use typed_sender::{TypedSender, TypedSenderError};
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(in crate::unicast) struct Packet<Message> {
+    pub session: u64,
+    pub sequence: u64,
+    // Every sequence number below `floor` was acknowledged
+    // by the receiver by the time the `Packet` was built
+    pub floor: u64,
+    pub message: Message,
+}
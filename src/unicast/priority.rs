@@ -0,0 +1,29 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    Control,
+    #[default]
+    Normal,
+    Bulk,
+}
+
+// One value per `Priority`, e.g., one request queue per `Priority`
+#[derive(Debug, Clone)]
+pub(in crate::unicast) struct Lanes<T> {
+    pub control: T,
+    pub normal: T,
+    pub bulk: T,
+}
+
+impl<T> Lanes<T> {
+    pub fn lane(&self, priority: Priority) -> &T {
+        match priority {
+            Priority::Control => &self.control,
+            Priority::Normal => &self.normal,
+            Priority::Bulk => &self.bulk,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        vec![&mut self.control, &mut self.normal, &mut self.bulk].into_iter()
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    sync::fuse::Fuse,
+    unicast::{Acknowledgement, PushOutcome},
+};
+
+use parking_lot::Mutex;
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        Notify,
+    },
+    task::JoinHandle,
+    time::Instant,
+};
+
+#[derive(Debug, Clone)]
+pub struct PushProgress {
+    pub acknowledgement: Option<Acknowledgement>,
+    pub attempts: u32,
+    pub elapsed: Duration,
+}
+
+// Shared between a running push and its `PushHandle` (if any)
+pub(in crate::unicast) struct PushTracker {
+    start: Instant,
+    state: Mutex<(u32, Option<Acknowledgement>)>,
+    cancel: Notify,
+    acknowledgement_inlet: Mutex<Option<UnboundedSender<Acknowledgement>>>,
+}
+
+/// Handle to a push started by `Sender::start_push`. Dropping a `PushHandle`
+/// aborts its push, while `cancel` stops it gracefully, so that `outcome`
+/// still reports what the push achieved before being cancelled.
+pub struct PushHandle {
+    tracker: Arc<PushTracker>,
+    acknowledgement_outlet: UnboundedReceiver<Acknowledgement>,
+    outcome: JoinHandle<Option<PushOutcome>>,
+    _fuse: Fuse,
+}
+
+impl PushTracker {
+    pub fn new() -> Self {
+        PushTracker {
+            start: Instant::now(),
+            state: Mutex::new((0, None)),
+            cancel: Notify::new(),
+            acknowledgement_inlet: Mutex::new(None),
+        }
+    }
+
+    // Like `new`, but also returns an outlet for every `Acknowledgement`
+    // recorded, which is closed by `terminate`
+    pub fn observed() -> (Self, UnboundedReceiver<Acknowledgement>) {
+        let (acknowledgement_inlet, acknowledgement_outlet) = mpsc::unbounded_channel();
+
+        let tracker = PushTracker {
+            acknowledgement_inlet: Mutex::new(Some(acknowledgement_inlet)),
+            ..PushTracker::new()
+        };
+
+        (tracker, acknowledgement_outlet)
+    }
+
+    pub fn record_attempt(&self) {
+        self.state.lock().0 += 1;
+    }
+
+    pub fn record_acknowledgement(&self, acknowledgement: Acknowledgement) {
+        self.state.lock().1 = Some(acknowledgement);
+
+        if let Some(acknowledgement_inlet) = self.acknowledgement_inlet.lock().as_ref() {
+            let _ = acknowledgement_inlet.send(acknowledgement);
+        }
+    }
+
+    pub fn terminate(&self) {
+        self.acknowledgement_inlet.lock().take();
+    }
+
+    pub fn progress(&self) -> PushProgress {
+        let (attempts, acknowledgement) = *self.state.lock();
+
+        PushProgress {
+            acknowledgement,
+            attempts,
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    pub async fn cancelled(&self) {
+        self.cancel.notified().await;
+    }
+}
+
+impl PushHandle {
+    pub(in crate::unicast) fn new(
+        tracker: Arc<PushTracker>,
+        acknowledgement_outlet: UnboundedReceiver<Acknowledgement>,
+        outcome: JoinHandle<Option<PushOutcome>>,
+        fuse: Fuse,
+    ) -> Self {
+        PushHandle {
+            tracker,
+            acknowledgement_outlet,
+            outcome,
+            _fuse: fuse,
+        }
+    }
+
+    pub fn progress(&self) -> PushProgress {
+        self.tracker.progress()
+    }
+
+    /// Waits for the next `Acknowledgement` received by the push, in order.
+    /// Returns `None` once the push has terminated and every `Acknowledgement`
+    /// has been returned.
+    pub async fn next_acknowledgement(&mut self) -> Option<Acknowledgement> {
+        self.acknowledgement_outlet.recv().await
+    }
+
+    pub fn cancel(&self) {
+        // `notify_one` stores a permit if the push is not currently waiting
+        // on `cancelled()`, so that cancellation is never missed
+        self.tracker.cancel.notify_one();
+    }
+
+    pub async fn outcome(self) -> PushOutcome {
+        let PushHandle { outcome, _fuse, .. } = self;
+
+        // `outcome` cannot be burned, as `_fuse` is alive until
+        // the end of this function
+        outcome.await.unwrap().unwrap()
+    }
+}
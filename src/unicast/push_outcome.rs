@@ -0,0 +1,25 @@
+use crate::unicast::Acknowledgement;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushTermination {
+    Acknowledged,
+    AttemptsExhausted,
+    DeadlineExceeded,
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct PushOutcome {
+    pub termination: PushTermination,
+    pub acknowledgement: Option<Acknowledgement>,
+    pub attempts: u32,
+    pub elapsed: Duration,
+}
+
+impl PushOutcome {
+    pub fn acknowledged(&self) -> bool {
+        self.termination == PushTermination::Acknowledged
+    }
+}
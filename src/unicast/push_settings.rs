@@ -1,6 +1,6 @@
 use crate::{
     time::{sleep_schedules::CappedExponential, SleepSchedule},
-    unicast::Acknowledgement,
+    unicast::{Acknowledgement, Priority},
 };
 
 use std::{sync::Arc, time::Duration};
@@ -9,11 +9,17 @@ use std::{sync::Arc, time::Duration};
 pub struct PushSettings {
     pub stop_condition: Acknowledgement,
     pub retry_schedule: Arc<dyn SleepSchedule>,
+    pub priority: Priority,
+    pub max_attempts: Option<u32>,
+    pub deadline: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
 pub struct PartialPushSettings {
     pub retry_schedule: Arc<dyn SleepSchedule>,
+    pub priority: Priority,
+    pub max_attempts: Option<u32>,
+    pub deadline: Option<Duration>,
 }
 
 impl PushSettings {
@@ -24,6 +30,9 @@ impl PushSettings {
         PushSettings {
             stop_condition,
             retry_schedule: partial_push_settings.retry_schedule,
+            priority: partial_push_settings.priority,
+            max_attempts: partial_push_settings.max_attempts,
+            deadline: partial_push_settings.deadline,
         }
     }
 }
@@ -37,6 +46,9 @@ impl Default for PushSettings {
                 2.,
                 Duration::from_secs(300),
             )),
+            priority: Priority::Normal,
+            max_attempts: None,
+            deadline: None,
         }
     }
 }
@@ -47,6 +59,9 @@ impl Default for PartialPushSettings {
 
         PartialPushSettings {
             retry_schedule: push_settings.retry_schedule,
+            priority: push_settings.priority,
+            max_attempts: push_settings.max_attempts,
+            deadline: push_settings.deadline,
         }
     }
 }
@@ -62,6 +77,9 @@ mod test {
             PushSettings {
                 stop_condition: Acknowledgement::Strong,
                 retry_schedule: Arc::new(Constant::new(Duration::from_millis(100))),
+                priority: Priority::Normal,
+                max_attempts: None,
+                deadline: None,
             }
         }
     }
@@ -3,26 +3,29 @@ use crate::{
     net::{Listener, SecureConnection, SecureReceiver, SecureSender},
     sync::fuse::Fuse,
     unicast::{
-        Acknowledgement, Acknowledger, Message as UnicastMessage, ReceiverSettings, Request,
-        Response,
+        Acknowledgement, Acknowledger, Credit, Credits, Message as UnicastMessage,
+        ReceiverSettings, Request, Response,
     },
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
+use std::{cmp, sync::Arc};
+
 use tokio::sync::{
     mpsc,
     mpsc::{Receiver as TokioReceiver, Sender as TokioSender},
 };
 
-type MessageInlet<Message> = TokioSender<(Identity, Message, Acknowledger)>;
-type MessageOutlet<Message> = TokioReceiver<(Identity, Message, Acknowledger)>;
+type MessageInlet<Message, Reply> = TokioSender<(Identity, Message, Acknowledger<Reply>, Credit)>;
+type MessageOutlet<Message, Reply> =
+    TokioReceiver<(Identity, Message, Acknowledger<Reply>, Credit)>;
 
-type ResponseInlet = TokioSender<Response>;
-type ResponseOutlet = TokioReceiver<Response>;
+type ResponseInlet<Reply> = TokioSender<Response<Reply>>;
+type ResponseOutlet<Reply> = TokioReceiver<Response<Reply>>;
 
-pub struct Receiver<Message: UnicastMessage> {
-    message_outlet: MessageOutlet<Message>,
+pub struct Receiver<Message: UnicastMessage, Reply: UnicastMessage = ()> {
+    message_outlet: MessageOutlet<Message, Reply>,
     _fuse: Fuse,
 }
 
@@ -38,6 +41,8 @@ enum ServeError {
 enum DriveInError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Remote sent a message without credit"))]
+    CreditExceeded,
 }
 
 #[derive(Doom)]
@@ -46,9 +51,10 @@ enum DriveOutError {
     ConnectionError,
 }
 
-impl<Message> Receiver<Message>
+impl<Message, Reply> Receiver<Message, Reply>
 where
     Message: UnicastMessage,
+    Reply: UnicastMessage,
 {
     pub fn new<L>(listener: L, settings: ReceiverSettings) -> Self
     where
@@ -68,16 +74,21 @@ where
         }
     }
 
-    pub async fn receive(&mut self) -> (Identity, Message, Acknowledger) {
+    pub async fn receive(&mut self) -> (Identity, Message, Acknowledger<Reply>) {
         // This cannot fail, as `message_inlet` is held by `listen` until
         // `self._fuse` is dropped along with `self`: if `recv()` failed,
         // one could not call `receive()` in the first place
-        self.message_outlet.recv().await.unwrap()
+        let (remote, message, acknowledger, credit) = self.message_outlet.recv().await.unwrap();
+
+        // Now that `message` is consumed, its credit can be granted back to `remote`
+        drop(credit);
+
+        (remote, message, acknowledger)
     }
 
     async fn listen<L>(
         mut listener: L,
-        message_inlet: MessageInlet<Message>,
+        message_inlet: MessageInlet<Message, Reply>,
         settings: ReceiverSettings,
     ) where
         L: Listener,
@@ -99,24 +110,43 @@ where
     async fn serve(
         remote: Identity,
         connection: SecureConnection,
-        message_inlet: MessageInlet<Message>,
+        message_inlet: MessageInlet<Message, Reply>,
         settings: ReceiverSettings,
     ) -> Result<(), Top<ServeError>> {
         let (sender, receiver) = connection.split();
 
+        // Up to `credit_window` messages can be awaiting acknowledgement
+        let response_channel_capacity = cmp::max(
+            settings.response_channel_capacity,
+            settings.credit_window as usize,
+        );
+
         let (response_inlet, response_outlet) =
-            mpsc::channel::<Response>(settings.response_channel_capacity);
+            mpsc::channel::<Response<Reply>>(response_channel_capacity);
+
+        let credits = Arc::new(Credits::new());
 
         let result = tokio::try_join!(
             async {
-                Receiver::<Message>::drive_in(remote, receiver, message_inlet, response_inlet)
-                    .await
-                    .pot(ServeError::DriveInFailed, here!())
+                Receiver::<Message, Reply>::drive_in(
+                    remote,
+                    receiver,
+                    &credits,
+                    message_inlet,
+                    response_inlet,
+                )
+                .await
+                .pot(ServeError::DriveInFailed, here!())
             },
             async {
-                Receiver::<Message>::drive_out(sender, response_outlet)
-                    .await
-                    .pot(ServeError::DriveOutFailed, here!())
+                Receiver::<Message, Reply>::drive_out(
+                    sender,
+                    &credits,
+                    settings.credit_window,
+                    response_outlet,
+                )
+                .await
+                .pot(ServeError::DriveOutFailed, here!())
             }
         );
 
@@ -126,42 +156,91 @@ where
     async fn drive_in(
         remote: Identity,
         mut receiver: SecureReceiver,
-        message_inlet: MessageInlet<Message>,
-        response_inlet: ResponseInlet,
+        credits: &Arc<Credits>,
+        message_inlet: MessageInlet<Message, Reply>,
+        response_inlet: ResponseInlet<Reply>,
     ) -> Result<(), Top<DriveInError>> {
-        for sequence in 0..u32::MAX {
-            let request: Request<Message> = receiver
+        let mut sequences = 0..u32::MAX;
+
+        loop {
+            // `Caster` coalesces requests into batches, each request in a batch
+            // taking the next sequence number
+            let batch: Vec<Request<Message>> = receiver
                 .receive()
                 .await
                 .pot(DriveInError::ConnectionError, here!())?;
 
-            match request {
-                Request::Message(message) => {
-                    let acknowledger = Acknowledger::new(sequence, response_inlet.clone());
-
-                    let _ = message_inlet.try_send((remote, message, acknowledger));
-                }
-                Request::KeepAlive => {
-                    let _ = response_inlet
-                        .try_send(Response::Acknowledgement(sequence, Acknowledgement::Weak));
+            for request in batch {
+                let sequence = match sequences.next() {
+                    Some(sequence) => sequence,
+                    None => return Ok(()),
+                };
+
+                match request {
+                    Request::Message(message) => {
+                        // A well-behaved `Caster` never exceeds its credit: the number
+                        // of messages queued for each connection is bounded by
+                        // `credit_window`, hence waiting on `message_inlet` is safe
+                        let credit = match credits.spend() {
+                            Some(credit) => credit,
+                            None => return DriveInError::CreditExceeded.fail().spot(here!()),
+                        };
+
+                        let acknowledger = Acknowledger::new(sequence, response_inlet.clone());
+
+                        // This can only fail if the (local) receiving end is
+                        // dropped, in which case we don't care about the error
+                        let _ = message_inlet
+                            .send((remote, message, acknowledger, credit))
+                            .await;
+                    }
+                    Request::KeepAlive => {
+                        // Waiting on `response_inlet` applies backpressure to a
+                        // remote that sends `KeepAlive`s faster than it reads
+                        let _ = response_inlet
+                            .send(Response::Acknowledgement(
+                                sequence,
+                                Acknowledgement::Weak,
+                                None,
+                            ))
+                            .await;
+                    }
                 }
             }
         }
-
-        Ok(())
     }
 
     async fn drive_out(
         mut sender: SecureSender,
-        mut response_outlet: ResponseOutlet,
+        credits: &Credits,
+        credit_window: u32,
+        mut response_outlet: ResponseOutlet<Reply>,
     ) -> Result<(), Top<DriveOutError>> {
+        credits.grant(credit_window);
+
+        sender
+            .send(&Response::<Reply>::Credit(credit_window))
+            .await
+            .pot(DriveOutError::ConnectionError, here!())?;
+
         loop {
-            if let Some(response) = response_outlet.recv().await {
-                sender
-                    .send(&response)
-                    .await
-                    .pot(DriveOutError::ConnectionError, here!())?;
-            }
+            let response = tokio::select! {
+                response = response_outlet.recv() => match response {
+                    Some(response) => response,
+                    None => continue,
+                },
+                credit = credits.grantable() => {
+                    // Credits are made available before being granted: the
+                    // remote can never spend credits that `credits` lacks
+                    credits.grant(credit);
+                    Response::Credit(credit)
+                }
+            };
+
+            sender
+                .send(&response)
+                .await
+                .pot(DriveOutError::ConnectionError, here!())?;
         }
     }
 }
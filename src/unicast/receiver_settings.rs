@@ -2,6 +2,7 @@
 pub struct ReceiverSettings {
     pub message_channel_capacity: usize,
     pub response_channel_capacity: usize,
+    pub credit_window: u32,
 }
 
 impl Default for ReceiverSettings {
@@ -9,6 +10,7 @@ impl Default for ReceiverSettings {
         ReceiverSettings {
             message_channel_capacity: 32768,
             response_channel_capacity: 64,
+            credit_window: 1024,
         }
     }
 }
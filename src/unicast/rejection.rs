@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Replied by a `Router` along with a `Weak` acknowledgement whenever a
+/// message cannot be delivered to a `TypedReceiver`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(in crate::unicast) enum Rejection {
+    UnknownTag,
+    MalformedPayload,
+    Congested,
+}
@@ -0,0 +1,221 @@
+use crate::{
+    crypto::Identity,
+    net::Listener,
+    sync::fuse::Fuse,
+    unicast::{
+        Acknowledger, Message as UnicastMessage, Packet, Receiver, ReliableReceiverSettings,
+    },
+};
+
+use std::{
+    cmp,
+    collections::{BTreeMap, HashMap, VecDeque},
+    time::Instant,
+};
+
+use tokio::sync::{
+    mpsc,
+    mpsc::{Receiver as TokioReceiver, Sender as TokioSender},
+};
+
+type MessageInlet<Message> = TokioSender<(Identity, Message)>;
+type MessageOutlet<Message> = TokioReceiver<(Identity, Message)>;
+
+pub struct ReliableReceiver<Message: UnicastMessage> {
+    message_outlet: MessageOutlet<Message>,
+    _fuse: Fuse,
+}
+
+struct Stream<Message> {
+    next: u64,
+    // Buffered messages keep their `Acknowledger`: they are only
+    // acknowledged `strong` once handed to `message_inlet`
+    pending: BTreeMap<u64, (Message, Acknowledger)>,
+    last_active: Instant,
+}
+
+// Next sequence number to deliver of every evicted session: a resumed
+// session never re-delivers what was delivered before its eviction. Only
+// the `capacity` most recently evicted sessions of each remote are kept
+struct Watermarks {
+    capacity: usize,
+    remotes: HashMap<Identity, VecDeque<(u64, u64)>>,
+}
+
+impl<Message> ReliableReceiver<Message>
+where
+    Message: UnicastMessage,
+{
+    pub fn new<L>(listener: L, settings: ReliableReceiverSettings) -> Self
+    where
+        L: Listener,
+    {
+        let receiver = Receiver::new(listener, settings.receiver_settings.clone());
+        let (message_inlet, message_outlet) = mpsc::channel(settings.message_channel_capacity);
+
+        let fuse = Fuse::new();
+
+        fuse.spawn(async move {
+            ReliableReceiver::order(receiver, message_inlet, settings).await;
+        });
+
+        ReliableReceiver {
+            message_outlet,
+            _fuse: fuse,
+        }
+    }
+
+    pub async fn receive(&mut self) -> (Identity, Message) {
+        // This cannot fail, as `message_inlet` is held by `order` until
+        // `self._fuse` is dropped along with `self`: if `recv()` failed,
+        // one could not call `receive()` in the first place
+        self.message_outlet.recv().await.unwrap()
+    }
+
+    async fn order(
+        mut receiver: Receiver<Packet<Message>>,
+        message_inlet: MessageInlet<Message>,
+        settings: ReliableReceiverSettings,
+    ) {
+        let mut streams: HashMap<Identity, HashMap<u64, Stream<Message>>> = HashMap::new();
+
+        let mut watermarks = Watermarks::new(settings.max_sessions);
+
+        let mut last_sweep = Instant::now();
+
+        loop {
+            let (remote, packet, acknowledger) = receiver.receive().await;
+            let now = Instant::now();
+
+            // Sessions idle for longer than `session_timeout` are evicted: should
+            // their `ReliableSender` resume, they pick up from their watermark
+            // (dropping buffered messages acknowledges them `weak`, so that
+            // the `ReliableSender` sends them again)
+            if now.duration_since(last_sweep) >= settings.session_timeout {
+                for (remote, sessions) in streams.iter_mut() {
+                    let idle = sessions
+                        .iter()
+                        .filter(|(_, stream)| {
+                            now.duration_since(stream.last_active) >= settings.session_timeout
+                        })
+                        .map(|(session, _)| *session)
+                        .collect::<Vec<_>>();
+
+                    for session in idle {
+                        let stream = sessions.remove(&session).unwrap();
+                        watermarks.retire(*remote, session, stream.next);
+                    }
+                }
+
+                streams.retain(|_, sessions| !sessions.is_empty());
+                last_sweep = now;
+            }
+
+            let sessions = streams.entry(remote).or_default();
+
+            if !sessions.contains_key(&packet.session) {
+                // The watermark of a resumed session is taken before any other
+                // session is evicted, lest it be pushed out of `watermarks`
+                let watermark = watermarks.resume(remote, packet.session);
+
+                // Each remote is allowed at most `max_sessions` concurrent
+                // sessions: beyond that, its least recently active is evicted
+                if sessions.len() >= settings.max_sessions {
+                    let stalest = sessions
+                        .iter()
+                        .min_by_key(|(_, stream)| stream.last_active)
+                        .map(|(session, _)| *session);
+
+                    if let Some(stalest) = stalest {
+                        let stream = sessions.remove(&stalest).unwrap();
+                        watermarks.retire(remote, stalest, stream.next);
+                    }
+                }
+
+                sessions.insert(
+                    packet.session,
+                    Stream {
+                        next: cmp::max(packet.floor, watermark),
+                        pending: BTreeMap::new(),
+                        last_active: now,
+                    },
+                );
+            }
+
+            let stream = sessions.get_mut(&packet.session).unwrap();
+            stream.last_active = now;
+
+            // Duplicates of delivered messages (e.g., messages re-sent after a
+            // reconnection, whose acknowledgement was lost) are acknowledged
+            // again, but not delivered
+            if packet.sequence < stream.next {
+                acknowledger.strong();
+                continue;
+            }
+
+            // Messages too far ahead are not buffered: the `ReliableSender`
+            // will re-send them once the gap before them is filled
+            if packet.sequence >= stream.next + settings.window {
+                acknowledger.weak();
+                continue;
+            }
+
+            // A duplicate of a buffered message replaces it, along with its
+            // `Acknowledger` (the replaced one is dropped, acknowledging `weak`)
+            stream
+                .pending
+                .insert(packet.sequence, (packet.message, acknowledger));
+
+            while let Some((message, acknowledger)) = stream.pending.remove(&stream.next) {
+                stream.next += 1;
+
+                if message_inlet.send((remote, message)).await.is_err() {
+                    return;
+                }
+
+                acknowledger.strong();
+            }
+        }
+    }
+}
+
+impl Watermarks {
+    fn new(capacity: usize) -> Self {
+        Watermarks {
+            capacity,
+            remotes: HashMap::new(),
+        }
+    }
+
+    fn retire(&mut self, remote: Identity, session: u64, next: u64) {
+        let watermarks = self.remotes.entry(remote).or_default();
+        watermarks.push_back((session, next));
+
+        // A session resumed after its watermark was dropped starts over from
+        // its `floor`, possibly re-delivering messages
+        while watermarks.len() > self.capacity {
+            watermarks.pop_front();
+        }
+    }
+
+    fn resume(&mut self, remote: Identity, session: u64) -> u64 {
+        let watermarks = match self.remotes.get_mut(&remote) {
+            Some(watermarks) => watermarks,
+            None => return 0,
+        };
+
+        let next = match watermarks
+            .iter()
+            .position(|(evicted, _)| *evicted == session)
+        {
+            Some(position) => watermarks.remove(position).unwrap().1,
+            None => 0,
+        };
+
+        if watermarks.is_empty() {
+            self.remotes.remove(&remote);
+        }
+
+        next
+    }
+}
@@ -0,0 +1,24 @@
+use crate::unicast::ReceiverSettings;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ReliableReceiverSettings {
+    pub receiver_settings: ReceiverSettings,
+    pub message_channel_capacity: usize,
+    pub window: u64,
+    pub session_timeout: Duration,
+    pub max_sessions: usize,
+}
+
+impl Default for ReliableReceiverSettings {
+    fn default() -> Self {
+        ReliableReceiverSettings {
+            receiver_settings: Default::default(),
+            message_channel_capacity: 32768,
+            window: 4096,
+            session_timeout: Duration::from_secs(3600),
+            max_sessions: 16,
+        }
+    }
+}
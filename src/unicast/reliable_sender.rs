@@ -0,0 +1,143 @@
+use crate::{
+    crypto::Identity,
+    net::Connector,
+    sync::fuse::{Fuse, Relay},
+    unicast::{
+        Acknowledgement, Message as UnicastMessage, Packet, Priority, PushOutcome, PushSettings,
+        ReliableSenderSettings, Sender,
+    },
+};
+
+use parking_lot::Mutex;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    sync::Arc,
+};
+
+use tokio::task::JoinHandle;
+
+#[derive(Clone)]
+pub struct ReliableSender<Message: UnicastMessage + Clone> {
+    sender: Sender<Packet<Message>>,
+    session: u64,
+    streams: Arc<Mutex<HashMap<Identity, Stream>>>,
+    settings: ReliableSenderSettings,
+    fuse: Arc<Fuse>,
+}
+
+#[derive(Default)]
+struct Stream {
+    next: u64,
+    unacknowledged: BTreeSet<u64>,
+}
+
+impl<Message> ReliableSender<Message>
+where
+    Message: UnicastMessage + Clone,
+{
+    pub fn new<C>(connector: C, settings: ReliableSenderSettings) -> Self
+    where
+        C: Connector,
+    {
+        let sender = Sender::new(connector, settings.sender_settings.clone());
+
+        // Sequence numbers are scoped by `session`: a `ReliableReceiver` never
+        // mistakes the messages of a new `ReliableSender` for duplicates
+        ReliableSender {
+            sender,
+            session: rand::random(),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            settings,
+            fuse: Arc::new(Fuse::new()),
+        }
+    }
+
+    /// Sends `message` to `remote`, retrying (across reconnections, if needed)
+    /// until `remote` acknowledges it. Each `message` is delivered exactly once
+    /// and, among the `message`s sent to the same `remote`, in the order in
+    /// which `send` was called (rather than the order in which the returned
+    /// futures are polled).
+    ///
+    /// Delivery starts upon calling `send`, and keeps going even if the returned
+    /// future is dropped: a sequence number, once assigned, is never skipped
+    /// (which would stall `remote`'s `ReliableReceiver`). Pending deliveries are
+    /// only abandoned once every clone of this `ReliableSender` (and every
+    /// future it returned) is dropped.
+    pub fn send(&self, remote: Identity, message: Message) -> impl Future<Output = PushOutcome> {
+        let packet = self.packet(remote, message);
+        let sequence = packet.sequence;
+
+        let sender = self.sender.clone();
+        let streams = self.streams.clone();
+        let settings = self.push_settings();
+
+        let handle = self.fuse.spawn(async move {
+            let outcome = sender.push(remote, packet, settings).await;
+
+            if let Some(stream) = streams.lock().get_mut(&remote) {
+                stream.unacknowledged.remove(&sequence);
+            }
+
+            outcome
+        });
+
+        // Holding `fuse` guarantees that the delivery is not cancelled
+        // while the returned future is pending
+        let fuse = self.fuse.clone();
+
+        async move {
+            let outcome = handle.await.unwrap().unwrap();
+            drop(fuse);
+
+            outcome
+        }
+    }
+
+    pub fn run_send(
+        &self,
+        remote: Identity,
+        message: Message,
+        relay: Relay,
+    ) -> JoinHandle<Option<PushOutcome>> {
+        relay.run(self.send(remote, message))
+    }
+
+    pub fn spawn_send(
+        &self,
+        remote: Identity,
+        message: Message,
+        fuse: &Fuse,
+    ) -> JoinHandle<Option<PushOutcome>> {
+        self.run_send(remote, message, fuse.relay())
+    }
+
+    fn packet(&self, remote: Identity, message: Message) -> Packet<Message> {
+        let mut streams = self.streams.lock();
+        let stream = streams.entry(remote).or_default();
+
+        let sequence = stream.next;
+        stream.next += 1;
+
+        stream.unacknowledged.insert(sequence);
+        let floor = *stream.unacknowledged.iter().next().unwrap();
+
+        Packet {
+            session: self.session,
+            sequence,
+            floor,
+            message,
+        }
+    }
+
+    fn push_settings(&self) -> PushSettings {
+        PushSettings {
+            stop_condition: Acknowledgement::Strong,
+            retry_schedule: self.settings.retry_schedule.clone(),
+            priority: Priority::Normal,
+            max_attempts: None,
+            deadline: None,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::{
+    time::SleepSchedule,
+    unicast::{PushSettings, SenderSettings},
+};
+
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct ReliableSenderSettings {
+    pub sender_settings: SenderSettings,
+    pub retry_schedule: Arc<dyn SleepSchedule>,
+}
+
+impl Default for ReliableSenderSettings {
+    fn default() -> Self {
+        ReliableSenderSettings {
+            sender_settings: Default::default(),
+            retry_schedule: PushSettings::default().retry_schedule,
+        }
+    }
+}
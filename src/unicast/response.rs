@@ -3,6 +3,7 @@ use crate::unicast::Acknowledgement;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(in crate::unicast) enum Response {
-    Acknowledgement(u32, Acknowledgement),
+pub(in crate::unicast) enum Response<Reply> {
+    Acknowledgement(u32, Acknowledgement, Option<Reply>),
+    Credit(u32),
 }
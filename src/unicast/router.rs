@@ -0,0 +1,131 @@
+use crate::{
+    crypto::Identity,
+    net::Listener,
+    sync::fuse::Fuse,
+    unicast::{
+        Acknowledgement, Acknowledger, Envelope, Receiver, Rejection, RouterSettings,
+        TaggedMessage, TypedReceiver,
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use parking_lot::Mutex;
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
+
+use tokio::sync::mpsc::{self, error::TrySendError, Sender};
+
+type PayloadInlet = Sender<(Identity, Vec<u8>, Acknowledger<Rejection>)>;
+
+pub struct Router {
+    database: Arc<Mutex<Database>>,
+    settings: RouterSettings,
+    fuse: Arc<Fuse>,
+}
+
+pub(in crate::unicast) struct Database {
+    pub inlets: HashMap<String, PayloadInlet>,
+}
+
+#[derive(Doom)]
+pub enum RouterError {
+    #[doom(description("Tag already registered: \"{}\"", tag))]
+    AlreadyRegistered { tag: String },
+}
+
+impl Router {
+    pub fn new<L>(listener: L, settings: RouterSettings) -> Self
+    where
+        L: Listener,
+    {
+        let database = Arc::new(Mutex::new(Database {
+            inlets: HashMap::new(),
+        }));
+
+        let fuse = Arc::new(Fuse::new());
+
+        {
+            let receiver = Receiver::new(listener, settings.receiver_settings.clone());
+            let database = database.clone();
+
+            fuse.spawn(async move {
+                Router::route(receiver, database).await;
+            });
+        }
+
+        Router {
+            database,
+            settings,
+            fuse,
+        }
+    }
+
+    pub fn register<Message>(&self) -> TypedReceiver<Message>
+    where
+        Message: TaggedMessage,
+    {
+        match self.try_register() {
+            Ok(receiver) => receiver,
+            Err(_) => panic!("called `register` twice for the same `TaggedMessage`"),
+        }
+    }
+
+    pub fn try_register<Message>(&self) -> Result<TypedReceiver<Message>, Top<RouterError>>
+    where
+        Message: TaggedMessage,
+    {
+        let (inlet, outlet) = mpsc::channel(self.settings.channel_capacity);
+
+        match self.database.lock().inlets.entry(Message::TAG.to_string()) {
+            Entry::Vacant(entry) => {
+                entry.insert(inlet);
+            }
+            Entry::Occupied(_) => {
+                return RouterError::AlreadyRegistered {
+                    tag: Message::TAG.to_string(),
+                }
+                .fail()
+                .spot(here!());
+            }
+        }
+
+        Ok(TypedReceiver::new(
+            outlet,
+            self.database.clone(),
+            self.fuse.clone(),
+        ))
+    }
+
+    async fn route(mut receiver: Receiver<Envelope, Rejection>, database: Arc<Mutex<Database>>) {
+        loop {
+            let (remote, envelope, acknowledger) = receiver.receive().await;
+
+            let inlet = database.lock().inlets.get(&envelope.tag).cloned();
+
+            let acknowledger = match inlet {
+                Some(inlet) => {
+                    match inlet.try_send((remote, envelope.payload, acknowledger)) {
+                        Ok(()) => continue,
+                        // Waiting for room would let a single slow `TypedReceiver`
+                        // stall every other tag: the message is rejected instead,
+                        // and the remote is left to retry it later
+                        Err(TrySendError::Full((_, _, acknowledger))) => {
+                            acknowledger.reply_with(Acknowledgement::Weak, Rejection::Congested);
+                            continue;
+                        }
+                        // If the `TypedReceiver` was dropped after `inlet` was
+                        // retrieved, `envelope.tag` is no longer registered
+                        Err(TrySendError::Closed((_, _, acknowledger))) => acknowledger,
+                    }
+                }
+                None => acknowledger,
+            };
+
+            acknowledger.reply_with(Acknowledgement::Weak, Rejection::UnknownTag);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::unicast::ReceiverSettings;
+
+#[derive(Debug, Clone)]
+pub struct RouterSettings {
+    pub receiver_settings: ReceiverSettings,
+    pub channel_capacity: usize,
+}
+
+impl Default for RouterSettings {
+    fn default() -> Self {
+        RouterSettings {
+            receiver_settings: ReceiverSettings::default(),
+            channel_capacity: 1024,
+        }
+    }
+}
@@ -2,9 +2,11 @@ use crate::{
     crypto::Identity,
     net::Connector,
     sync::fuse::{Fuse, Relay},
+    time,
     unicast::{
         Acknowledgement, Caster, CasterError, CasterSettings, CasterTerminated,
-        Message as UnicastMessage, PushSettings, Request, SenderSettings,
+        Message as UnicastMessage, Priority, PushHandle, PushOutcome, PushSettings,
+        PushTermination, PushTracker, Request, SenderSettings,
     },
 };
 
@@ -18,36 +20,42 @@ use std::{
     time::Instant,
 };
 
-use tokio::{sync::oneshot::Receiver, task::JoinHandle, time};
+use tokio::{sync::oneshot::Receiver, task::JoinHandle};
 
-type AcknowledgementOutlet = Receiver<Result<Acknowledgement, Top<CasterError>>>;
+type AcknowledgementOutlet<Reply> =
+    Receiver<Result<(Acknowledgement, Option<Reply>), Top<CasterError>>>;
 
-#[derive(Clone)]
-pub struct Sender<Message: UnicastMessage> {
+type SendHandle<Reply> =
+    JoinHandle<Option<Result<(Acknowledgement, Option<Reply>), Top<SenderError>>>>;
+
+pub struct Sender<Message: UnicastMessage, Reply: UnicastMessage = ()> {
     connector: Arc<dyn Connector>,
-    database: Arc<Mutex<Database<Message>>>,
+    database: Arc<Mutex<Database<Message, Reply>>>,
     settings: SenderSettings,
     _fuse: Arc<Fuse>,
 }
 
-struct Database<Message: UnicastMessage> {
-    links: HashMap<Identity, Link<Message>>,
+struct Database<Message: UnicastMessage, Reply: UnicastMessage> {
+    links: HashMap<Identity, Link<Message, Reply>>,
 }
 
-struct Link<Message: UnicastMessage> {
-    caster: Caster<Message>,
+struct Link<Message: UnicastMessage, Reply: UnicastMessage> {
+    caster: Caster<Message, Reply>,
     last_message: Instant,
 }
 
 #[derive(Doom)]
 pub enum SenderError {
+    #[doom(description("Deadline exceeded while waiting for credit or acknowledgement"))]
+    DeadlineExceeded,
     #[doom(description("Failed to `send` message"))]
     SendFailed,
 }
 
-impl<Message> Sender<Message>
+impl<Message, Reply> Sender<Message, Reply>
 where
     Message: UnicastMessage,
+    Reply: UnicastMessage,
 {
     pub fn new<C>(connector: C, settings: SenderSettings) -> Self
     where
@@ -82,38 +90,63 @@ where
         &self,
         remote: Identity,
         message: Message,
-    ) -> Result<Acknowledgement, Top<SenderError>> {
-        self.post(remote, Request::Message(message))
+    ) -> Result<(Acknowledgement, Option<Reply>), Top<SenderError>> {
+        self.send_with_priority(remote, message, Priority::Normal)
             .await
-            .unwrap()
-            .pot(SenderError::SendFailed, here!())
     }
 
-    pub fn run_send(
+    pub fn run_send(&self, remote: Identity, message: Message, relay: Relay) -> SendHandle<Reply> {
+        self.run_send_with_priority(remote, message, Priority::Normal, relay)
+    }
+
+    pub fn spawn_send(&self, remote: Identity, message: Message, fuse: &Fuse) -> SendHandle<Reply> {
+        self.run_send(remote, message, fuse.relay())
+    }
+
+    pub async fn send_with_priority(
         &self,
         remote: Identity,
         message: Message,
-        relay: Relay,
-    ) -> JoinHandle<Option<Result<Acknowledgement, Top<SenderError>>>> {
-        let post = self.post(remote, Request::Message(message));
+        priority: Priority,
+    ) -> Result<(Acknowledgement, Option<Reply>), Top<SenderError>> {
+        let delivery = self.deliver(remote, message, priority);
+
+        time::optional_timeout(self.settings.send_deadline, delivery)
+            .await
+            .pot(SenderError::DeadlineExceeded, here!())?
+    }
 
-        relay.run(async move { post.await.unwrap().pot(SenderError::SendFailed, here!()) })
+    pub fn run_send_with_priority(
+        &self,
+        remote: Identity,
+        message: Message,
+        priority: Priority,
+        relay: Relay,
+    ) -> SendHandle<Reply> {
+        let sender = self.clone();
+        relay.run(async move { sender.send_with_priority(remote, message, priority).await })
     }
 
-    pub fn spawn_send(
+    pub fn spawn_send_with_priority(
         &self,
         remote: Identity,
         message: Message,
+        priority: Priority,
         fuse: &Fuse,
-    ) -> JoinHandle<Option<Result<Acknowledgement, Top<SenderError>>>> {
-        self.run_send(remote, message, fuse.relay())
+    ) -> SendHandle<Reply> {
+        self.run_send_with_priority(remote, message, priority, fuse.relay())
     }
 
-    pub async fn push(&self, remote: Identity, message: Message, settings: PushSettings)
+    pub async fn push(
+        &self,
+        remote: Identity,
+        message: Message,
+        settings: PushSettings,
+    ) -> PushOutcome
     where
         Message: Clone,
     {
-        self.drive(remote, message, None, settings).await;
+        self.drive(remote, message, None, settings).await
     }
 
     pub fn run_push(
@@ -122,7 +155,7 @@ where
         message: Message,
         settings: PushSettings,
         relay: Relay,
-    ) -> JoinHandle<Option<()>>
+    ) -> JoinHandle<Option<PushOutcome>>
     where
         Message: Clone,
     {
@@ -136,23 +169,36 @@ where
         message: Message,
         settings: PushSettings,
         fuse: &Fuse,
-    ) -> JoinHandle<Option<()>>
+    ) -> JoinHandle<Option<PushOutcome>>
     where
         Message: Clone,
     {
         self.run_push(remote, message, settings, fuse.relay())
     }
 
+    pub fn start_push(
+        &self,
+        remote: Identity,
+        message: Message,
+        settings: PushSettings,
+    ) -> PushHandle
+    where
+        Message: Clone,
+    {
+        self.start(remote, message, None, settings)
+    }
+
     pub async fn push_brief(
         &self,
         remote: Identity,
         brief: Message,
         expanded: Message,
         settings: PushSettings,
-    ) where
+    ) -> PushOutcome
+    where
         Message: Clone,
     {
-        self.drive(remote, brief, Some(expanded), settings).await;
+        self.drive(remote, brief, Some(expanded), settings).await
     }
 
     pub fn run_push_brief(
@@ -162,7 +208,7 @@ where
         expanded: Message,
         settings: PushSettings,
         relay: Relay,
-    ) -> JoinHandle<Option<()>>
+    ) -> JoinHandle<Option<PushOutcome>>
     where
         Message: Clone,
     {
@@ -177,28 +223,133 @@ where
         expanded: Message,
         settings: PushSettings,
         fuse: &Fuse,
-    ) -> JoinHandle<Option<()>>
+    ) -> JoinHandle<Option<PushOutcome>>
     where
         Message: Clone,
     {
         self.run_push_brief(remote, brief, expanded, settings, fuse.relay())
     }
 
+    pub fn start_push_brief(
+        &self,
+        remote: Identity,
+        brief: Message,
+        expanded: Message,
+        settings: PushSettings,
+    ) -> PushHandle
+    where
+        Message: Clone,
+    {
+        self.start(remote, brief, Some(expanded), settings)
+    }
+
     pub async fn drive(
+        &self,
+        remote: Identity,
+        message: Message,
+        fallback: Option<Message>,
+        settings: PushSettings,
+    ) -> PushOutcome
+    where
+        Message: Clone,
+    {
+        self.track(remote, message, fallback, settings, &PushTracker::new())
+            .await
+    }
+
+    fn start(
+        &self,
+        remote: Identity,
+        message: Message,
+        fallback: Option<Message>,
+        settings: PushSettings,
+    ) -> PushHandle
+    where
+        Message: Clone,
+    {
+        let (tracker, acknowledgement_outlet) = PushTracker::observed();
+        let tracker = Arc::new(tracker);
+
+        let fuse = Fuse::new();
+
+        let outcome = {
+            let sender = self.clone();
+            let tracker = tracker.clone();
+
+            fuse.spawn(async move {
+                sender
+                    .track(remote, message, fallback, settings, &tracker)
+                    .await
+            })
+        };
+
+        PushHandle::new(tracker, acknowledgement_outlet, outcome, fuse)
+    }
+
+    async fn track(
+        &self,
+        remote: Identity,
+        message: Message,
+        fallback: Option<Message>,
+        settings: PushSettings,
+        tracker: &PushTracker,
+    ) -> PushOutcome
+    where
+        Message: Clone,
+    {
+        let attempts = self.attempt(remote, message, fallback, &settings, tracker);
+
+        let termination = tokio::select! {
+            termination = time::optional_timeout(settings.deadline, attempts) => {
+                termination.unwrap_or(PushTermination::DeadlineExceeded)
+            }
+            _ = tracker.cancelled() => PushTermination::Cancelled,
+        };
+
+        tracker.terminate();
+
+        let progress = tracker.progress();
+
+        PushOutcome {
+            termination,
+            acknowledgement: progress.acknowledgement,
+            attempts: progress.attempts,
+            elapsed: progress.elapsed,
+        }
+    }
+
+    async fn attempt(
         &self,
         remote: Identity,
         mut message: Message,
         mut fallback: Option<Message>,
-        settings: PushSettings,
-    ) where
+        settings: &PushSettings,
+        tracker: &PushTracker,
+    ) -> PushTermination
+    where
         Message: Clone,
     {
+        let exhausted = |attempts| matches!(settings.max_attempts, Some(max_attempts) if attempts >= max_attempts);
+
         let mut sleep_agent = settings.retry_schedule.agent();
+        let mut attempts = 0;
 
         loop {
-            if let Ok(acknowledgement) = self.send(remote, message.clone()).await {
+            if exhausted(attempts) {
+                return PushTermination::AttemptsExhausted;
+            }
+
+            attempts += 1;
+            tracker.record_attempt();
+
+            if let Ok((acknowledgement, _)) = self
+                .send_with_priority(remote, message.clone(), settings.priority)
+                .await
+            {
+                tracker.record_acknowledgement(acknowledgement);
+
                 if acknowledgement >= settings.stop_condition {
-                    break;
+                    return PushTermination::Acknowledged;
                 }
 
                 if acknowledgement == Acknowledgement::Expand {
@@ -209,44 +360,92 @@ where
                 }
             }
 
+            // Checked here as well, in order not to sleep after the last attempt
+            if exhausted(attempts) {
+                return PushTermination::AttemptsExhausted;
+            }
+
             sleep_agent.step().await;
         }
     }
 
-    fn post(&self, remote: Identity, mut request: Request<Message>) -> AcknowledgementOutlet {
-        let mut database = self.database.lock();
+    async fn deliver(
+        &self,
+        remote: Identity,
+        message: Message,
+        priority: Priority,
+    ) -> Result<(Acknowledgement, Option<Reply>), Top<SenderError>> {
+        let acknowledgement_outlet = self.post(remote, Request::Message(message), priority).await;
+
+        acknowledgement_outlet
+            .await
+            .map_err(|_| SenderError::SendFailed.into_top())
+            .spot(here!())?
+            .pot(SenderError::SendFailed, here!())
+    }
 
+    async fn post(
+        &self,
+        remote: Identity,
+        mut request: Request<Message>,
+        priority: Priority,
+    ) -> AcknowledgementOutlet<Reply> {
         loop {
-            let link = match database.links.entry(remote) {
-                Entry::Occupied(entry) => entry.into_mut(),
-                Entry::Vacant(entry) => entry.insert(Link {
-                    caster: Caster::new(
-                        self.connector.clone(),
-                        remote,
-                        CasterSettings::from_sender_settings(&self.settings),
-                    ),
-                    last_message: Instant::now(),
-                }),
+            let inlet = {
+                let mut database = self.database.lock();
+
+                let link = match database.links.entry(remote) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(Link {
+                        caster: Caster::new(
+                            self.connector.clone(),
+                            remote,
+                            CasterSettings::from_sender_settings(&self.settings),
+                        ),
+                        last_message: Instant::now(),
+                    }),
+                };
+
+                match link.caster.inlet() {
+                    Some(inlet) => inlet,
+                    None => {
+                        database.links.remove(&remote);
+                        continue;
+                    }
+                }
             };
 
-            request = match link.caster.post(request) {
+            // `database` is unlocked while waiting for room in the request queue:
+            // a congested link must not stall the other links
+            request = match inlet.post(request, priority).await {
                 Ok(outlet) => break outlet,
-                Err(CasterTerminated(request)) => {
-                    database.links.remove(&remote);
-                    request
-                }
+                Err(CasterTerminated(request)) => request,
             };
         }
     }
 
-    async fn keep_alive(database: Arc<Mutex<Database<Message>>>, settings: SenderSettings) {
+    async fn keep_alive(database: Arc<Mutex<Database<Message, Reply>>>, settings: SenderSettings) {
         loop {
             database.lock().links.retain(|_, link| {
-                (link.last_message.elapsed() <= settings.link_timeout)
-                    && link.caster.post(Request::KeepAlive).is_ok()
+                (link.last_message.elapsed() <= settings.link_timeout) && link.caster.keep_alive()
             });
 
-            time::sleep(settings.keepalive_interval).await;
+            tokio::time::sleep(settings.keepalive_interval).await;
+        }
+    }
+}
+
+impl<Message, Reply> Clone for Sender<Message, Reply>
+where
+    Message: UnicastMessage,
+    Reply: UnicastMessage,
+{
+    fn clone(&self) -> Self {
+        Sender {
+            connector: self.connector.clone(),
+            database: self.database.clone(),
+            settings: self.settings.clone(),
+            _fuse: self._fuse.clone(),
         }
     }
 }
@@ -5,6 +5,10 @@ pub struct SenderSettings {
     pub request_channel_capacity: usize,
     pub link_timeout: Duration,
     pub keepalive_interval: Duration,
+    pub send_deadline: Option<Duration>,
+    pub max_batch_bytes: usize,
+    pub max_linger: Duration,
+    pub bulk_share: u32,
 }
 
 impl Default for SenderSettings {
@@ -13,6 +17,10 @@ impl Default for SenderSettings {
             request_channel_capacity: 32768,
             link_timeout: Duration::from_secs(1800),
             keepalive_interval: Duration::from_secs(10),
+            send_deadline: None,
+            max_batch_bytes: 65536,
+            max_linger: Duration::from_millis(0),
+            bulk_share: 8,
         }
     }
 }
@@ -0,0 +1,7 @@
+use crate::unicast::Message as UnicastMessage;
+
+/// A `Message` that can be sent through a `TypedSender` and routed by a
+/// `Router`, which dispatches it to the `TypedReceiver` registered for `TAG`.
+pub trait TaggedMessage: UnicastMessage {
+    const TAG: &'static str;
+}
@@ -0,0 +1,81 @@
+use crate::{
+    net::test::fuzz,
+    unicast::{Request, Response},
+};
+
+/// Feeds `data` to the deserialization of request batches, as
+/// `Receiver`s do upon `receive`ing.
+pub fn request(data: &[u8]) {
+    let _ = fuzz::decode::<Vec<Request<Vec<u8>>>>(data);
+}
+
+/// Feeds `data` to the deserialization of `Response`s, as
+/// `Sender`s do upon `receive`ing.
+pub fn response(data: &[u8]) {
+    let _ = fuzz::decode::<Response<Vec<u8>>>(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::unicast::Acknowledgement;
+
+    use proptest::{collection, prelude::*};
+
+    fn batches() -> impl Strategy<Value = Vec<Request<Vec<u8>>>> {
+        let request = prop_oneof![
+            collection::vec(any::<u8>(), 0..64).prop_map(Request::Message),
+            Just(()).prop_map(|_| Request::KeepAlive),
+        ];
+
+        collection::vec(request, 0..16)
+    }
+
+    fn responses() -> impl Strategy<Value = Response<Vec<u8>>> {
+        let acknowledgement = prop_oneof![
+            Just(Acknowledgement::Weak),
+            Just(Acknowledgement::Expand),
+            Just(Acknowledgement::Strong),
+        ];
+
+        prop_oneof![
+            (any::<u32>(), acknowledgement, any::<Option<Vec<u8>>>()).prop_map(
+                |(sequence, acknowledgement, reply)| {
+                    Response::Acknowledgement(sequence, acknowledgement, reply)
+                }
+            ),
+            any::<u32>().prop_map(Response::Credit),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn request_arbitrary(data in collection::vec(any::<u8>(), 0..1024)) {
+            request(&data);
+        }
+
+        #[test]
+        fn response_arbitrary(data in collection::vec(any::<u8>(), 0..1024)) {
+            response(&data);
+        }
+
+        #[test]
+        fn request_roundtrip(batch in batches()) {
+            let encoding = bincode::serialize(&batch).unwrap();
+            let decoded = fuzz::decode::<Vec<Request<Vec<u8>>>>(&encoding).unwrap();
+
+            prop_assert_eq!(bincode::serialize(&decoded).unwrap(), encoding.clone());
+            fuzz::truncations::<Vec<Request<Vec<u8>>>>(&encoding);
+        }
+
+        #[test]
+        fn response_roundtrip(response in responses()) {
+            let encoding = bincode::serialize(&response).unwrap();
+            let decoded = fuzz::decode::<Response<Vec<u8>>>(&encoding).unwrap();
+
+            prop_assert_eq!(bincode::serialize(&decoded).unwrap(), encoding.clone());
+            fuzz::truncations::<Response<Vec<u8>>>(&encoding);
+        }
+    }
+}
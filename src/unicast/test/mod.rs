@@ -3,4 +3,6 @@ mod unicast_system;
 #[cfg(test)]
 mod tests;
 
+pub mod fuzz;
+
 pub use unicast_system::UnicastSystem;
@@ -1,18 +1,30 @@
 mod unicast {
     use crate::{
+        net::test::System as NetSystem,
         time::test::join,
-        unicast::{test::UnicastSystem, Acknowledgement, PushSettings},
+        unicast::{
+            test::UnicastSystem, Acknowledgement, Packet, Priority, PushSettings, PushTermination,
+            Receiver, ReceiverSettings, ReliableReceiver, ReliableReceiverSettings, ReliableSender,
+            Router, RouterSettings, Sender, SenderError, SenderSettings, TaggedMessage,
+            TypedSender, TypedSenderError,
+        },
     };
 
     use futures::stream::{FuturesUnordered, StreamExt};
 
+    use serde::{Deserialize, Serialize};
+
+    use std::time::Duration;
+
+    use tokio::{task::JoinHandle, time};
+
     #[tokio::test]
     async fn constant_one_to_one_strong() {
         let UnicastSystem {
             keys,
             mut senders,
             mut receivers,
-        } = UnicastSystem::<u32>::setup(1).await.into();
+        } = UnicastSystem::<u32>::setup(1).await;
 
         let mut receiver = receivers.remove(0);
         let sender = senders.remove(0);
@@ -24,7 +36,7 @@ mod unicast {
             acknowledger.strong();
         });
 
-        let ack = sender.send(keys[0], 42).await.unwrap();
+        let (ack, _) = sender.send(keys[0], 42).await.unwrap();
         assert_eq!(ack, Acknowledgement::Strong);
 
         join([handle]).await.unwrap();
@@ -38,7 +50,7 @@ mod unicast {
             keys,
             mut senders,
             mut receivers,
-        } = UnicastSystem::<u32>::setup(1).await.into();
+        } = UnicastSystem::<u32>::setup(1).await;
 
         let mut receiver = receivers.remove(0);
         let sender = senders.remove(0);
@@ -53,13 +65,55 @@ mod unicast {
         });
 
         for _ in 0..MESSAGES {
-            let ack = sender.send(keys[0], 42).await.unwrap();
+            let (ack, _) = sender.send(keys[0], 42).await.unwrap();
             assert_eq!(ack, Acknowledgement::Strong);
         }
 
         join([handle]).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn acknowledgement_flood() {
+        const MESSAGES: usize = 512;
+
+        let UnicastSystem {
+            keys,
+            mut senders,
+            mut receivers,
+        } = UnicastSystem::<u32>::setup(1).await;
+
+        let remote = keys[0];
+
+        let mut receiver = receivers.remove(0);
+        let sender = senders.remove(0);
+
+        let handles = (0..MESSAGES)
+            .map(|_| {
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    let (ack, _) = sender.send(remote, 42).await.unwrap();
+                    assert_eq!(ack, Acknowledgement::Strong);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Acknowledging all messages at once overflows `response_channel_capacity`:
+        // no acknowledgement is lost
+        let mut acknowledgers = Vec::new();
+
+        for _ in 0..MESSAGES {
+            let (_, _, acknowledger) = receiver.receive().await;
+            acknowledgers.push(acknowledger);
+        }
+
+        for acknowledger in acknowledgers {
+            acknowledger.strong();
+        }
+
+        join(handles).await.unwrap();
+    }
+
     #[tokio::test]
     async fn constant_one_to_many_strong() {
         const PEERS: usize = 8;
@@ -68,7 +122,7 @@ mod unicast {
             keys,
             mut senders,
             receivers,
-        } = UnicastSystem::<u32>::setup(PEERS).await.into();
+        } = UnicastSystem::<u32>::setup(PEERS).await;
 
         let sender = senders.remove(0);
 
@@ -88,9 +142,9 @@ mod unicast {
             .iter()
             .map(move |key| {
                 let sender = sender.clone();
-                let key = key.clone();
+                let key = *key;
 
-                async move { sender.send(key, 42).await.unwrap() }
+                async move { sender.send(key, 42).await.unwrap().0 }
             })
             .collect::<FuturesUnordered<_>>()
             .collect::<Vec<_>>()
@@ -111,7 +165,7 @@ mod unicast {
             keys,
             senders,
             receivers,
-        } = UnicastSystem::<u32>::setup(PEERS).await.into();
+        } = UnicastSystem::<u32>::setup(PEERS).await;
 
         let handles = receivers
             .into_iter()
@@ -129,15 +183,14 @@ mod unicast {
 
         let acknowledgements = senders
             .iter()
-            .map(|sender| {
+            .flat_map(|sender| {
                 keys.iter().map(move |key| {
                     let sender = sender.clone();
-                    let key = key.clone();
+                    let key = *key;
 
-                    async move { sender.send(key, 42).await.unwrap() }
+                    async move { sender.send(key, 42).await.unwrap().0 }
                 })
             })
-            .flatten()
             .collect::<FuturesUnordered<_>>()
             .collect::<Vec<_>>()
             .await;
@@ -157,7 +210,7 @@ mod unicast {
             keys,
             mut senders,
             mut receivers,
-        } = UnicastSystem::<u32>::setup(1).await.into();
+        } = UnicastSystem::<u32>::setup(1).await;
 
         let mut receiver = receivers.remove(0);
         let sender = senders.remove(0);
@@ -173,10 +226,13 @@ mod unicast {
             acknowledger.strong();
         });
 
-        sender
+        let outcome = sender
             .push(keys[0], 42, PushSettings::strong_constant())
             .await;
 
+        assert!(outcome.acknowledged());
+        assert_eq!(outcome.acknowledgement, Some(Acknowledgement::Strong));
+
         join([handle]).await.unwrap();
     }
 
@@ -186,7 +242,7 @@ mod unicast {
             keys,
             mut senders,
             mut receivers,
-        } = UnicastSystem::<u32>::setup(1).await.into();
+        } = UnicastSystem::<u32>::setup(1).await;
 
         let mut receiver = receivers.remove(0);
         let sender = senders.remove(0);
@@ -211,7 +267,7 @@ mod unicast {
             keys,
             mut senders,
             mut receivers,
-        } = UnicastSystem::<u32>::setup(1).await.into();
+        } = UnicastSystem::<u32>::setup(1).await;
 
         let mut receiver = receivers.remove(0);
         let sender = senders.remove(0);
@@ -234,4 +290,918 @@ mod unicast {
 
         join([handle]).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn reliable_in_order() {
+        const MESSAGES: u32 = 100;
+
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = ReliableSender::<u32>::new(connectors.remove(0), Default::default());
+        let mut receiver = ReliableReceiver::<u32>::new(listeners.remove(0), Default::default());
+
+        // Sequence numbers are assigned upon calling `send`: messages
+        // must be delivered in order, regardless of how tasks are scheduled
+        let handles = (0..MESSAGES)
+            .map(|message| {
+                let send = sender.send(keys[0], message);
+
+                tokio::spawn(async move {
+                    let outcome = send.await;
+                    assert_eq!(outcome.termination, PushTermination::Acknowledged);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for expected in 0..MESSAGES {
+            let (remote, message) = receiver.receive().await;
+
+            assert_eq!(remote, keys[0]);
+            assert_eq!(message, expected);
+        }
+
+        join(handles).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reliable_dropped() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = ReliableSender::<u32>::new(connectors.remove(0), Default::default());
+        let mut receiver = ReliableReceiver::<u32>::new(listeners.remove(0), Default::default());
+
+        // Dropping the future returned by `send` neither cancels the delivery
+        // of its message, nor stalls the messages that follow
+        drop(sender.send(keys[0], 0));
+
+        let outcome = sender.send(keys[0], 1).await;
+        assert_eq!(outcome.termination, PushTermination::Acknowledged);
+
+        for expected in 0..2 {
+            assert_eq!(receiver.receive().await.1, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn reliable_deduplication() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<Packet<u32>>::new(connectors.remove(0), Default::default());
+
+        let mut receiver = ReliableReceiver::<u32>::new(
+            listeners.remove(0),
+            ReliableReceiverSettings {
+                window: 4,
+                ..Default::default()
+            },
+        );
+
+        let packet = |sequence| Packet {
+            session: 7,
+            sequence,
+            floor: 0,
+            message: sequence as u32,
+        };
+
+        // A message buffered behind a gap is only acknowledged once delivered
+        let mut buffered = {
+            let sender = sender.clone();
+            let key = keys[0];
+
+            tokio::spawn(async move { sender.send(key, packet(1)).await.unwrap().0 })
+        };
+
+        assert!(time::timeout(Duration::from_millis(100), &mut buffered)
+            .await
+            .is_err());
+
+        for (sequence, expected) in [
+            (0, Acknowledgement::Strong),
+            (0, Acknowledgement::Strong),
+            (1, Acknowledgement::Strong),
+            (6, Acknowledgement::Weak),
+            (2, Acknowledgement::Strong),
+        ]
+        .iter()
+        {
+            let (acknowledgement, _) = sender.send(keys[0], packet(*sequence)).await.unwrap();
+            assert_eq!(acknowledgement, *expected);
+        }
+
+        assert_eq!(buffered.await.unwrap(), Acknowledgement::Strong);
+
+        for expected in 0..3 {
+            assert_eq!(receiver.receive().await.1, expected);
+        }
+
+        assert!(
+            time::timeout(Duration::from_millis(100), receiver.receive())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn reliable_session_eviction() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<Packet<u32>>::new(connectors.remove(0), Default::default());
+
+        let mut receiver = ReliableReceiver::<u32>::new(
+            listeners.remove(0),
+            ReliableReceiverSettings {
+                max_sessions: 1,
+                ..Default::default()
+            },
+        );
+
+        // Session 8 evicts session 7, which then resumes from `floor`
+        for (session, sequence, floor, message) in
+            [(7, 0, 0, 0), (7, 1, 0, 1), (8, 0, 0, 2), (7, 2, 2, 3)].iter()
+        {
+            let packet = Packet {
+                session: *session,
+                sequence: *sequence,
+                floor: *floor,
+                message: *message,
+            };
+
+            let (acknowledgement, _) = sender.send(keys[0], packet).await.unwrap();
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+        }
+
+        for expected in 0..4 {
+            assert_eq!(receiver.receive().await.1, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn reliable_eviction_duplicate() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<Packet<u32>>::new(connectors.remove(0), Default::default());
+
+        let mut receiver = ReliableReceiver::<u32>::new(
+            listeners.remove(0),
+            ReliableReceiverSettings {
+                max_sessions: 1,
+                ..Default::default()
+            },
+        );
+
+        // Session 7's first message is delivered, but (as if its acknowledgement
+        // was lost) re-sent after session 8 evicted session 7: it is not
+        // delivered a second time
+        for (session, sequence, floor, message) in
+            [(7, 0, 0, 0), (8, 0, 0, 1), (7, 0, 0, 0), (7, 1, 0, 2)].iter()
+        {
+            let packet = Packet {
+                session: *session,
+                sequence: *sequence,
+                floor: *floor,
+                message: *message,
+            };
+
+            let (acknowledgement, _) = sender.send(keys[0], packet).await.unwrap();
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+        }
+
+        for expected in 0..3 {
+            assert_eq!(receiver.receive().await.1, expected);
+        }
+
+        assert!(
+            time::timeout(Duration::from_millis(100), receiver.receive())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn reliable_watermark_cycling() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<Packet<u32>>::new(connectors.remove(0), Default::default());
+
+        let mut receiver = ReliableReceiver::<u32>::new(
+            listeners.remove(0),
+            ReliableReceiverSettings {
+                max_sessions: 2,
+                ..Default::default()
+            },
+        );
+
+        let packet = |session: u64| Packet {
+            session,
+            sequence: 0,
+            floor: 0,
+            message: session as u32,
+        };
+
+        // Sessions 0 to 9 each deliver one message, evicting one another:
+        // only the watermarks of sessions 6 and 7 are kept (8 and 9 are live)
+        for session in 0..10 {
+            let (acknowledgement, _) = sender.send(keys[0], packet(session)).await.unwrap();
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+
+            assert_eq!(receiver.receive().await.1, session as u32);
+        }
+
+        // Session 7 resumes from its watermark, while session 0's watermark
+        // was dropped: its first message is delivered again
+        for session in [7, 0].iter() {
+            let (acknowledgement, _) = sender.send(keys[0], packet(*session)).await.unwrap();
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+        }
+
+        assert_eq!(receiver.receive().await.1, 0);
+
+        assert!(
+            time::timeout(Duration::from_millis(100), receiver.receive())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn reliable_eviction_buffered() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<Packet<u32>>::new(connectors.remove(0), Default::default());
+
+        let mut receiver = ReliableReceiver::<u32>::new(
+            listeners.remove(0),
+            ReliableReceiverSettings {
+                max_sessions: 1,
+                ..Default::default()
+            },
+        );
+
+        let packet = |session, sequence, message| Packet {
+            session,
+            sequence,
+            floor: 0,
+            message,
+        };
+
+        // Session 7's second message is buffered behind a gap
+        let buffered = {
+            let sender = sender.clone();
+            let key = keys[0];
+
+            tokio::spawn(async move { sender.send(key, packet(7, 1, 2)).await.unwrap().0 })
+        };
+
+        time::sleep(Duration::from_millis(100)).await;
+
+        // Session 8 evicts session 7: the buffered message is dropped,
+        // and acknowledged `Weak` so that it is sent again
+        let (acknowledgement, _) = sender.send(keys[0], packet(8, 0, 0)).await.unwrap();
+        assert_eq!(acknowledgement, Acknowledgement::Strong);
+
+        assert_eq!(buffered.await.unwrap(), Acknowledgement::Weak);
+
+        for (sequence, message) in [(0, 1), (1, 2)].iter() {
+            let (acknowledgement, _) = sender
+                .send(keys[0], packet(7, *sequence, *message))
+                .await
+                .unwrap();
+
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+        }
+
+        for expected in 0..3 {
+            assert_eq!(receiver.receive().await.1, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn reply() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<u32, String>::new(connectors.remove(0), Default::default());
+        let mut receiver = Receiver::<u32, String>::new(listeners.remove(0), Default::default());
+
+        let handle = tokio::spawn(async move {
+            let (_, message, acknowledger) = receiver.receive().await;
+            assert_eq!(message, 42);
+            acknowledger.reply(String::from("Forty-two"));
+
+            let (_, message, acknowledger) = receiver.receive().await;
+            assert_eq!(message, 43);
+            acknowledger.reply_with(Acknowledgement::Weak, String::from("Not forty-two"));
+
+            let (_, message, acknowledger) = receiver.receive().await;
+            assert_eq!(message, 44);
+            acknowledger.expand();
+        });
+
+        assert_eq!(
+            sender.send(keys[0], 42).await.unwrap(),
+            (Acknowledgement::Strong, Some(String::from("Forty-two")))
+        );
+
+        assert_eq!(
+            sender.send(keys[0], 43).await.unwrap(),
+            (Acknowledgement::Weak, Some(String::from("Not forty-two")))
+        );
+
+        assert_eq!(
+            sender.send(keys[0], 44).await.unwrap(),
+            (Acknowledgement::Expand, None)
+        );
+
+        join([handle]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn credit_backpressure() {
+        const MESSAGES: u32 = 64;
+
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<u32>::new(connectors.remove(0), Default::default());
+
+        // Without credit, messages in excess of `message_channel_capacity`
+        // would be dropped while the receiver is not consuming
+        let mut receiver = Receiver::<u32>::new(
+            listeners.remove(0),
+            ReceiverSettings {
+                message_channel_capacity: 4,
+                credit_window: 4,
+                ..Default::default()
+            },
+        );
+
+        let handle = tokio::spawn(async move {
+            time::sleep(Duration::from_millis(100)).await;
+
+            let mut messages = Vec::new();
+
+            for _ in 0..MESSAGES {
+                let (_, message, acknowledger) = receiver.receive().await;
+
+                messages.push(message);
+                acknowledger.strong();
+            }
+
+            messages.sort();
+            assert_eq!(messages, (0..MESSAGES).collect::<Vec<_>>());
+        });
+
+        let acknowledgements = (0..MESSAGES)
+            .map(|message| {
+                let sender = sender.clone();
+                let key = keys[0];
+
+                async move { sender.send(key, message).await.unwrap().0 }
+            })
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+
+        for acknowledgement in acknowledgements {
+            assert_eq!(acknowledgement, Acknowledgement::Strong);
+        }
+
+        join([handle]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_deadline() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<u32>::new(
+            connectors.remove(0),
+            SenderSettings {
+                send_deadline: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+        );
+
+        // The receiver never consumes, so its only credit is never granted back
+        let _receiver = Receiver::<u32>::new(
+            listeners.remove(0),
+            ReceiverSettings {
+                credit_window: 1,
+                ..Default::default()
+            },
+        );
+
+        for message in 0..2 {
+            match sender.send(keys[0], message).await.unwrap_err().top() {
+                SenderError::DeadlineExceeded => (),
+                error => panic!("unexpected error upon sending without credit: {}", error),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn batching() {
+        const MESSAGES: u32 = 256;
+
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        // Small batches and a long linger force requests to be split
+        // across several batches, some of which are flushed by the linger
+        let sender = Sender::<u32, u32>::new(
+            connectors.remove(0),
+            SenderSettings {
+                max_batch_bytes: 64,
+                max_linger: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+
+        let mut receiver = Receiver::<u32, u32>::new(listeners.remove(0), Default::default());
+
+        let handle = tokio::spawn(async move {
+            for _ in 0..MESSAGES {
+                let (_, message, acknowledger) = receiver.receive().await;
+                acknowledger.reply(message);
+            }
+
+            // Dropping `receiver` here could cut off replies still being batched
+            receiver
+        });
+
+        // Replies are routed by sequence number: each reply must match its message
+        (0..MESSAGES)
+            .map(|message| {
+                let sender = sender.clone();
+                let key = keys[0];
+
+                async move {
+                    assert_eq!(
+                        sender.send(key, message).await.unwrap(),
+                        (Acknowledgement::Strong, Some(message))
+                    );
+                }
+            })
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+
+        let _receiver = handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn priority_under_bulk_flood() {
+        const BULK_MESSAGES: usize = 512;
+        const BULK_SIZE: usize = 4096;
+        const CREDIT_WINDOW: u32 = 16;
+
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<Vec<u8>>::new(connectors.remove(0), Default::default());
+
+        let mut receiver = Receiver::<Vec<u8>>::new(
+            listeners.remove(0),
+            ReceiverSettings {
+                credit_window: CREDIT_WINDOW,
+                ..Default::default()
+            },
+        );
+
+        // The receiver consumes slowly, so that bulk messages pile up
+        // in the sender's bulk queue. The (empty) control message is
+        // returned along with the number of bulk messages received before it
+        let handle = tokio::spawn(async move {
+            let mut position = None;
+
+            for index in 0..(BULK_MESSAGES + 1) {
+                let (_, message, acknowledger) = receiver.receive().await;
+                acknowledger.strong();
+
+                if message.is_empty() {
+                    position = Some(index);
+                }
+
+                time::sleep(Duration::from_millis(1)).await;
+            }
+
+            position.unwrap()
+        });
+
+        let bulk = (0..BULK_MESSAGES)
+            .map(|_| {
+                let sender = sender.clone();
+                let key = keys[0];
+
+                tokio::spawn(async move {
+                    sender
+                        .send_with_priority(key, vec![0; BULK_SIZE], Priority::Bulk)
+                        .await
+                        .unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+
+        time::sleep(Duration::from_millis(100)).await;
+
+        let start = time::Instant::now();
+
+        let (acknowledgement, _) = sender
+            .send_with_priority(keys[0], Vec::new(), Priority::Control)
+            .await
+            .unwrap();
+
+        let latency = start.elapsed();
+
+        assert_eq!(acknowledgement, Acknowledgement::Strong);
+
+        // Only bulk messages already granted credit can overtake the control message
+        assert!(latency < Duration::from_millis(250));
+
+        join(bulk).await.unwrap();
+
+        let position = handle.await.unwrap();
+        assert!(position <= 2 * CREDIT_WINDOW as usize);
+    }
+
+    #[tokio::test]
+    async fn bulk_under_normal_flood() {
+        const NORMAL_MESSAGES: usize = 512;
+        const CREDIT_WINDOW: u32 = 16;
+        const BULK_SHARE: u32 = 8;
+
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = Sender::<u32>::new(
+            connectors.remove(0),
+            SenderSettings {
+                bulk_share: BULK_SHARE,
+                ..Default::default()
+            },
+        );
+
+        let mut receiver = Receiver::<u32>::new(
+            listeners.remove(0),
+            ReceiverSettings {
+                credit_window: CREDIT_WINDOW,
+                ..Default::default()
+            },
+        );
+
+        // The receiver consumes slowly, so that normal messages pile up in
+        // the sender's normal queue. The bulk message (`u32::MAX`) is returned
+        // along with the number of normal messages received before it
+        let handle = tokio::spawn(async move {
+            let mut position = None;
+
+            for index in 0..(NORMAL_MESSAGES + 1) {
+                let (_, message, acknowledger) = receiver.receive().await;
+                acknowledger.strong();
+
+                if message == u32::MAX {
+                    position = Some(index);
+                }
+
+                time::sleep(Duration::from_millis(1)).await;
+            }
+
+            position.unwrap()
+        });
+
+        let normal = (0..NORMAL_MESSAGES as u32)
+            .map(|message| {
+                let sender = sender.clone();
+                let key = keys[0];
+
+                tokio::spawn(async move {
+                    sender.send(key, message).await.unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+
+        time::sleep(Duration::from_millis(100)).await;
+
+        let (acknowledgement, _) = sender
+            .send_with_priority(keys[0], u32::MAX, Priority::Bulk)
+            .await
+            .unwrap();
+
+        assert_eq!(acknowledgement, Acknowledgement::Strong);
+
+        join(normal).await.unwrap();
+
+        // Under strict priority, the bulk message would be received last
+        let position = handle.await.unwrap();
+        assert!(position <= 2 * CREDIT_WINDOW as usize + BULK_SHARE as usize);
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Ping(u32);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Pong(String);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Unregistered;
+
+    impl TaggedMessage for Ping {
+        const TAG: &'static str = "Ping";
+    }
+
+    impl TaggedMessage for Pong {
+        const TAG: &'static str = "Pong";
+    }
+
+    impl TaggedMessage for Unregistered {
+        const TAG: &'static str = "Unregistered";
+    }
+
+    #[tokio::test]
+    async fn typed_routing() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = TypedSender::new(connectors.remove(0), Default::default());
+        let router = Router::new(listeners.remove(0), Default::default());
+
+        let mut pings = router.register::<Ping>();
+        let mut pongs = router.register::<Pong>();
+
+        assert!(router.try_register::<Ping>().is_err());
+
+        let handle = tokio::spawn(async move {
+            let (_, ping, acknowledger) = pings.receive().await;
+            assert_eq!(ping, Ping(42));
+            acknowledger.strong();
+
+            let (_, pong, acknowledger) = pongs.receive().await;
+            assert_eq!(pong, Pong(String::from("Forty-two")));
+            acknowledger.expand();
+        });
+
+        assert_eq!(
+            sender.send(keys[0], Ping(42)).await.unwrap(),
+            Acknowledgement::Strong
+        );
+
+        assert_eq!(
+            sender
+                .send(keys[0], Pong(String::from("Forty-two")))
+                .await
+                .unwrap(),
+            Acknowledgement::Expand
+        );
+
+        match sender.send(keys[0], Unregistered).await.unwrap_err().top() {
+            TypedSenderError::UnknownTag { tag } => assert_eq!(tag, "Unregistered"),
+            error => panic!("unexpected error upon sending unregistered type: {}", error),
+        }
+
+        join([handle]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn router_congested_tag() {
+        let NetSystem {
+            keys,
+            mut connectors,
+            mut listeners,
+        } = NetSystem::setup(1).await;
+
+        let sender = TypedSender::new(connectors.remove(0), Default::default());
+
+        let router = Router::new(
+            listeners.remove(0),
+            RouterSettings {
+                channel_capacity: 1,
+                ..Default::default()
+            },
+        );
+
+        let mut pings = router.register::<Ping>();
+        let mut pongs = router.register::<Pong>();
+
+        // The first `Ping` fills up the queue of `pings`, which is not yet received from
+        let first = {
+            let sender = sender.clone();
+            let key = keys[0];
+
+            tokio::spawn(async move { sender.send(key, Ping(0)).await.unwrap() })
+        };
+
+        time::sleep(Duration::from_millis(100)).await;
+
+        // The second `Ping` finds the queue full, and is explicitly rejected
+        match sender.send(keys[0], Ping(1)).await.unwrap_err().top() {
+            TypedSenderError::Congested { tag } => assert_eq!(tag, "Ping"),
+            error => panic!("unexpected error upon sending to congested tag: {}", error),
+        }
+
+        // A congested tag does not stall the others
+        let handle = tokio::spawn(async move {
+            let (_, pong, acknowledger) = pongs.receive().await;
+            assert_eq!(pong, Pong(String::from("Forty-two")));
+            acknowledger.strong();
+        });
+
+        assert_eq!(
+            sender
+                .send(keys[0], Pong(String::from("Forty-two")))
+                .await
+                .unwrap(),
+            Acknowledgement::Strong
+        );
+
+        join([handle]).await.unwrap();
+
+        let (_, ping, acknowledger) = pings.receive().await;
+        assert_eq!(ping, Ping(0));
+        acknowledger.strong();
+
+        assert_eq!(first.await.unwrap(), Acknowledgement::Strong);
+    }
+
+    fn weak_receiver(mut receiver: Receiver<u32>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let (_, _, acknowledger) = receiver.receive().await;
+                acknowledger.weak();
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn push_max_attempts() {
+        let UnicastSystem {
+            keys,
+            mut senders,
+            mut receivers,
+        } = UnicastSystem::<u32>::setup(1).await;
+
+        let _handle = weak_receiver(receivers.remove(0));
+
+        let outcome = senders
+            .remove(0)
+            .push(
+                keys[0],
+                42,
+                PushSettings {
+                    max_attempts: Some(3),
+                    ..PushSettings::strong_constant()
+                },
+            )
+            .await;
+
+        assert_eq!(outcome.termination, PushTermination::AttemptsExhausted);
+        assert_eq!(outcome.acknowledgement, Some(Acknowledgement::Weak));
+        assert_eq!(outcome.attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn push_deadline() {
+        let UnicastSystem {
+            keys,
+            mut senders,
+            mut receivers,
+        } = UnicastSystem::<u32>::setup(1).await;
+
+        let _handle = weak_receiver(receivers.remove(0));
+
+        let outcome = senders
+            .remove(0)
+            .push(
+                keys[0],
+                42,
+                PushSettings {
+                    deadline: Some(Duration::from_secs(1)),
+                    ..PushSettings::strong_constant()
+                },
+            )
+            .await;
+
+        assert_eq!(outcome.termination, PushTermination::DeadlineExceeded);
+        assert!(outcome.attempts >= 2);
+        assert!(outcome.elapsed >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn push_cancel() {
+        let UnicastSystem {
+            keys,
+            mut senders,
+            mut receivers,
+        } = UnicastSystem::<u32>::setup(1).await;
+
+        let _handle = weak_receiver(receivers.remove(0));
+
+        let push = senders
+            .remove(0)
+            .start_push(keys[0], 42, PushSettings::strong_constant());
+
+        time::sleep(Duration::from_secs(1)).await;
+
+        let progress = push.progress();
+        assert!(progress.attempts >= 2);
+        assert_eq!(progress.acknowledgement, Some(Acknowledgement::Weak));
+
+        push.cancel();
+        let outcome = push.outcome().await;
+
+        assert_eq!(outcome.termination, PushTermination::Cancelled);
+        assert!(outcome.attempts >= progress.attempts);
+    }
+
+    #[tokio::test]
+    async fn push_across_partition() {
+        time::pause();
+
+        let (
+            UnicastSystem {
+                keys,
+                mut senders,
+                mut receivers,
+            },
+            scenario,
+        ) = UnicastSystem::<u32>::setup_simulated(2, Default::default());
+
+        let mut receiver = receivers.remove(1);
+
+        let _handle = tokio::spawn(async move {
+            loop {
+                let (_, message, acknowledger) = receiver.receive().await;
+                assert_eq!(message, 42);
+                acknowledger.strong();
+            }
+        });
+
+        scenario.partition(vec![0], vec![1]);
+
+        let push = senders
+            .remove(0)
+            .start_push(keys[1], 42, PushSettings::strong_constant());
+
+        time::sleep(Duration::from_secs(1)).await;
+
+        // The push keeps retrying while partitioned ...
+        let progress = push.progress();
+        assert!(progress.attempts >= 2);
+        assert_eq!(progress.acknowledgement, None);
+
+        scenario.heal();
+
+        // ... and succeeds once the partition heals
+        let outcome = push.outcome().await;
+        assert_eq!(outcome.termination, PushTermination::Acknowledged);
+        assert_eq!(outcome.acknowledgement, Some(Acknowledgement::Strong));
+    }
 }
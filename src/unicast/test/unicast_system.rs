@@ -1,6 +1,6 @@
 use crate::{
     crypto::{Identity, KeyChain},
-    net::test::System as NetSystem,
+    net::test::{Scenario, SimulatedSystem, SimulationSettings, System as NetSystem},
     unicast::{Message as UnicastMessage, Receiver, Sender},
 };
 
@@ -52,4 +52,19 @@ where
 
         UnicastSystem::new(keys, senders, receivers)
     }
+
+    /// Builds `Sender`s and `Receiver`s over a `SimulatedSystem` (see `setup_wrapped`).
+    pub fn setup_simulated(
+        peers: usize,
+        settings: SimulationSettings,
+    ) -> (UnicastSystem<Message>, Scenario) {
+        let (keys, senders, receivers, scenario) = SimulatedSystem::setup_wrapped(
+            peers,
+            settings,
+            |connector| Sender::new(connector, Default::default()),
+            |listener| Receiver::new(listener, Default::default()),
+        );
+
+        (UnicastSystem::new(keys, senders, receivers), scenario)
+    }
 }
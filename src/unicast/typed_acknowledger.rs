@@ -0,0 +1,26 @@
+use crate::unicast::{Acknowledger, Rejection};
+
+/// Acknowledges a message received through a `TypedReceiver`. Unlike an
+/// `Acknowledger`, it cannot reply: replies are reserved to the `Router`,
+/// which uses them to tell the remote `TypedSender` why a message was refused.
+pub struct TypedAcknowledger {
+    acknowledger: Acknowledger<Rejection>,
+}
+
+impl TypedAcknowledger {
+    pub(in crate::unicast) fn new(acknowledger: Acknowledger<Rejection>) -> Self {
+        TypedAcknowledger { acknowledger }
+    }
+
+    pub fn weak(self) {
+        self.acknowledger.weak();
+    }
+
+    pub fn expand(self) {
+        self.acknowledger.expand();
+    }
+
+    pub fn strong(self) {
+        self.acknowledger.strong();
+    }
+}
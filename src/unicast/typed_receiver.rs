@@ -0,0 +1,67 @@
+use crate::{
+    crypto::Identity,
+    sync::fuse::Fuse,
+    unicast::{
+        router::Database, Acknowledgement, Acknowledger, Rejection, TaggedMessage,
+        TypedAcknowledger,
+    },
+};
+
+use parking_lot::Mutex;
+
+use std::{marker::PhantomData, sync::Arc};
+
+use tokio::sync::mpsc::Receiver;
+
+type PayloadOutlet = Receiver<(Identity, Vec<u8>, Acknowledger<Rejection>)>;
+
+pub struct TypedReceiver<Message: TaggedMessage> {
+    outlet: PayloadOutlet,
+    database: Arc<Mutex<Database>>,
+    _fuse: Arc<Fuse>,
+    _message: PhantomData<Message>,
+}
+
+impl<Message> TypedReceiver<Message>
+where
+    Message: TaggedMessage,
+{
+    pub(in crate::unicast) fn new(
+        outlet: PayloadOutlet,
+        database: Arc<Mutex<Database>>,
+        fuse: Arc<Fuse>,
+    ) -> Self {
+        TypedReceiver {
+            outlet,
+            database,
+            _fuse: fuse,
+            _message: PhantomData,
+        }
+    }
+
+    pub async fn receive(&mut self) -> (Identity, Message, TypedAcknowledger) {
+        loop {
+            // In order for `self.outlet.recv()` to return `None`, the corresponding
+            // `inlet` would need to be dropped from `self.database.inlets`. This,
+            // however, happens only when `TypedReceiver` is dropped, which cannot
+            // happen while `receive()` is being executed.
+            let (remote, payload, acknowledger) = self.outlet.recv().await.unwrap();
+
+            match bincode::deserialize(payload.as_slice()) {
+                Ok(message) => return (remote, message, TypedAcknowledger::new(acknowledger)),
+                Err(_) => {
+                    acknowledger.reply_with(Acknowledgement::Weak, Rejection::MalformedPayload)
+                }
+            }
+        }
+    }
+}
+
+impl<Message> Drop for TypedReceiver<Message>
+where
+    Message: TaggedMessage,
+{
+    fn drop(&mut self) {
+        self.database.lock().inlets.remove(Message::TAG);
+    }
+}
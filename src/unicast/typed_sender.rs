@@ -0,0 +1,110 @@
+use crate::{
+    crypto::Identity,
+    net::Connector,
+    sync::fuse::{Fuse, Relay},
+    unicast::{Acknowledgement, Envelope, Rejection, Sender, SenderSettings, TaggedMessage},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use tokio::task::JoinHandle;
+
+#[derive(Clone)]
+pub struct TypedSender {
+    sender: Sender<Envelope, Rejection>,
+}
+
+#[derive(Doom)]
+pub enum TypedSenderError {
+    #[doom(description("Remote has no room for messages tagged \"{}\"", tag))]
+    Congested { tag: String },
+    #[doom(description("Remote failed to deserialize message tagged \"{}\"", tag))]
+    MalformedPayload { tag: String },
+    #[doom(description("Failed to `send` message"))]
+    SendFailed,
+    #[doom(description("Failed to serialize: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+    #[doom(description("Tag not registered by remote: \"{}\"", tag))]
+    UnknownTag { tag: String },
+}
+
+impl TypedSender {
+    pub fn new<C>(connector: C, settings: SenderSettings) -> Self
+    where
+        C: Connector,
+    {
+        TypedSender {
+            sender: Sender::new(connector, settings),
+        }
+    }
+
+    pub async fn send<Message>(
+        &self,
+        remote: Identity,
+        message: Message,
+    ) -> Result<Acknowledgement, Top<TypedSenderError>>
+    where
+        Message: TaggedMessage,
+    {
+        let payload = bincode::serialize(&message)
+            .map_err(TypedSenderError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let envelope = Envelope {
+            tag: Message::TAG.to_string(),
+            payload,
+        };
+
+        let (acknowledgement, rejection) = self
+            .sender
+            .send(remote, envelope)
+            .await
+            .pot(TypedSenderError::SendFailed, here!())?;
+
+        match rejection {
+            None => Ok(acknowledgement),
+            Some(Rejection::UnknownTag) => TypedSenderError::UnknownTag {
+                tag: Message::TAG.to_string(),
+            }
+            .fail()
+            .spot(here!()),
+            Some(Rejection::MalformedPayload) => TypedSenderError::MalformedPayload {
+                tag: Message::TAG.to_string(),
+            }
+            .fail()
+            .spot(here!()),
+            Some(Rejection::Congested) => TypedSenderError::Congested {
+                tag: Message::TAG.to_string(),
+            }
+            .fail()
+            .spot(here!()),
+        }
+    }
+
+    pub fn run_send<Message>(
+        &self,
+        remote: Identity,
+        message: Message,
+        relay: Relay,
+    ) -> JoinHandle<Option<Result<Acknowledgement, Top<TypedSenderError>>>>
+    where
+        Message: TaggedMessage,
+    {
+        let sender = self.clone();
+        relay.run(async move { sender.send(remote, message).await })
+    }
+
+    pub fn spawn_send<Message>(
+        &self,
+        remote: Identity,
+        message: Message,
+        fuse: &Fuse,
+    ) -> JoinHandle<Option<Result<Acknowledgement, Top<TypedSenderError>>>>
+    where
+        Message: TaggedMessage,
+    {
+        self.run_send(remote, message, fuse.relay())
+    }
+}